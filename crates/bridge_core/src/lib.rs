@@ -40,7 +40,7 @@ use std::{
     ffi::CStr,
     fmt::{Display, Error as FmtError, Formatter},
     io::{self, Read, SeekFrom, Write},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
     result::Result as StdResult,
     slice,
     sync::Mutex,
@@ -282,6 +282,15 @@ impl<'a> CoreBridgeLauncher<'a> {
         self
     }
 
+    /// Alternatively, report paths relative to *base*, which should be
+    /// absolute. This keeps SyncTeX data usable when absolute paths are
+    /// hidden, and independent of where the sources live. It takes precedence
+    /// over [`Self::with_expose_absolute_paths`].
+    pub fn with_relative_paths(&mut self, base: PathBuf) -> &mut Self {
+        self.filesystem_emulation_settings.relative_path_base = Some(base);
+        self
+    }
+
     /// Ditto for file modification timestamps. In deterministic mode, we return
     /// the configured build time (i.e. `SOURCE_DATE_EPOCH`) instead of the
     /// modification timestamp reported by the IO subsystem.
@@ -846,6 +855,9 @@ struct FsEmulationSettings {
     /// resolve paths to TeX sources), we can disable them for reproducibility.
     expose_absolute_paths: bool,
 
+    /// If set, paths are reported relative to this directory instead.
+    relative_path_base: Option<PathBuf>,
+
     /// Ditto for file modification timestamps. In deterministic mode, we return
     /// the configured build time (i.e. `SOURCE_DATE_EPOCH`) instead of the
     /// modification timestamp reported by the IO subsystem.
@@ -856,6 +868,7 @@ impl Default for FsEmulationSettings {
     fn default() -> Self {
         Self {
             expose_absolute_paths: true,
+            relative_path_base: None,
             mtime_override: None,
         }
     }
@@ -1055,10 +1068,16 @@ pub unsafe extern "C" fn ttbc_get_last_input_abspath(
     buffer: *mut u8,
     len: libc::size_t,
 ) -> libc::ssize_t {
-    if !es.fs_emulation_settings.expose_absolute_paths {
-        return 0;
-    }
-    match es.latest_input_path {
+    let settings = &es.fs_emulation_settings;
+
+    let path = match (&es.latest_input_path, &settings.relative_path_base) {
+        (None, _) => None,
+        (Some(p), Some(base)) => relative_path(p, base),
+        (Some(p), None) if settings.expose_absolute_paths => Some(p.clone()),
+        (Some(_), None) => None,
+    };
+
+    match path {
         None => 0,
 
         Some(ref p) => {
@@ -1083,6 +1102,46 @@ pub unsafe extern "C" fn ttbc_get_last_input_abspath(
     }
 }
 
+/// Express *path* relative to the directory *base*, going up with `..` if
+/// needed. Returns `None` if the two have nothing in common, as with paths on
+/// different Windows drives.
+fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    if path.is_relative() {
+        return Some(path.to_owned());
+    }
+
+    let mut path_iter = path.components().peekable();
+    let mut base_iter = base.components().peekable();
+    let mut n_common = 0;
+
+    while let (Some(a), Some(b)) = (path_iter.peek(), base_iter.peek()) {
+        if a != b {
+            break;
+        }
+
+        path_iter.next();
+        base_iter.next();
+        n_common += 1;
+    }
+
+    if n_common == 0 {
+        return None;
+    }
+
+    let mut rel = PathBuf::new();
+
+    for c in base_iter {
+        match c {
+            Component::Normal(_) => rel.push(".."),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    rel.extend(path_iter);
+    Some(rel)
+}
+
 /// Get the size of a Tectonic input file.
 #[no_mangle]
 pub extern "C" fn ttbc_input_get_size(
@@ -1243,19 +1302,19 @@ pub extern "C" fn ttbc_input_close(
 /// A buffer for diagnostic messages. Rust code does not need to use this type.
 ///
 /// This type has to be public so that it can be exposed in the C/C++ headers,
-/// but it doesn't provide any useful functionality on the Rust side.
+/// but it doesn't provide any useful functionality on the Rust side. It is
+/// used to build up a [`tectonic_status_base::Diagnostic`], which is what gets
+/// reported to the driver.
 #[derive(Clone, Debug)]
 pub struct Diagnostic {
-    message: String,
-    kind: MessageKind,
+    inner: tectonic_status_base::Diagnostic,
 }
 
 /// Create a new diagnostic that will be reported as a warning.
 #[no_mangle]
 pub extern "C" fn ttbc_diag_begin_warning() -> *mut Diagnostic {
     let warning = Box::new(Diagnostic {
-        message: String::new(),
-        kind: MessageKind::Warning,
+        inner: tectonic_status_base::Diagnostic::new(MessageKind::Warning),
     });
     Box::into_raw(warning)
 }
@@ -1264,8 +1323,7 @@ pub extern "C" fn ttbc_diag_begin_warning() -> *mut Diagnostic {
 #[no_mangle]
 pub extern "C" fn ttbc_diag_begin_error() -> *mut Diagnostic {
     let warning = Box::new(Diagnostic {
-        message: String::new(),
-        kind: MessageKind::Error,
+        inner: tectonic_status_base::Diagnostic::new(MessageKind::Error),
     });
     Box::into_raw(warning)
 }
@@ -1278,7 +1336,37 @@ pub extern "C" fn ttbc_diag_begin_error() -> *mut Diagnostic {
 #[no_mangle]
 pub unsafe extern "C" fn ttbc_diag_append(diag: &mut Diagnostic, text: *const libc::c_char) {
     let rtext = CStr::from_ptr(text);
-    diag.message.push_str(&rtext.to_string_lossy());
+    diag.inner.message.push_str(&rtext.to_string_lossy());
+}
+
+/// Set the source location of a diagnostic. A nonpositive line number
+/// indicates that the line is unknown.
+///
+/// # Safety
+///
+/// This function is unsafe because it accepts a raw C string.
+#[no_mangle]
+pub unsafe extern "C" fn ttbc_diag_set_location(
+    diag: &mut Diagnostic,
+    file: *const libc::c_char,
+    line: libc::c_int,
+) {
+    let rfile = CStr::from_ptr(file);
+    diag.inner.file = Some(rfile.to_string_lossy().into_owned());
+    diag.inner.line = u32::try_from(line).ok().filter(|l| *l > 0);
+}
+
+/// Add a line of source context to a diagnostic.
+///
+/// # Safety
+///
+/// This function is unsafe because it accepts a raw C string.
+#[no_mangle]
+pub unsafe extern "C" fn ttbc_diag_add_context(diag: &mut Diagnostic, text: *const libc::c_char) {
+    let rtext = CStr::from_ptr(text);
+    diag.inner
+        .context
+        .push(rtext.to_string_lossy().into_owned());
 }
 
 /// "Finish" a diagnostic: report it to the driver and free the diagnostic object.
@@ -1289,9 +1377,13 @@ pub unsafe extern "C" fn ttbc_diag_append(diag: &mut Diagnostic, text: *const li
 #[no_mangle]
 pub unsafe extern "C" fn ttbc_diag_finish(es: &mut CoreBridgeState, diag: *mut Diagnostic) {
     // By creating the box, we will free the diagnostic when this function exits.
-    let rdiag = Box::from_raw(diag);
-    es.status
-        .report(rdiag.kind, format_args!("{}", rdiag.message), None);
+    let mut rdiag = Box::from_raw(diag);
+
+    // The engines tend to leave trailing newlines on their messages.
+    let len = rdiag.inner.message.trim_end().len();
+    rdiag.inner.message.truncate(len);
+
+    es.status.report_diagnostic(&rdiag.inner);
 }
 
 /// Run a shell command
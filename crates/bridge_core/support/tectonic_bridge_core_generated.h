@@ -333,6 +333,25 @@ ttbc_diagnostic_t *ttbc_diag_begin_error(void);
  */
 void ttbc_diag_append(ttbc_diagnostic_t *diag, const char *text);
 
+/**
+ * Set the source location of a diagnostic. A nonpositive line number
+ * indicates that the line is unknown.
+ *
+ * # Safety
+ *
+ * This function is unsafe because it accepts a raw C string.
+ */
+void ttbc_diag_set_location(ttbc_diagnostic_t *diag, const char *file, int line);
+
+/**
+ * Add a line of source context to a diagnostic.
+ *
+ * # Safety
+ *
+ * This function is unsafe because it accepts a raw C string.
+ */
+void ttbc_diag_add_context(ttbc_diagnostic_t *diag, const char *text);
+
 /**
  * "Finish" a diagnostic: report it to the driver and free the diagnostic object.
  *
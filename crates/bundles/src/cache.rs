@@ -5,15 +5,18 @@
 //!
 //! This module implements Tectonic’s local filesystem caching mechanism for TeX
 //! support files. To make a cachable bundle, wrap any [`CachableBundle`] with a
-//! [`BundleCache`].
+//! [`BundleCache`]. To inspect and clean up the cache directory itself, use
+//! [`CacheDirectory`].
 
-use crate::{Bundle, CachableBundle, FileIndex, FileInfo};
+use crate::{check_file_hash, ttb::TTBFileIndex, Bundle, CachableBundle, FileIndex, FileInfo};
 use std::{
+    collections::{HashMap, HashSet},
     fs::{self, File},
     io::{self, BufReader, Read, Write},
     path::{Path, PathBuf},
     process,
     str::FromStr,
+    time::SystemTime,
 };
 use tectonic_errors::{anyhow::Context, prelude::*};
 use tectonic_io_base::{
@@ -21,7 +24,7 @@ use tectonic_io_base::{
     digest::{self, DigestData},
     InputHandle, InputOrigin, IoProvider, OpenResult,
 };
-use tectonic_status_base::StatusBackend;
+use tectonic_status_base::{tt_warning, StatusBackend};
 
 /// A convenience method to provide a better error message when writing to a created file.
 fn file_create_write<P, F, E>(path: P, write_fn: F) -> Result<()>
@@ -92,6 +95,10 @@ pub struct BundleCache<'this, T> {
 
     // The hash of the bundle we're caching.
     bundle_hash: DigestData,
+
+    /// Cached files that we've already checked against the index during this
+    /// session, so that each one is only hashed once.
+    verified: HashSet<PathBuf>,
 }
 
 impl<'this, T: FileIndex<'this>> BundleCache<'this, T> {
@@ -171,6 +178,7 @@ impl<'this, T: FileIndex<'this>> BundleCache<'this, T> {
             bundle,
             cache_root,
             bundle_hash,
+            verified: HashSet::new(),
         };
 
         // Right now, files are stored in
@@ -185,6 +193,16 @@ impl<'this, T: FileIndex<'this>> BundleCache<'this, T> {
             .cache_root
             .join(format!("data/{}", bundle.bundle_hash)));
 
+        // Record that this bundle's data are in use, so that old data can be
+        // garbage-collected. This is best-effort, since it doesn't matter much
+        // if it fails.
+        let _ = fs::write(
+            bundle
+                .cache_root
+                .join(format!("data/{}.used", bundle.bundle_hash)),
+            b"",
+        );
+
         Ok(bundle)
     }
 
@@ -269,25 +287,23 @@ impl<'this, T: FileIndex<'this>> BundleCache<'this, T> {
     }
 
     /// Get a FileInfo from a name.
-    /// This returns (in_cache, info), where in_cache is true
-    /// if this file is already in our cache and can be retrieved
-    /// without touching the backing bundle.
-    fn get_fileinfo(&mut self, name: &str) -> OpenResult<(bool, T::InfoType)> {
+    fn get_fileinfo(&mut self, name: &str) -> OpenResult<T::InfoType> {
         if let Err(e) = self.ensure_index() {
             return OpenResult::Err(e);
         };
 
-        let info = match self.bundle.search(name) {
-            Some(i) => i,
-            None => return OpenResult::NotAvailable,
-        };
-
-        let target = self.get_file_path(&info);
-        OpenResult::Ok((target.exists(), info))
+        match self.bundle.search(name) {
+            Some(i) => OpenResult::Ok(i),
+            None => OpenResult::NotAvailable,
+        }
     }
 
-    /// Fetch a file from the bundle backing this cache.
-    /// Returns a path to the file that was created.
+    /// Fetch a file from the bundle backing this cache, unless it's already
+    /// cached. Returns a path to the file.
+    ///
+    /// Files are checked against the index hash as they're written into the
+    /// cache, and again the first time they're read back out in each session.
+    /// A cached file that no longer matches is discarded and downloaded anew.
     fn fetch_file(
         &mut self,
         info: T::InfoType,
@@ -301,7 +317,23 @@ impl<'this, T: FileIndex<'this>> BundleCache<'this, T> {
 
         // Already in the cache?
         if target.exists() {
-            return OpenResult::Ok(target);
+            if self.verified.contains(&target) {
+                return OpenResult::Ok(target);
+            }
+
+            match file_digest(&target).and_then(|d| check_file_hash(&info, &d)) {
+                Ok(()) => {
+                    self.verified.insert(target.clone());
+                    return OpenResult::Ok(target);
+                }
+                Err(e) => {
+                    tt_warning!(status, "discarding cached copy of \"{}\"", info.name(); e);
+
+                    if let Err(e) = fs::remove_file(&target) {
+                        return OpenResult::Err(e.into());
+                    }
+                }
+            }
         }
 
         // No, it's not. Are we in cache-only mode?
@@ -309,23 +341,45 @@ impl<'this, T: FileIndex<'this>> BundleCache<'this, T> {
             return OpenResult::NotAvailable;
         }
 
-        // Get the file.
-        let mut handle = match self.bundle.open_fileinfo(&info, status) {
-            OpenResult::Ok(c) => c,
-            OpenResult::Err(e) => return OpenResult::Err(e),
-            OpenResult::NotAvailable => return OpenResult::NotAvailable,
-        };
-
         // Download to a known-unique temporary location, then move.
-        // This prevents issues when running multiple processes.
+        // This prevents issues when running multiple processes. If the
+        // download doesn't match the index (e.g., because it was truncated by
+        // a flaky connection), try once more before giving up, so that bad
+        // data never make it into the cache.
         let tmp_path = self.get_file_path_tmp(&info);
-        if let Err(e) = file_create_write(&tmp_path, |f| io::copy(&mut handle, f).map(|_| ())) {
-            return OpenResult::Err(e);
+        let mut retried = false;
+
+        loop {
+            let mut handle = match self.bundle.open_fileinfo(&info, status) {
+                OpenResult::Ok(c) => c,
+                OpenResult::Err(e) => return OpenResult::Err(e),
+                OpenResult::NotAvailable => return OpenResult::NotAvailable,
+            };
+
+            if let Err(e) = file_create_write(&tmp_path, |f| io::copy(&mut handle, f).map(|_| ())) {
+                return OpenResult::Err(e);
+            }
+
+            match file_digest(&tmp_path).and_then(|d| check_file_hash(&info, &d)) {
+                Ok(()) => break,
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp_path);
+
+                    if retried {
+                        return OpenResult::Err(e);
+                    }
+
+                    tt_warning!(status, "retrying download of \"{}\"", info.name(); e);
+                    retried = true;
+                }
+            }
         }
+
         if let Err(e) = fs::rename(&tmp_path, &target) {
             return OpenResult::Err(e.into());
         };
 
+        self.verified.insert(target.clone());
         OpenResult::Ok(target)
     }
 }
@@ -339,8 +393,7 @@ impl<'this, T: FileIndex<'this>> IoProvider for BundleCache<'this, T> {
         let path = match self.get_fileinfo(name) {
             OpenResult::NotAvailable => return OpenResult::NotAvailable,
             OpenResult::Err(e) => return OpenResult::Err(e),
-            OpenResult::Ok((true, f)) => self.get_file_path(&f),
-            OpenResult::Ok((false, f)) => match self.fetch_file(f, status) {
+            OpenResult::Ok(f) => match self.fetch_file(f, status) {
                 OpenResult::Ok(p) => p,
                 OpenResult::NotAvailable => return OpenResult::NotAvailable,
                 OpenResult::Err(e) => return OpenResult::Err(e),
@@ -368,4 +421,566 @@ impl<'this, T: FileIndex<'this>> Bundle for BundleCache<'this, T> {
     fn all_files(&self) -> Vec<String> {
         self.bundle.all_files()
     }
+
+    fn prefetch(
+        &mut self,
+        filter: &mut dyn FnMut(&str) -> bool,
+        status: &mut dyn StatusBackend,
+    ) -> Result<usize> {
+        // The file list is only available once the index has been loaded.
+        self.ensure_index()?;
+
+        let mut n_matched = 0;
+
+        for path in self.bundle.all_files() {
+            if !filter(&path) {
+                continue;
+            }
+
+            let info = match self.bundle.search(&path) {
+                Some(i) => i,
+                None => bail!(
+                    "bundle file `{}` is listed in its index but can't be found",
+                    path
+                ),
+            };
+
+            match self.fetch_file(info, status) {
+                OpenResult::Ok(_) => n_matched += 1,
+                OpenResult::NotAvailable => {
+                    bail!("bundle file `{}` isn't cached and can't be fetched", path)
+                }
+                OpenResult::Err(e) => {
+                    return Err(e.context(format!("failed to fetch bundle file `{path}`")))
+                }
+            }
+        }
+
+        Ok(n_matched)
+    }
+
+    fn search_section(&mut self, name: &str) -> Result<Vec<String>> {
+        self.ensure_index()?;
+
+        match self.bundle.index().search_section(name) {
+            Some(specs) => Ok(specs.to_vec()),
+            None => bail!("the bundle index has no `[SEARCH:{}]` section", name),
+        }
+    }
+}
+
+/// A summary of the data cached for one bundle.
+#[derive(Clone, Debug)]
+pub struct CachedBundle {
+    /// The digest of the bundle, which names its data directory.
+    pub digest: DigestData,
+
+    /// The (sanitized) locations of the bundles whose current digest is this
+    /// one. If this is empty, the data are unreferenced.
+    pub locations: Vec<String>,
+
+    /// The number of files in the bundle's data directory.
+    pub n_files: usize,
+
+    /// The total size of the bundle's cached data, including its index, in
+    /// bytes.
+    pub size: u64,
+
+    /// The last time that the bundle's data were used, if known.
+    pub last_used: Option<SystemTime>,
+}
+
+/// The results of verifying the data cached for one bundle.
+#[derive(Clone, Debug, Default)]
+pub struct VerifyReport {
+    /// The number of cached files whose contents were checked.
+    pub n_checked: usize,
+
+    /// The number of cached files for which the bundle index records no hash,
+    /// and which therefore could not be checked.
+    pub n_unhashed: usize,
+
+    /// The paths of cached files whose contents do not match their hashes.
+    pub corrupt: Vec<String>,
+}
+
+/// A bundle cache directory, as used by [`BundleCache`].
+///
+/// This type provides inspection and maintenance operations on the cache as a
+/// whole. The cache is laid out as follows:
+///
+/// - `hashes/<location>` records the current digest of the bundle at each
+///   location that has been used.
+/// - `data/<digest>/` holds the cached files of the bundle with that digest.
+/// - `data/<digest>.index` holds a copy of its index.
+/// - `data/<digest>.used` is touched whenever the bundle is opened.
+///
+/// When the bundle at a location changes, its `hashes/` entry is updated but
+/// the old data are left in place, so that they can be garbage-collected with
+/// [`Self::remove_bundle`].
+#[derive(Clone, Debug)]
+pub struct CacheDirectory {
+    root: PathBuf,
+}
+
+impl CacheDirectory {
+    /// Open a cache directory.
+    ///
+    /// If *root* is unspecified, the default location used by
+    /// [`BundleCache::new`] is used.
+    pub fn new(root: Option<PathBuf>) -> Result<Self> {
+        let root = match root {
+            None => app_dirs::get_user_cache_dir("bundles").context("while making cache root")?,
+            Some(p) => p,
+        };
+
+        Ok(CacheDirectory { root })
+    }
+
+    /// Get the root directory of the cache.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Read the `hashes/` directory, returning a map from bundle digests (as
+    /// text) to the names of the entries referencing them.
+    fn read_hashes(&self) -> Result<HashMap<String, Vec<String>>> {
+        let mut refs: HashMap<String, Vec<String>> = HashMap::new();
+        let hash_dir = self.root.join("hashes");
+
+        let entries = match fs::read_dir(&hash_dir) {
+            Ok(e) => e,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(refs),
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            let text = atry!(
+                fs::read_to_string(entry.path());
+                ["couldn't read `{}`", entry.path().display()]
+            );
+
+            // Entries we can't parse don't reference anything.
+            if let Ok(digest) = DigestData::from_str(text.trim()) {
+                refs.entry(digest.to_string())
+                    .or_default()
+                    .push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+
+        for locations in refs.values_mut() {
+            locations.sort();
+        }
+
+        Ok(refs)
+    }
+
+    /// List the bundles with data in the cache, sorted by digest.
+    pub fn bundles(&self) -> Result<Vec<CachedBundle>> {
+        let mut refs = self.read_hashes()?;
+        let data_dir = self.root.join("data");
+        let mut bundles = Vec::new();
+
+        let entries = match fs::read_dir(&data_dir) {
+            Ok(e) => e,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(bundles),
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let digest = match entry.file_name().to_str().map(DigestData::from_str) {
+                Some(Ok(d)) => d,
+                _ => continue,
+            };
+
+            let (n_files, mut size) = dir_usage(&entry.path())?;
+
+            if let Ok(md) = fs::metadata(data_dir.join(format!("{digest}.index"))) {
+                size += md.len();
+            }
+
+            let last_used = fs::metadata(data_dir.join(format!("{digest}.used")))
+                .or_else(|_| entry.metadata())
+                .and_then(|md| md.modified())
+                .ok();
+
+            bundles.push(CachedBundle {
+                digest,
+                locations: refs.remove(&digest.to_string()).unwrap_or_default(),
+                n_files,
+                size,
+                last_used,
+            });
+        }
+
+        bundles.sort_by_key(|b| b.digest.to_string());
+        Ok(bundles)
+    }
+
+    /// Remove all of the data cached for a bundle, along with any `hashes/`
+    /// entries referencing it.
+    pub fn remove_bundle(&self, digest: &DigestData) -> Result<()> {
+        let data_dir = self.root.join("data");
+        let bundle_dir = data_dir.join(digest.to_string());
+
+        atry!(
+            fs::remove_dir_all(&bundle_dir);
+            ["couldn't remove `{}`", bundle_dir.display()]
+        );
+
+        for ext in ["index", "used"] {
+            let p = data_dir.join(format!("{digest}.{ext}"));
+
+            match fs::remove_file(&p) {
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("couldn't remove {p:?}")),
+            }
+        }
+
+        if let Some(locations) = self.read_hashes()?.get(&digest.to_string()) {
+            for loc in locations {
+                let p = self.root.join("hashes").join(loc);
+                atry!(
+                    fs::remove_file(&p);
+                    ["couldn't remove `{}`", p.display()]
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the directory holding the files cached for a bundle. Once all of
+    /// them have been cached, it is laid out like the bundle's content
+    /// directory, as made by `bundle create`.
+    pub fn bundle_dir(&self, digest: &DigestData) -> PathBuf {
+        self.root.join("data").join(digest.to_string())
+    }
+
+    /// List the files of a bundle that haven't been cached yet, according to
+    /// the cached copy of its index.
+    pub fn uncached_files(&self, digest: &DigestData) -> Result<Vec<String>> {
+        let index_path = self.root.join("data").join(format!("{digest}.index"));
+        let bundle_dir = self.bundle_dir(digest);
+        let mut index = TTBFileIndex::default();
+
+        let mut f = atry!(
+            File::open(&index_path);
+            ["no index for bundle `{}` in the cache", digest]
+        );
+        atry!(
+            index.initialize(&mut f);
+            ["couldn't read bundle index `{}`", index_path.display()]
+        );
+
+        Ok(index
+            .iter()
+            .filter(|info| !bundle_dir.join(info.path()).is_file())
+            .map(|info| info.path().to_owned())
+            .collect())
+    }
+
+    /// Check the files cached for a bundle against the hashes recorded in its
+    /// index.
+    ///
+    /// Only bundles in the “TTB” format record file hashes. If *remove_corrupt*
+    /// is true, files that fail the check are deleted, so that they will be
+    /// fetched again the next time that they are needed.
+    pub fn verify_bundle(&self, digest: &DigestData, remove_corrupt: bool) -> Result<VerifyReport> {
+        let data_dir = self.root.join("data");
+        let index_path = data_dir.join(format!("{digest}.index"));
+        let bundle_dir = data_dir.join(digest.to_string());
+        let mut report = VerifyReport::default();
+
+        let mut index = TTBFileIndex::default();
+
+        match File::open(&index_path) {
+            Ok(mut f) => atry!(
+                index.initialize(&mut f);
+                ["couldn't read bundle index `{}`", index_path.display()]
+            ),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e.into()),
+        }
+
+        for info in index.iter() {
+            let path = bundle_dir.join(info.path());
+
+            if !path.is_file() {
+                continue;
+            }
+
+            if info.hash.is_none() {
+                report.n_unhashed += 1;
+                continue;
+            }
+
+            report.n_checked += 1;
+
+            if check_file_hash(info, &file_digest(&path)?).is_err() {
+                report.corrupt.push(info.path().to_owned());
+
+                if remove_corrupt {
+                    atry!(
+                        fs::remove_file(&path);
+                        ["couldn't remove `{}`", path.display()]
+                    );
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Count the files in a directory tree and their total size.
+fn dir_usage(dir: &Path) -> Result<(usize, u64)> {
+    let mut n_files = 0;
+    let mut size = 0;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let ft = entry.file_type()?;
+
+        if ft.is_dir() {
+            let (n, s) = dir_usage(&entry.path())?;
+            n_files += n;
+            size += s;
+        } else {
+            n_files += 1;
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok((n_files, size))
+}
+
+/// Compute the SHA-256 digest of a file's contents.
+fn file_digest(path: &Path) -> Result<DigestData> {
+    let mut f = atry!(
+        File::open(path);
+        ["couldn't open `{}`", path.display()]
+    );
+    let mut dc = digest::create();
+    io::copy(&mut f, &mut dc)?;
+    Ok(DigestData::from(dc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, io::Cursor, rc::Rc};
+    use tectonic_io_base::digest::Digest;
+    use tectonic_status_base::NoopStatusBackend;
+
+    const GOOD_DATA: &[u8] = b"\\relax\n";
+
+    #[derive(Clone, Debug)]
+    struct FakeInfo {
+        path: String,
+        hash: String,
+    }
+
+    impl FileInfo for FakeInfo {
+        fn path(&self) -> &str {
+            &self.path
+        }
+
+        fn name(&self) -> &str {
+            &self.path
+        }
+
+        fn hash(&self) -> Option<&str> {
+            Some(&self.hash)
+        }
+    }
+
+    #[derive(Debug)]
+    struct FakeIndex {
+        files: Vec<FakeInfo>,
+    }
+
+    impl<'this> FileIndex<'this> for FakeIndex {
+        type InfoType = FakeInfo;
+
+        fn iter(&'this self) -> Box<dyn Iterator<Item = &'this FakeInfo> + 'this> {
+            Box::new(self.files.iter())
+        }
+
+        fn len(&self) -> usize {
+            self.files.len()
+        }
+
+        fn initialize(&mut self, _reader: &mut dyn Read) -> Result<()> {
+            Ok(())
+        }
+
+        fn search(&'this mut self, name: &str) -> Option<FakeInfo> {
+            self.files.iter().find(|f| f.path == name).cloned()
+        }
+    }
+
+    /// A bundle with a single file, whose first `n_bad` reads return
+    /// corrupted data.
+    struct FlakyBundle {
+        index: FakeIndex,
+        n_bad: usize,
+        n_opens: Rc<Cell<usize>>,
+    }
+
+    impl FlakyBundle {
+        fn new(n_bad: usize, n_opens: Rc<Cell<usize>>) -> Self {
+            let mut dc = digest::create();
+            dc.update(GOOD_DATA);
+
+            FlakyBundle {
+                index: FakeIndex {
+                    files: vec![FakeInfo {
+                        path: "a.tex".to_owned(),
+                        hash: DigestData::from(dc).to_string(),
+                    }],
+                },
+                n_bad,
+                n_opens,
+            }
+        }
+    }
+
+    impl IoProvider for FlakyBundle {}
+
+    impl Bundle for FlakyBundle {
+        fn get_digest(&mut self) -> Result<DigestData> {
+            Ok(DigestData::zeros())
+        }
+
+        fn all_files(&self) -> Vec<String> {
+            self.index.files.iter().map(|f| f.path.clone()).collect()
+        }
+    }
+
+    impl<'this> CachableBundle<'this, FakeIndex> for FlakyBundle {
+        fn get_index_reader(&mut self) -> Result<Box<dyn Read>> {
+            Ok(Box::new(io::empty()))
+        }
+
+        fn index(&mut self) -> &mut FakeIndex {
+            &mut self.index
+        }
+
+        fn open_fileinfo(
+            &mut self,
+            info: &FakeInfo,
+            _status: &mut dyn StatusBackend,
+        ) -> OpenResult<InputHandle> {
+            let n = self.n_opens.get();
+            self.n_opens.set(n + 1);
+
+            // A truncated download.
+            let data = if n < self.n_bad {
+                &GOOD_DATA[..3]
+            } else {
+                GOOD_DATA
+            };
+
+            OpenResult::Ok(InputHandle::new_read_only(
+                info.name(),
+                Cursor::new(data.to_vec()),
+                InputOrigin::Other,
+            ))
+        }
+
+        fn search(&mut self, name: &str) -> Option<FakeInfo> {
+            self.index.search(name)
+        }
+
+        fn get_location(&mut self) -> String {
+            "flaky".to_owned()
+        }
+    }
+
+    fn cached_path(root: &Path) -> PathBuf {
+        root.join(format!("data/{}/a.tex", DigestData::zeros()))
+    }
+
+    #[test]
+    fn corrupt_download_is_retried() {
+        let root = tempfile::tempdir().unwrap();
+        let n_opens = Rc::new(Cell::new(0));
+        let bundle = FlakyBundle::new(1, n_opens.clone());
+        let mut cache =
+            BundleCache::new(Box::new(bundle), false, Some(root.path().to_owned())).unwrap();
+        let mut status = NoopStatusBackend::default();
+
+        let mut data = Vec::new();
+        let mut ih = cache.input_open_name("a.tex", &mut status).unwrap();
+        ih.read_to_end(&mut data).unwrap();
+        assert_eq!(data, GOOD_DATA);
+        assert_eq!(n_opens.get(), 2);
+        assert_eq!(fs::read(cached_path(root.path())).unwrap(), GOOD_DATA);
+
+        // Now it's cached, so the bundle shouldn't be touched again.
+        cache.input_open_name("a.tex", &mut status).unwrap();
+        assert_eq!(n_opens.get(), 2);
+    }
+
+    #[test]
+    fn corrupt_download_is_not_cached() {
+        let root = tempfile::tempdir().unwrap();
+        let n_opens = Rc::new(Cell::new(0));
+        let bundle = FlakyBundle::new(usize::MAX, n_opens.clone());
+        let mut cache =
+            BundleCache::new(Box::new(bundle), false, Some(root.path().to_owned())).unwrap();
+        let mut status = NoopStatusBackend::default();
+
+        let e = match cache.input_open_name("a.tex", &mut status) {
+            OpenResult::Err(e) => e,
+            _ => panic!("expected an error"),
+        };
+        assert!(e.to_string().contains("is corrupt"));
+        assert_eq!(n_opens.get(), 2);
+
+        // Neither the file nor its temporary download should be left behind.
+        let dir = cached_path(root.path()).parent().unwrap().to_owned();
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn corrupt_cached_file_is_refetched() {
+        let root = tempfile::tempdir().unwrap();
+        let n_opens = Rc::new(Cell::new(0));
+        let mut status = NoopStatusBackend::default();
+
+        let bundle = FlakyBundle::new(0, n_opens.clone());
+        let mut cache =
+            BundleCache::new(Box::new(bundle), false, Some(root.path().to_owned())).unwrap();
+        cache.input_open_name("a.tex", &mut status).unwrap();
+        assert_eq!(n_opens.get(), 1);
+
+        // Damage the cached copy behind the cache's back, then start a new
+        // session.
+        fs::write(cached_path(root.path()), &GOOD_DATA[..3]).unwrap();
+
+        let bundle = FlakyBundle::new(0, n_opens.clone());
+        let mut cache =
+            BundleCache::new(Box::new(bundle), false, Some(root.path().to_owned())).unwrap();
+
+        let mut data = Vec::new();
+        let mut ih = cache.input_open_name("a.tex", &mut status).unwrap();
+        ih.read_to_end(&mut data).unwrap();
+        assert_eq!(data, GOOD_DATA);
+        assert_eq!(n_opens.get(), 2);
+        assert_eq!(fs::read(cached_path(root.path())).unwrap(), GOOD_DATA);
+
+        // Once verified, the file is served without going back to the bundle.
+        cache.input_open_name("a.tex", &mut status).unwrap();
+        assert_eq!(n_opens.get(), 2);
+    }
 }
@@ -4,6 +4,7 @@
 //! A module for the directory bundle [`DirBundle`].
 
 use std::{
+    collections::HashSet,
     fs,
     io::Read,
     path::{Path, PathBuf},
@@ -11,9 +12,12 @@ use std::{
 };
 use tectonic_errors::prelude::*;
 use tectonic_io_base::{digest, filesystem::FilesystemIo, InputHandle, IoProvider, OpenResult};
-use tectonic_status_base::{NoopStatusBackend, StatusBackend};
+use tectonic_status_base::{tt_warning, NoopStatusBackend, StatusBackend};
 
-use super::Bundle;
+use super::{
+    search::{SearchIndex, SEARCH_NAME},
+    Bundle,
+};
 
 /// A "bundle" of a bunch of files in a directory.
 ///
@@ -22,21 +26,181 @@ use super::Bundle;
 /// read-only, self-contained, and implements the [`Bundle`] trait. The
 /// directory should contain a file named `SHA256SUM` if the bundle fingerprint
 /// will be needed.
-pub struct DirBundle(FilesystemIo);
+///
+/// If the directory contains a file named `SEARCH`, files are looked up in the
+/// subdirectories that it lists, in order, just as in a TTB bundle. A
+/// subdirectory ending in `//` is searched recursively. So the `content`
+/// directory of a bundle made by `tectonic -X bundle create` behaves the same
+/// as the packed bundle. Otherwise, names are resolved relative to the
+/// directory itself.
+pub struct DirBundle {
+    fs: FilesystemIo,
+
+    /// The search order, if files aren't just resolved relative to the root.
+    search: Option<String>,
+
+    /// The index of the files in the tree, built when it's first needed.
+    index: Option<SearchIndex>,
+
+    /// Whether this bundle is a `//` search path. If so, names are looked up
+    /// relative to the root first, so that the tree is only indexed if that
+    /// fails, and the index is limited in size.
+    search_path: bool,
+}
+
+/// The deepest that the index of a `//` search path goes below its root.
+const MAX_SEARCH_PATH_DEPTH: usize = 32;
+
+/// The most files that the index of a `//` search path includes.
+const MAX_SEARCH_PATH_FILES: usize = 100_000;
 
 impl DirBundle {
     /// Create a new directory bundle.
     ///
     /// No validation of the input path is performed, which is why this function
-    /// is infallible.
+    /// is infallible. If the directory contains a `SEARCH` file, it is read
+    /// right away, and the directory tree is indexed when the first file is
+    /// looked up.
     pub fn new<P: AsRef<Path>>(dir: P) -> DirBundle {
-        DirBundle(FilesystemIo::new(
-            dir.as_ref(),
-            false,              // no writes
-            false,              // no absolute paths
-            Default::default(), // no hidden files
-        ))
+        let dir = dir.as_ref();
+        let search = fs::read_to_string(dir.join(SEARCH_NAME)).ok();
+        Self::new_with_search(dir, search, false, Default::default())
+    }
+
+    /// Create a directory bundle for an extra search path, as given to the
+    /// `-Z search-path` option.
+    ///
+    /// As in `TEXINPUTS`, a path ending in `//` is searched recursively. Its
+    /// tree is only indexed once a file isn't found directly under it, and
+    /// the index stops at 32 levels deep or 100,000 files. Otherwise, the
+    /// directory's `SEARCH` file is honored if it has one, as with
+    /// [`DirBundle::new`]. Files in *hidden_input_paths* are never found.
+    pub fn new_for_search_path<P: AsRef<Path>>(
+        path: P,
+        hidden_input_paths: HashSet<PathBuf>,
+    ) -> DirBundle {
+        let path = path.as_ref();
+        let text = path.to_string_lossy();
+
+        if let Some(dir) = text.strip_suffix("//") {
+            // Don't turn `//` into the empty path.
+            let dir = if dir.is_empty() { "/" } else { dir };
+            Self::new_with_search(
+                Path::new(dir),
+                Some("//".to_owned()),
+                true,
+                hidden_input_paths,
+            )
+        } else {
+            let search = fs::read_to_string(path.join(SEARCH_NAME)).ok();
+            Self::new_with_search(path, search, false, hidden_input_paths)
+        }
+    }
+
+    fn new_with_search(
+        dir: &Path,
+        search: Option<String>,
+        search_path: bool,
+        hidden_input_paths: HashSet<PathBuf>,
+    ) -> DirBundle {
+        let fs = FilesystemIo::new(
+            dir,
+            false, // no writes
+            false, // no absolute paths
+            hidden_input_paths,
+        );
+
+        DirBundle {
+            fs,
+            search,
+            index: None,
+            search_path,
+        }
+    }
+
+    /// Get the index of the tree, building it if need be. Returns `None` if
+    /// there's no search order.
+    fn index(&mut self, status: &mut dyn StatusBackend) -> Option<&SearchIndex> {
+        let search = self.search.as_deref()?;
+
+        if self.index.is_none() {
+            let root = self.fs.root();
+
+            let files = if self.search_path {
+                let (files, complete) =
+                    list_files_limited(root, MAX_SEARCH_PATH_DEPTH, MAX_SEARCH_PATH_FILES);
+
+                if !complete {
+                    tt_warning!(
+                        status,
+                        "search path `{}//` has more than {} files or is nested more than {} \
+                         levels deep; only some of them will be found",
+                        root.display(),
+                        MAX_SEARCH_PATH_FILES,
+                        MAX_SEARCH_PATH_DEPTH
+                    );
+                }
+
+                files
+            } else {
+                list_files(root)
+            };
+
+            self.index = Some(SearchIndex::new(search, files));
+        }
+
+        self.index.as_ref()
+    }
+}
+
+/// List the paths of all of the files in the tree rooted at *root*, relative
+/// to it and with `/` as the separator. Symbolic links to files are included,
+/// but links to directories aren't followed, to avoid cycles.
+fn list_files(root: &Path) -> Vec<String> {
+    list_files_limited(root, usize::MAX, usize::MAX).0
+}
+
+/// Like [`list_files`], but going at most *max_depth* directories below
+/// *root* and stopping after *max_files* files. Also returns whether the
+/// listing is complete.
+fn list_files_limited(root: &Path, max_depth: usize, max_files: usize) -> (Vec<String>, bool) {
+    let mut files = Vec::new();
+    let mut dirs = vec![(String::new(), 0)];
+    let mut complete = true;
+
+    while let Some((rel, depth)) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(root.join(&rel)) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            if files.len() >= max_files {
+                complete = false;
+                break;
+            }
+
+            let Some(name) = entry.file_name().to_str().map(|n| n.to_owned()) else {
+                continue;
+            };
+
+            let path = if rel.is_empty() {
+                name
+            } else {
+                format!("{rel}/{name}")
+            };
+
+            match entry.file_type() {
+                Ok(t) if t.is_dir() && depth < max_depth => dirs.push((path, depth + 1)),
+                Ok(t) if t.is_dir() => complete = false,
+                Ok(t) if t.is_file() => files.push(path),
+                Ok(t) if t.is_symlink() && entry.path().is_file() => files.push(path),
+                _ => {}
+            }
+        }
     }
+
+    files.sort();
+    (files, complete)
 }
 
 impl IoProvider for DirBundle {
@@ -45,7 +209,11 @@ impl IoProvider for DirBundle {
         name: &str,
         status: &mut dyn StatusBackend,
     ) -> OpenResult<InputHandle> {
-        self.0.input_open_name(name, status)
+        match self.input_open_name_with_abspath(name, status) {
+            OpenResult::Ok((h, _path)) => OpenResult::Ok(h),
+            OpenResult::Err(e) => OpenResult::Err(e),
+            OpenResult::NotAvailable => OpenResult::NotAvailable,
+        }
     }
 
     fn input_open_name_with_abspath(
@@ -53,19 +221,51 @@ impl IoProvider for DirBundle {
         name: &str,
         status: &mut dyn StatusBackend,
     ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
-        self.0.input_open_name_with_abspath(name, status)
+        if self.search.is_none() {
+            return self.fs.input_open_name_with_abspath(name, status);
+        }
+
+        // Files right under a search path are found without indexing it.
+        if self.search_path {
+            match self.fs.input_open_name_with_abspath(name, status) {
+                OpenResult::NotAvailable => {}
+                r => return r,
+            }
+        }
+
+        let Some(index) = self.index(status) else {
+            return OpenResult::NotAvailable;
+        };
+
+        match index.search(name) {
+            Some(path) if path == name => self.fs.input_open_name_with_abspath(name, status),
+
+            // The handle must carry the name that was asked for, so that the
+            // I/O layer can track it.
+            Some(path) => {
+                let path = path.to_owned();
+
+                match self.fs.input_open_name_with_abspath(&path, status) {
+                    OpenResult::Ok((h, abspath)) => {
+                        let origin = h.origin();
+                        OpenResult::Ok((InputHandle::new(name, h, origin), abspath))
+                    }
+                    r => r,
+                }
+            }
+
+            // Paths that don't name a file in the tree, such as `./foo.tex`,
+            // are still resolved relative to the root.
+            None if name.contains('/') => self.fs.input_open_name_with_abspath(name, status),
+
+            None => OpenResult::NotAvailable,
+        }
     }
 }
 
 impl Bundle for DirBundle {
     fn all_files(&self) -> Vec<String> {
-        fs::read_dir(self.0.root())
-            .unwrap()
-            .filter_map(|x| x.ok())
-            .filter(|x| !x.file_type().map(|x| x.is_dir()).unwrap_or(false))
-            .map(|x| x.file_name().to_str().unwrap_or("").to_owned())
-            .filter(|x| !x.is_empty())
-            .collect()
+        list_files(self.fs.root())
     }
 
     fn get_digest(&mut self) -> Result<tectonic_io_base::digest::DigestData> {
@@ -89,3 +289,50 @@ impl Bundle for DirBundle {
         Ok(atry!(digest::DigestData::from_str(&digest_text); ["corrupted SHA256 digest data"]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("a/b/c")).unwrap();
+        fs::write(root.path().join("top.tex"), "").unwrap();
+        fs::write(root.path().join("a/b/c/deep.tex"), "").unwrap();
+        root
+    }
+
+    #[test]
+    fn limited_listing() {
+        let root = tree();
+
+        assert_eq!(
+            list_files_limited(root.path(), usize::MAX, usize::MAX),
+            (
+                vec!["a/b/c/deep.tex".to_owned(), "top.tex".to_owned()],
+                true
+            )
+        );
+        assert_eq!(
+            list_files_limited(root.path(), 2, usize::MAX),
+            (vec!["top.tex".to_owned()], false)
+        );
+        assert_eq!(list_files_limited(root.path(), usize::MAX, 1).0.len(), 1);
+        assert!(!list_files_limited(root.path(), usize::MAX, 1).1);
+    }
+
+    #[test]
+    fn search_path_indexed_lazily() {
+        let root = tree();
+        let mut path = root.path().as_os_str().to_owned();
+        path.push("//");
+        let mut bundle = DirBundle::new_for_search_path(path, HashSet::new());
+        let mut status = NoopStatusBackend::default();
+
+        bundle.input_open_name("top.tex", &mut status).unwrap();
+        assert!(bundle.index.is_none());
+
+        bundle.input_open_name("deep.tex", &mut status).unwrap();
+        assert!(bundle.index.is_some());
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! A bundle that stacks several other bundles.
+//!
+//! The main type offered by this module is the [`LayeredBundle`] struct. It
+//! makes it possible to add a handful of files to a standard bundle, or to
+//! override some of its files, without having to create a whole new bundle.
+
+use std::{collections::HashSet, io::Read, path::PathBuf};
+use tectonic_errors::prelude::*;
+use tectonic_io_base::{
+    digest::{self, Digest, DigestData},
+    InputFeatures, InputHandle, IoProvider, OpenResult,
+};
+use tectonic_status_base::{NoopStatusBackend, StatusBackend};
+
+use crate::Bundle;
+
+/// A bundle composed of several other bundles, in priority order.
+///
+/// When a file is requested, each layer is searched in turn, and the first
+/// one that provides the file wins. The usual arrangement is to put one or
+/// more small local bundles, such as [`crate::dir::DirBundle`]s, on top of a
+/// standard network bundle.
+pub struct LayeredBundle {
+    layers: Vec<Box<dyn Bundle>>,
+
+    /// The digest, once computed. It can be expensive to get.
+    digest: Option<DigestData>,
+}
+
+impl LayeredBundle {
+    /// Create a new layered bundle. The first layer has the highest priority.
+    pub fn new(layers: Vec<Box<dyn Bundle>>) -> Self {
+        LayeredBundle {
+            layers,
+            digest: None,
+        }
+    }
+}
+
+impl IoProvider for LayeredBundle {
+    fn input_open_name(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<InputHandle> {
+        for layer in &mut self.layers {
+            match layer.input_open_name(name, status) {
+                OpenResult::NotAvailable => continue,
+                r => return r,
+            }
+        }
+
+        OpenResult::NotAvailable
+    }
+
+    fn input_open_name_with_abspath(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        for layer in &mut self.layers {
+            match layer.input_open_name_with_abspath(name, status) {
+                OpenResult::NotAvailable => continue,
+                r => return r,
+            }
+        }
+
+        OpenResult::NotAvailable
+    }
+}
+
+impl Bundle for LayeredBundle {
+    /// The digest of a layered bundle combines the digests of its layers, so
+    /// that it changes if any of them do. Local bundles often don't record a
+    /// digest, so for a layer without one, a digest of the names, sizes and
+    /// modification times of all of its files is used instead.
+    ///
+    /// Files without a modification time, such as those in a ZIP bundle, have
+    /// to be read in full instead. The result is computed only once for each
+    /// `LayeredBundle`, which is fine since bundles are read-only.
+    fn get_digest(&mut self) -> Result<DigestData> {
+        if let Some(d) = self.digest {
+            return Ok(d);
+        }
+
+        let mut dc = digest::create();
+
+        for layer in &mut self.layers {
+            let d = match layer.get_digest() {
+                Ok(d) => d,
+                Err(e) => match content_digest(layer.as_mut())? {
+                    Some(d) => d,
+                    None => return Err(e),
+                },
+            };
+
+            dc.update(d.to_string().as_bytes());
+        }
+
+        let d = DigestData::from(dc);
+        self.digest = Some(d);
+        Ok(d)
+    }
+
+    fn all_files(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+
+        for layer in &self.layers {
+            for f in layer.all_files() {
+                if seen.insert(f.clone()) {
+                    files.push(f);
+                }
+            }
+        }
+
+        files
+    }
+
+    /// Prefetch files for every layer that supports it. This fails only if
+    /// none of them do.
+    fn prefetch(
+        &mut self,
+        filter: &mut dyn FnMut(&str) -> bool,
+        status: &mut dyn StatusBackend,
+    ) -> Result<usize> {
+        let mut total = None;
+        let mut first_err = None;
+
+        for layer in &mut self.layers {
+            match layer.prefetch(filter, status) {
+                Ok(n) => *total.get_or_insert(0) += n,
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+
+        match (total, first_err) {
+            (Some(n), _) => Ok(n),
+            (None, Some(e)) => Err(e),
+            (None, None) => bail!("a layered bundle must have at least one layer"),
+        }
+    }
+
+    /// Use the search section of the first layer that has one with this name.
+    fn search_section(&mut self, name: &str) -> Result<Vec<String>> {
+        let mut first_err = None;
+
+        for layer in &mut self.layers {
+            match layer.search_section(name) {
+                Ok(specs) => return Ok(specs),
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_err {
+            Some(e) => Err(e),
+            None => bail!("a layered bundle must have at least one layer"),
+        }
+    }
+}
+
+/// Compute a digest that changes whenever any file in a bundle does: from
+/// the name, size and modification time of each file, or from its contents if
+/// it has no modification time. Returns `None` if the bundle doesn't list any
+/// files.
+fn content_digest(bundle: &mut dyn Bundle) -> Result<Option<DigestData>> {
+    let mut names = bundle.all_files();
+
+    if names.is_empty() {
+        return Ok(None);
+    }
+
+    names.sort();
+    let mut dc = digest::create();
+    let mut status = NoopStatusBackend::default();
+
+    for name in &names {
+        let mut h = match bundle.input_open_name(name, &mut status) {
+            OpenResult::Ok(h) => h,
+            OpenResult::NotAvailable => bail!("bundle file `{}` disappeared", name),
+            OpenResult::Err(e) => return Err(e),
+        };
+
+        dc.update(name.as_bytes());
+        dc.update([0]);
+
+        if let Some(mtime) = h.get_unix_mtime()? {
+            dc.update([0]);
+            dc.update((h.get_size()? as u64).to_le_bytes());
+            dc.update(mtime.to_le_bytes());
+        } else {
+            let mut data = Vec::new();
+            h.read_to_end(&mut data)?;
+            dc.update([1]);
+            dc.update((data.len() as u64).to_le_bytes());
+            dc.update(&data);
+        }
+    }
+
+    Ok(Some(DigestData::from(dc)))
+}
@@ -11,11 +11,12 @@
 //!
 //! - [`cache::BundleCache`] provides filesystem-backed caching for any bundle
 //! - [`itar::ItarBundle`] provides filesystem-backed caching for any bundle
+//! - [`layered::LayeredBundle`] stacks several bundles in priority order
 //! - [`dir::DirBundle`] turns a directory full of files into a bundle; it is
 //!   useful for testing and lightweight usage.
 //! - [`zip::ZipBundle`] for a ZIP-format bundle.
 
-use std::{fmt::Debug, io::Read, path::PathBuf};
+use std::{cell::RefCell, fmt::Debug, io::Read, path::PathBuf, rc::Rc};
 use tectonic_errors::{prelude::bail, Result};
 use tectonic_io_base::{digest::DigestData, InputHandle, IoProvider, OpenResult};
 use tectonic_status_base::StatusBackend;
@@ -23,6 +24,8 @@ use tectonic_status_base::StatusBackend;
 pub mod cache;
 pub mod dir;
 pub mod itar;
+pub mod layered;
+mod search;
 mod ttb;
 pub mod ttb_fs;
 pub mod ttb_net;
@@ -51,6 +54,29 @@ pub trait FileInfo: Clone + Debug {
 
     /// Return the name of this file
     fn name(&self) -> &str;
+
+    /// Return the hex-encoded SHA-256 hash of this file's contents, if the
+    /// bundle index records one.
+    fn hash(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Check the digest of a file read from a bundle against the hash recorded
+/// in the bundle's index, if there is one.
+pub(crate) fn check_file_hash<I: FileInfo>(info: &I, actual: &DigestData) -> Result<()> {
+    if let Some(expected) = info.hash() {
+        if !actual.to_string().eq_ignore_ascii_case(expected) {
+            bail!(
+                "bundle file `{}` is corrupt: its SHA-256 digest is {} but the bundle index says it should be {}",
+                info.path(),
+                actual,
+                expected
+            );
+        }
+    }
+
+    Ok(())
 }
 
 /// Keeps track of
@@ -86,6 +112,12 @@ where
     ///
     /// Returns a `Some(FileInfo)` if a file was found, and `None` otherwise.
     fn search(&'this mut self, name: &str) -> Option<Self::InfoType>;
+
+    /// Get the search-path specifications listed in the search section of
+    /// this index with the given name, if there is one.
+    fn search_section(&self, _name: &str) -> Option<&[String]> {
+        None
+    }
 }
 
 /// A trait for bundles of Tectonic support files.
@@ -107,6 +139,31 @@ pub trait Bundle: IoProvider {
     /// Iterate over all file paths in this bundle.
     /// This is used for the `bundle search` command
     fn all_files(&self) -> Vec<String>;
+
+    /// Make sure that every file whose path satisfies *filter* is available
+    /// locally, fetching it from the backend if need be. Returns the number of
+    /// files that matched.
+    ///
+    /// This is used for the `bundle fetch` command. Only bundles that cache
+    /// remote files can be prefetched; the default implementation returns an
+    /// error.
+    fn prefetch(
+        &mut self,
+        _filter: &mut dyn FnMut(&str) -> bool,
+        _status: &mut dyn StatusBackend,
+    ) -> Result<usize> {
+        bail!("only network bundles can be prefetched")
+    }
+
+    /// Get the search-path specifications listed in the `[SEARCH:<name>]`
+    /// section of this bundle's index.
+    ///
+    /// This is used for the `bundle fetch` command. Only bundles in the TTB
+    /// format have such sections, and only cached ones expose them; the
+    /// default implementation returns an error.
+    fn search_section(&mut self, name: &str) -> Result<Vec<String>> {
+        bail!("this bundle has no search section named `{name}`")
+    }
 }
 
 impl<B: Bundle + ?Sized> Bundle for Box<B> {
@@ -117,6 +174,43 @@ impl<B: Bundle + ?Sized> Bundle for Box<B> {
     fn all_files(&self) -> Vec<String> {
         (**self).all_files()
     }
+
+    fn prefetch(
+        &mut self,
+        filter: &mut dyn FnMut(&str) -> bool,
+        status: &mut dyn StatusBackend,
+    ) -> Result<usize> {
+        (**self).prefetch(filter, status)
+    }
+
+    fn search_section(&mut self, name: &str) -> Result<Vec<String>> {
+        (**self).search_section(name)
+    }
+}
+
+/// A shared bundle may be used by several owners, one at a time. This allows
+/// a long-running process to open a bundle once and use it for many
+/// processing sessions.
+impl<B: Bundle + ?Sized> Bundle for Rc<RefCell<B>> {
+    fn get_digest(&mut self) -> Result<DigestData> {
+        self.borrow_mut().get_digest()
+    }
+
+    fn all_files(&self) -> Vec<String> {
+        self.borrow().all_files()
+    }
+
+    fn prefetch(
+        &mut self,
+        filter: &mut dyn FnMut(&str) -> bool,
+        status: &mut dyn StatusBackend,
+    ) -> Result<usize> {
+        self.borrow_mut().prefetch(filter, status)
+    }
+
+    fn search_section(&mut self, name: &str) -> Result<Vec<String>> {
+        self.borrow_mut().search_section(name)
+    }
 }
 
 /// A bundle that may be cached.
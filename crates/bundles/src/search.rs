@@ -0,0 +1,218 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! Resolving file names with a bundle's search order.
+//!
+//! Bundles built by `tectonic -X bundle create` keep their files in a
+//! directory tree and come with a `SEARCH` file listing the directories to
+//! search, in order. A directory ending in `//` is searched along with all of
+//! its subdirectories. This module implements that lookup for bundle formats
+//! that don't have an index of their own.
+
+use std::collections::HashMap;
+
+/// The name of the file listing a bundle's search order.
+pub(crate) const SEARCH_NAME: &str = "SEARCH";
+
+/// The files of a bundle, indexed for lookups according to a search order.
+pub(crate) struct SearchIndex {
+    order: Vec<String>,
+
+    /// Full paths of the bundle's files, keyed by their final components.
+    by_name: HashMap<String, Vec<String>>,
+}
+
+impl SearchIndex {
+    /// Create an index from the text of a `SEARCH` file and the paths of all
+    /// of the files in the bundle.
+    pub(crate) fn new<I: IntoIterator<Item = String>>(search: &str, paths: I) -> Self {
+        let order = search
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| l.to_owned())
+            .collect();
+
+        let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+
+        for path in paths {
+            // Directory entries have an empty final component.
+            let name = path.rsplit('/').next().unwrap_or("");
+
+            if !name.is_empty() {
+                by_name.entry(name.to_owned()).or_default().push(path);
+            }
+        }
+
+        for paths in by_name.values_mut() {
+            paths.sort();
+        }
+
+        SearchIndex { order, by_name }
+    }
+
+    /// Find the path of the file that a request for *name* should open.
+    ///
+    /// A name containing a slash refers to a file by (the end of) its path,
+    /// as some packages do; search directories don't apply. Otherwise, the
+    /// search directories are tried in order. If a directory contains
+    /// several matching files at different depths, the first one in
+    /// alphabetical order of their paths wins. Files at the top level of the
+    /// bundle are found even if the search order doesn't include it.
+    pub(crate) fn search(&self, name: &str) -> Option<&str> {
+        let (parent, base) = match name.rsplit_once('/') {
+            Some((p, b)) => (Some(p), b),
+            None => (None, name),
+        };
+
+        let candidates = self.by_name.get(base)?;
+
+        if parent.is_some() {
+            let name = name.trim_start_matches('/');
+
+            return candidates
+                .iter()
+                .find(|p| *p == name)
+                .or_else(|| {
+                    candidates
+                        .iter()
+                        .find(|p| p.strip_suffix(name).is_some_and(|s| s.ends_with('/')))
+                })
+                .map(|p| p.as_str());
+        }
+
+        for rule in &self.order {
+            let (dir, recursive) = match rule.strip_suffix("//") {
+                Some(d) => (d, true),
+                None => (rule.as_str(), false),
+            };
+            let dir = dir.trim_matches('/');
+
+            let found = candidates.iter().find(|p| {
+                let p_parent = parent_of(p);
+
+                if recursive {
+                    dir.is_empty()
+                        || p_parent == dir
+                        || p_parent
+                            .strip_prefix(dir)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                } else {
+                    p_parent == dir
+                }
+            });
+
+            if let Some(p) = found {
+                return Some(p);
+            }
+        }
+
+        // Files at the top level, such as the bundle metadata, can always be
+        // opened by name.
+        candidates.iter().find(|p| *p == name).map(|p| p.as_str())
+    }
+}
+
+/// The directory part of a bundle path, without a trailing slash.
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(p, _)| p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATHS: &[&str] = &[
+        "SEARCH",
+        "fonts/tfm/public/cm/cmr10.tfm",
+        "tex/",
+        "tex/latex/a.sty",
+        "tex/latex/base/a.sty",
+        "tex/latex/base/b.sty",
+        "tex/latex/tools/b.sty",
+        "tex/latexmk/c.tex",
+        "tex/plain/d.tex",
+    ];
+
+    fn index(search: &str) -> SearchIndex {
+        SearchIndex::new(search, PATHS.iter().map(|p| p.to_string()))
+    }
+
+    #[test]
+    fn recursive() {
+        let idx = index("/tex/latex//\n/fonts//\n");
+        assert_eq!(idx.search("b.sty"), Some("tex/latex/base/b.sty"));
+        assert_eq!(
+            idx.search("cmr10.tfm"),
+            Some("fonts/tfm/public/cm/cmr10.tfm")
+        );
+
+        // `latexmk` isn't a subdirectory of `latex`.
+        assert_eq!(idx.search("c.tex"), None);
+        assert_eq!(idx.search("d.tex"), None);
+    }
+
+    #[test]
+    fn non_recursive() {
+        let idx = index("/tex/latex/\n/tex/plain\n");
+        assert_eq!(idx.search("a.sty"), Some("tex/latex/a.sty"));
+        assert_eq!(idx.search("d.tex"), Some("tex/plain/d.tex"));
+        assert_eq!(idx.search("b.sty"), None);
+        assert_eq!(idx.search("cmr10.tfm"), None);
+    }
+
+    #[test]
+    fn root() {
+        // Top-level files are found whether or not the root is searched.
+        assert_eq!(index("/\n").search("SEARCH"), Some("SEARCH"));
+        assert_eq!(index("/tex/plain/\n").search("SEARCH"), Some("SEARCH"));
+        assert_eq!(index("/\n").search("d.tex"), None);
+
+        let idx = index("//\n");
+        assert_eq!(idx.search("d.tex"), Some("tex/plain/d.tex"));
+        assert_eq!(
+            idx.search("cmr10.tfm"),
+            Some("fonts/tfm/public/cm/cmr10.tfm")
+        );
+
+        // Directory entries are never found.
+        assert_eq!(idx.search("tex"), None);
+        assert_eq!(idx.search(""), None);
+    }
+
+    #[test]
+    fn priority() {
+        // The first directory in the search order that has the file wins.
+        assert_eq!(
+            index("/tex/latex/\n/tex/latex//\n").search("a.sty"),
+            Some("tex/latex/a.sty")
+        );
+        assert_eq!(
+            index("/tex/latex/base/\n/tex/latex/\n").search("a.sty"),
+            Some("tex/latex/base/a.sty")
+        );
+
+        // Within a recursive directory, the first path alphabetically wins.
+        assert_eq!(
+            index("/tex/latex//\n").search("a.sty"),
+            Some("tex/latex/a.sty")
+        );
+        assert_eq!(
+            index("/tex/latex/tools/\n/tex/latex//\n").search("b.sty"),
+            Some("tex/latex/tools/b.sty")
+        );
+    }
+
+    #[test]
+    fn parent_directories() {
+        // Names with slashes match the ends of paths, regardless of the search
+        // order, but only at directory boundaries.
+        let idx = index("/tex/plain/\n");
+        assert_eq!(idx.search("base/b.sty"), Some("tex/latex/base/b.sty"));
+        assert_eq!(idx.search("tools/b.sty"), Some("tex/latex/tools/b.sty"));
+        assert_eq!(idx.search("latex/a.sty"), Some("tex/latex/a.sty"));
+        assert_eq!(idx.search("/tex/latex/a.sty"), Some("tex/latex/a.sty"));
+        assert_eq!(idx.search("ase/b.sty"), None);
+        assert_eq!(idx.search("other/b.sty"), None);
+    }
+}
@@ -68,6 +68,10 @@ impl FileInfo for TTBFileInfo {
     fn path(&self) -> &str {
         &self.path
     }
+
+    fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
 }
 
 #[derive(Default, Debug)]
@@ -176,6 +180,10 @@ impl<'this> FileIndex<'this> for TTBFileIndex {
         Ok(())
     }
 
+    fn search_section(&self, name: &str) -> Option<&[String]> {
+        self.search_orders.get(name).map(|s| s.as_slice())
+    }
+
     fn search(&'this mut self, name: &str) -> Option<TTBFileInfo> {
         match self.search_cache.get(name) {
             None => {}
@@ -189,6 +197,17 @@ impl<'this> FileIndex<'this> for TTBFileIndex {
             return None;
         }
 
+        // A full path within the bundle always identifies its file. Besides
+        // inputs that give one, this matters to `bundle convert`, which
+        // unpacks each file by its path.
+        if name.contains('/') {
+            if let Some(info) = self.content.iter().find(|i| i.path == name) {
+                let matching = Some(info.clone());
+                self.search_cache.insert(name.to_owned(), matching.clone());
+                return matching;
+            }
+        }
+
         // Get last element of path, since
         // some packages reference a path to a file.
         // `fithesis4` is one example.
@@ -286,3 +305,36 @@ impl<'this> FileIndex<'this> for TTBFileIndex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = "\
+[DEFAULTSEARCH]
+MAIN
+[SEARCH:MAIN]
+/tex//
+[FILELIST]
+0 1 1 nohash tex/latex/a.sty
+0 1 1 nohash tex/latex/b.sty
+0 1 1 nohash tex/latex/base/a.sty
+0 1 1 nohash xtex/latex/base/a.sty
+";
+
+    #[test]
+    fn full_paths() {
+        let mut index = TTBFileIndex::default();
+        index.initialize(&mut INDEX.as_bytes()).unwrap();
+
+        // Each file can be found by its path, even if other files share its
+        // name or end with its path.
+        for path in [
+            "tex/latex/a.sty",
+            "tex/latex/base/a.sty",
+            "xtex/latex/base/a.sty",
+        ] {
+            assert_eq!(index.search(path).map(|i| i.path), Some(path.to_owned()));
+        }
+    }
+}
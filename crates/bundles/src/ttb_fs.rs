@@ -6,6 +6,7 @@
 //! The main type offered by this module is the [`Ttbv1NetBundle`] struct.
 
 use crate::{
+    check_file_hash,
     ttb::{TTBFileIndex, TTBFileInfo, TTBv1Header},
     Bundle, FileIndex, FileInfo,
 };
@@ -17,8 +18,11 @@ use std::{
     path::Path,
 };
 use tectonic_errors::prelude::*;
-use tectonic_io_base::{digest::DigestData, InputHandle, InputOrigin, IoProvider, OpenResult};
-use tectonic_status_base::StatusBackend;
+use tectonic_io_base::{
+    digest::{self, Digest, DigestData},
+    InputHandle, InputOrigin, IoProvider, OpenResult,
+};
+use tectonic_status_base::{tt_warning, StatusBackend};
 
 /// Read a [`TTBFileInfo`] from this bundle.
 /// We assume that `fileinfo` points to a valid file in this bundle.
@@ -75,6 +79,13 @@ impl TTBFsBundle<TTBFileIndex> {
         Ok(())
     }
 
+    /// Read and decompress a file from this bundle.
+    fn read_file(&mut self, info: &TTBFileInfo) -> Result<Vec<u8>> {
+        let mut v: Vec<u8> = Vec::with_capacity(info.real_len as usize);
+        read_fileinfo(info, &mut self.file)?.read_to_end(&mut v)?;
+        Ok(v)
+    }
+
     /// Open a file on the filesystem as a zip bundle.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         Self::new(File::open(path)?)
@@ -85,7 +96,7 @@ impl IoProvider for TTBFsBundle<TTBFileIndex> {
     fn input_open_name(
         &mut self,
         name: &str,
-        _status: &mut dyn StatusBackend,
+        status: &mut dyn StatusBackend,
     ) -> OpenResult<InputHandle> {
         // Fetch index if it is empty
         if self.index.is_empty() {
@@ -99,14 +110,26 @@ impl IoProvider for TTBFsBundle<TTBFileIndex> {
             Some(s) => s,
         };
 
-        let mut v: Vec<u8> = Vec::with_capacity(info.real_len as usize);
+        // If the data don't match the index, try reading them once more in
+        // case the problem was transient (e.g., a flaky network filesystem).
+        let mut retried = false;
+
+        let v = loop {
+            let v = match self.read_file(&info) {
+                Ok(v) => v,
+                Err(e) => return OpenResult::Err(e),
+            };
+
+            let mut dc = digest::create();
+            dc.update(&v);
 
-        match read_fileinfo(&info, &mut self.file) {
-            Err(e) => return OpenResult::Err(e),
-            Ok(mut b) => {
-                if let Err(e) = b.read_to_end(&mut v) {
-                    return OpenResult::Err(e.into());
+            match check_file_hash(&info, &DigestData::from(dc)) {
+                Ok(()) => break v,
+                Err(e) if !retried => {
+                    tt_warning!(status, "rereading \"{}\" from the bundle", info.name; e);
+                    retried = true;
                 }
+                Err(e) => return OpenResult::Err(e),
             }
         };
 
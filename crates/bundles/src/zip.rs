@@ -3,7 +3,10 @@
 
 //! ZIP files as Tectonic bundles.
 
-use crate::Bundle;
+use crate::{
+    search::{SearchIndex, SEARCH_NAME},
+    Bundle,
+};
 use std::{
     fs::File,
     io::{Cursor, Read, Seek},
@@ -12,41 +15,55 @@ use std::{
 };
 use tectonic_errors::prelude::*;
 use tectonic_io_base::{digest, InputHandle, InputOrigin, IoProvider, OpenResult};
-use tectonic_status_base::{NoopStatusBackend, StatusBackend};
+use tectonic_status_base::StatusBackend;
 use zip::{result::ZipError, ZipArchive};
 
 /// A bundle backed by a ZIP file.
+///
+/// If the ZIP file contains a `SEARCH` file at its top level, as the ones
+/// made by `tectonic -X bundle create` do, names are looked up in the
+/// directories that it lists. Otherwise, names must match the paths of the
+/// ZIP file's entries exactly, so that files at the top level can be found
+/// by their plain names.
 pub struct ZipBundle<R: Read + Seek> {
     zip: ZipArchive<R>,
+    search: Option<SearchIndex>,
 }
 
 impl<R: Read + Seek> ZipBundle<R> {
     /// Create a new ZIP bundle for a generic readable and seekable stream.
     pub fn new(reader: R) -> Result<ZipBundle<R>> {
-        Ok(ZipBundle {
+        let mut bundle = ZipBundle {
             zip: ZipArchive::new(reader)?,
-        })
-    }
-}
+            search: None,
+        };
 
-impl ZipBundle<File> {
-    /// Open a file on the filesystem as a ZIP bundle.
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<ZipBundle<File>> {
-        Self::new(File::open(path)?)
+        let search = match bundle.read_entry(SEARCH_NAME, SEARCH_NAME) {
+            OpenResult::Ok(mut h) => {
+                let mut text = String::new();
+                h.read_to_string(&mut text)?;
+                Some(text)
+            }
+            OpenResult::NotAvailable => None,
+            OpenResult::Err(e) => return Err(e),
+        };
+
+        if let Some(text) = search {
+            let paths = bundle.zip.file_names().map(|x| x.to_owned());
+            bundle.search = Some(SearchIndex::new(&text, paths));
+        }
+
+        Ok(bundle)
     }
-}
 
-impl<R: Read + Seek> IoProvider for ZipBundle<R> {
-    fn input_open_name(
-        &mut self,
-        name: &str,
-        _status: &mut dyn StatusBackend,
-    ) -> OpenResult<InputHandle> {
+    /// Read the entry with exactly this path, returning a handle with the
+    /// given name.
+    fn read_entry(&mut self, path: &str, name: &str) -> OpenResult<InputHandle> {
         // We need to be able to look at other items in the Zip file while
         // reading this one, so the only path forward is to read the entire
         // contents into a buffer right now. RAM is cheap these days.
 
-        let mut zipitem = match self.zip.by_name(name) {
+        let mut zipitem = match self.zip.by_name(path) {
             Ok(f) => f,
             Err(e) => {
                 return match e {
@@ -75,14 +92,39 @@ impl<R: Read + Seek> IoProvider for ZipBundle<R> {
     }
 }
 
+impl ZipBundle<File> {
+    /// Open a file on the filesystem as a ZIP bundle.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ZipBundle<File>> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> IoProvider for ZipBundle<R> {
+    fn input_open_name(
+        &mut self,
+        name: &str,
+        _status: &mut dyn StatusBackend,
+    ) -> OpenResult<InputHandle> {
+        let path = match &self.search {
+            None => name.to_owned(),
+            Some(index) => match index.search(name) {
+                Some(p) => p.to_owned(),
+                None => return OpenResult::NotAvailable,
+            },
+        };
+
+        self.read_entry(&path, name)
+    }
+}
+
 impl<R: Read + Seek> Bundle for ZipBundle<R> {
     fn all_files(&self) -> Vec<String> {
         self.zip.file_names().map(|x| x.to_owned()).collect()
     }
 
     fn get_digest(&mut self) -> Result<tectonic_io_base::digest::DigestData> {
-        let digest_text = match self.input_open_name(digest::DIGEST_NAME, &mut NoopStatusBackend {})
-        {
+        // The digest is always at the top level, whatever the search order.
+        let digest_text = match self.read_entry(digest::DIGEST_NAME, digest::DIGEST_NAME) {
             OpenResult::Ok(h) => {
                 let mut text = String::new();
                 h.take(64).read_to_string(&mut text)?;
@@ -3,9 +3,9 @@
 
 //! A single Tectonic document.
 //!
-//! Every document is part of a [`crate::workspace::Workspace`]. At the moment
-//! workspaces can only contain a single document each, but in the future it
-//! might become possible for one workspace to contain multiple documents.
+//! Every document is part of a [`crate::workspace::Workspace`]. A workspace may
+//! consist of just one document, or it may list several member documents that
+//! live in subdirectories of the workspace root.
 //!
 //! This crate, on its own, does not provide document-processing capabilities.
 //! The main `tectonic` crate provides extension traits that set up document
@@ -13,7 +13,7 @@
 
 use std::{
     collections::HashMap,
-    env, fs,
+    env, fmt, fs,
     io::{Read, Write},
     path::{Component, Path, PathBuf},
 };
@@ -71,6 +71,11 @@ pub struct Document {
     /// Either a URL or a local path.
     pub bundle_loc: String,
 
+    /// Bundles to layer on top of the main one, in priority order. Each is
+    /// either a URL or a local path, which may be absolute or relative to
+    /// src_dir.
+    pub bundle_overlays: Vec<String>,
+
     /// Extra local search paths for this document.
     /// May be absolute or relative to src_dir.
     pub extra_paths: Vec<PathBuf>,
@@ -120,6 +125,7 @@ impl Document {
             build_dir: build_dir.into(),
             name: doc.doc.name,
             bundle_loc: doc.doc.bundle,
+            bundle_overlays: doc.doc.bundle_overlays.unwrap_or_default(),
             extra_paths: doc.doc.extra_paths.unwrap_or_default(),
             metadata: doc.doc.metadata,
             outputs,
@@ -145,10 +151,17 @@ impl Document {
             Some(self.extra_paths.clone())
         };
 
+        let bundle_overlays = if self.bundle_overlays.is_empty() {
+            None
+        } else {
+            Some(self.bundle_overlays.clone())
+        };
+
         let doc = syntax::TomlDocument {
             doc: syntax::TomlDocSection {
                 name: self.name.clone(),
                 bundle: self.bundle_loc.clone(),
+                bundle_overlays,
                 extra_paths,
                 metadata: None,
             },
@@ -179,6 +192,15 @@ impl Document {
         &self.src_dir
     }
 
+    /// Get the path of this document's `Tectonic.lock` file, which may not
+    /// exist.
+    ///
+    /// The file lives next to `Tectonic.toml` in [`Self::src_dir`]. See
+    /// [`crate::lockfile::Lockfile`].
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.src_dir.join(crate::lockfile::LOCKFILE_NAME)
+    }
+
     /// Get this document's build directory.
     ///
     /// This is the directory where persistent files associated with the
@@ -256,6 +278,66 @@ pub struct OutputProfile {
     ///
     /// Default is false.
     pub synctex: bool,
+
+    /// Whether SyncTeX data should record input paths relative to the
+    /// document's `src_dir`, rather than as absolute paths. This is needed
+    /// for SyncTeX to work in deterministic mode.
+    ///
+    /// Default is false.
+    pub synctex_relative_paths: bool,
+
+    /// The initial paper size, such as `a4` or `letter`.
+    ///
+    /// If unspecified, the engine default (US Letter) is used.
+    pub paper_size: Option<String>,
+
+    /// The standard that PDF output should conform to.
+    pub pdf_conformance: PdfConformance,
+
+    /// The ICC profile to embed as the output intent of conforming PDF
+    /// output, found like any other input file.
+    ///
+    /// If unspecified, a default suited to the conformance level is used.
+    pub output_intent_profile: Option<String>,
+
+    /// The identifier of the output condition characterized by the output
+    /// intent's profile, such as `FOGRA39`.
+    ///
+    /// If unspecified, a default suited to the conformance level is used.
+    pub output_condition: Option<String>,
+
+    /// How to encrypt PDF output, if at all.
+    pub encryption: Option<PdfEncryption>,
+
+    /// Rerun the TeX engine exactly this many times after the first.
+    ///
+    /// If unspecified, the number of reruns is determined automatically.
+    pub reruns: Option<usize>,
+
+    /// Which engines to run.
+    pub pass: BuildPass,
+
+    /// The minimum number of citations of a cross-referenced BibTeX entry
+    /// needed for it to be included in the bibliography by itself.
+    ///
+    /// If unspecified, BibTeX's default of 2 is used.
+    pub min_crossrefs: Option<u32>,
+
+    /// Whether to ensure a deterministic build environment.
+    ///
+    /// Default is false.
+    pub deterministic_mode: bool,
+
+    /// Extra local search paths for this profile, used in addition to the
+    /// document's [`Document::extra_paths`]. May be absolute or relative to
+    /// the document's `src_dir`.
+    pub extra_paths: Vec<PathBuf>,
+
+    /// Whether intermediate files (such as `.aux` and `.bbl` files) should be
+    /// saved in the build output directory.
+    ///
+    /// Default is false.
+    pub keep_intermediates: bool,
 }
 
 /// The output target type of a document build.
@@ -268,6 +350,90 @@ pub enum BuildTargetType {
     Pdf,
 }
 
+/// The engine passes run by a document build.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BuildPass {
+    /// Repeatedly run TeX and BibTeX until the output stabilizes.
+    #[default]
+    Default,
+
+    /// Just run the TeX engine once.
+    Tex,
+
+    /// Like the default, but run BibTeX once first, before anything else.
+    BibtexFirst,
+}
+
+/// A standard that PDF output can conform to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PdfConformance {
+    /// Plain PDF.
+    #[default]
+    None,
+
+    /// PDF/A-2b, for archiving.
+    PdfA2b,
+
+    /// PDF/X-4, for printing.
+    PdfX4,
+}
+
+/// Settings for encrypting PDF output.
+///
+/// Unset fields take the engine defaults: empty passwords, a 128-bit key, and
+/// permission to print, modify, copy and annotate. The `Debug` output of
+/// this type doesn't show the passwords.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct PdfEncryption {
+    /// The password that gives full access to the document.
+    pub owner_password: Option<String>,
+
+    /// The password needed to open the document.
+    pub user_password: Option<String>,
+
+    /// The length of the encryption key in bits.
+    pub key_length: Option<u32>,
+
+    /// The names of the things that a reader may do without the owner
+    /// password, such as `print` or `copy`.
+    pub permissions: Option<Vec<String>>,
+}
+
+impl fmt::Debug for PdfEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |pw: &Option<String>| pw.as_ref().map(|_| "<redacted>");
+
+        f.debug_struct("PdfEncryption")
+            .field("owner_password", &redacted(&self.owner_password))
+            .field("user_password", &redacted(&self.user_password))
+            .field("key_length", &self.key_length)
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+impl PdfEncryption {
+    /// Combine these settings with *overrides*, whose fields take precedence
+    /// where they are set.
+    pub fn overridden_by(&self, overrides: &PdfEncryption) -> PdfEncryption {
+        PdfEncryption {
+            owner_password: overrides
+                .owner_password
+                .clone()
+                .or_else(|| self.owner_password.clone()),
+            user_password: overrides
+                .user_password
+                .clone()
+                .or_else(|| self.user_password.clone()),
+            key_length: overrides.key_length.or(self.key_length),
+            permissions: overrides
+                .permissions
+                .clone()
+                .or_else(|| self.permissions.clone()),
+        }
+    }
+}
+
 /// An input provided to a document build
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum InputFile {
@@ -327,6 +493,7 @@ impl Document {
             build_dir,
             name,
             bundle_loc,
+            bundle_overlays: wc.bundle_overlays.clone(),
             extra_paths,
             outputs: crate::document::default_outputs(),
             metadata: None,
@@ -349,6 +516,18 @@ pub(crate) fn default_outputs() -> HashMap<String, OutputProfile> {
             shell_escape: false,
             shell_escape_cwd: None,
             synctex: false,
+            synctex_relative_paths: false,
+            paper_size: None,
+            pdf_conformance: PdfConformance::None,
+            output_intent_profile: None,
+            output_condition: None,
+            encryption: None,
+            reruns: None,
+            pass: BuildPass::Default,
+            min_crossrefs: None,
+            deterministic_mode: false,
+            extra_paths: Vec::new(),
+            keep_intermediates: false,
         },
     );
     outputs
@@ -383,6 +562,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bundle_overlays() {
+        const TOML: &str = r#"
+        [doc]
+        name = "test"
+        bundle = "na"
+        bundle_overlays = ["house-style/", "extra.zip"]
+
+        [[output]]
+        name = "o"
+        type = "pdf"
+        "#;
+
+        let mut c = Cursor::new(TOML.as_bytes());
+        let doc = Document::new_from_toml(".", ".", &mut c).unwrap();
+        assert_eq!(doc.bundle_overlays, vec!["house-style/", "extra.zip"]);
+
+        const TOML_DEFAULT: &str = r#"
+        [doc]
+        name = "test"
+        bundle = "na"
+
+        [[output]]
+        name = "o"
+        type = "pdf"
+        "#;
+
+        let mut c = Cursor::new(TOML_DEFAULT.as_bytes());
+        let doc = Document::new_from_toml(".", ".", &mut c).unwrap();
+        assert!(doc.bundle_overlays.is_empty());
+    }
+
     #[test]
     fn shell_escape_default_false() {
         const TOML: &str = r#"
@@ -450,4 +661,117 @@ mod tests {
         let doc = Document::new_from_toml(".", ".", &mut c).unwrap();
         assert!(doc.outputs.get("o").unwrap().synctex);
     }
+
+    #[test]
+    fn engine_options_default() {
+        const TOML: &str = r#"
+        [doc]
+        name = "test"
+        bundle = "na"
+
+        [[output]]
+        name = "o"
+        type = "pdf"
+        "#;
+        let mut c = Cursor::new(TOML.as_bytes());
+        let doc = Document::new_from_toml(".", ".", &mut c).unwrap();
+        let profile = doc.outputs.get("o").unwrap();
+        assert_eq!(profile.paper_size, None);
+        assert!(!profile.synctex_relative_paths);
+        assert_eq!(profile.pdf_conformance, PdfConformance::None);
+        assert_eq!(profile.output_intent_profile, None);
+        assert_eq!(profile.encryption, None);
+        assert_eq!(profile.reruns, None);
+        assert_eq!(profile.pass, BuildPass::Default);
+        assert_eq!(profile.min_crossrefs, None);
+        assert!(!profile.deterministic_mode);
+        assert!(profile.extra_paths.is_empty());
+        assert!(!profile.keep_intermediates);
+    }
+
+    #[test]
+    fn engine_options_set() {
+        const TOML: &str = r#"
+        [doc]
+        name = "test"
+        bundle = "na"
+
+        [[output]]
+        name = "o"
+        type = "pdf"
+        paper_size = "a4"
+        synctex_relative_paths = true
+        pdf_conformance = "pdfx-4"
+        output_intent_profile = "press.icc"
+        output_condition = "FOGRA51"
+        reruns = 2
+        pass = "bibtex_first"
+        min_crossrefs = 5
+        deterministic_mode = true
+        extra_paths = ["../shared"]
+        keep_intermediates = true
+        "#;
+        let mut c = Cursor::new(TOML.as_bytes());
+        let doc = Document::new_from_toml(".", ".", &mut c).unwrap();
+        let profile = doc.outputs.get("o").unwrap();
+        assert_eq!(profile.paper_size.as_deref(), Some("a4"));
+        assert!(profile.synctex_relative_paths);
+        assert_eq!(profile.pdf_conformance, PdfConformance::PdfX4);
+        assert_eq!(profile.output_intent_profile.as_deref(), Some("press.icc"));
+        assert_eq!(profile.output_condition.as_deref(), Some("FOGRA51"));
+        assert_eq!(profile.reruns, Some(2));
+        assert_eq!(profile.pass, BuildPass::BibtexFirst);
+        assert_eq!(profile.min_crossrefs, Some(5));
+        assert!(profile.deterministic_mode);
+        assert_eq!(profile.extra_paths, vec![PathBuf::from("../shared")]);
+        assert!(profile.keep_intermediates);
+
+        // These settings should survive a trip back into TOML.
+        let toml_profile = crate::syntax::TomlOutputProfile::from(profile);
+        let roundtripped = OutputProfile::from(&toml_profile);
+        assert_eq!(roundtripped.paper_size.as_deref(), Some("a4"));
+        assert!(roundtripped.synctex_relative_paths);
+        assert_eq!(roundtripped.pdf_conformance, PdfConformance::PdfX4);
+        assert_eq!(roundtripped.output_condition.as_deref(), Some("FOGRA51"));
+        assert_eq!(roundtripped.pass, BuildPass::BibtexFirst);
+        assert!(roundtripped.keep_intermediates);
+    }
+
+    #[test]
+    fn encryption_section() {
+        const TOML: &str = r#"
+        [doc]
+        name = "test"
+        bundle = "na"
+
+        [[output]]
+        name = "o"
+        type = "pdf"
+
+        [output.encryption]
+        owner_password = "owner"
+        key_length = 256
+        permissions = ["print", "copy"]
+        "#;
+        let mut c = Cursor::new(TOML.as_bytes());
+        let doc = Document::new_from_toml(".", ".", &mut c).unwrap();
+        let enc = doc.outputs.get("o").unwrap().encryption.as_ref().unwrap();
+        assert_eq!(enc.owner_password.as_deref(), Some("owner"));
+        assert_eq!(enc.user_password, None);
+        assert_eq!(enc.key_length, Some(256));
+        assert_eq!(
+            enc.permissions.as_deref(),
+            Some(&["print".to_owned(), "copy".to_owned()][..])
+        );
+
+        let overridden = enc.overridden_by(&PdfEncryption {
+            user_password: Some("user".to_owned()),
+            key_length: Some(128),
+            ..PdfEncryption::default()
+        });
+        assert_eq!(overridden.owner_password.as_deref(), Some("owner"));
+        assert_eq!(overridden.user_password.as_deref(), Some("user"));
+        assert_eq!(overridden.key_length, Some(128));
+        assert_eq!(overridden.permissions, enc.permissions);
+    }
 }
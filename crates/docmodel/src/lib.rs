@@ -15,7 +15,11 @@
 //! up a workspace by searching the process’ current directory and parents for a
 //! `Tectonic.toml` file. There is also [`workspace::WorkspaceCreator`] for
 //! creating new workspaces from scratch.
+//!
+//! The [`lockfile`] module handles the `Tectonic.lock` file, which pins a
+//! document to an exact version of its bundle.
 
 pub mod document;
+pub mod lockfile;
 mod syntax;
 pub mod workspace;
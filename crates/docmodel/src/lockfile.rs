@@ -0,0 +1,144 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `Tectonic.lock` file.
+//!
+//! A document’s `Tectonic.toml` file only says where to find its bundle. If the
+//! bundle at that location changes, builds of the document may silently
+//! change too. A lockfile records the exact digest of the bundle, and
+//! optionally the digests of the bundle files that the document uses, so that
+//! such changes can be detected.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+use tectonic_errors::prelude::*;
+
+use crate::syntax;
+
+/// The name of the lockfile, which lives next to a document's `Tectonic.toml`.
+pub const LOCKFILE_NAME: &str = "Tectonic.lock";
+
+const LOCKFILE_HEADER: &str = "\
+# This file is generated by `tectonic -X bundle update`. It pins this document
+# to an exact version of its bundle, and should not be edited by hand.
+
+";
+
+/// The contents of a `Tectonic.lock` file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Lockfile {
+    /// The location of the bundle when the lockfile was written. This is
+    /// informational: it is the digest that counts.
+    pub bundle_loc: String,
+
+    /// The hex SHA-256 digest of the bundle.
+    pub bundle_digest: String,
+
+    /// The hex SHA-256 digests of the bundle files used by the document, keyed
+    /// by the names under which they were requested. This is empty if file
+    /// digests weren't recorded.
+    pub files: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    /// Parse a lockfile from a stream.
+    pub fn new_from_toml<R: Read>(toml_data: &mut R) -> Result<Self> {
+        let mut toml_text = String::new();
+        toml_data.read_to_string(&mut toml_text)?;
+        let lock: syntax::TomlLockfile = toml::from_str(&toml_text)?;
+
+        Ok(Lockfile {
+            bundle_loc: lock.bundle.location,
+            bundle_digest: lock.bundle.digest,
+            files: lock.files,
+        })
+    }
+
+    /// Load the lockfile at *path*, returning `None` if it doesn't exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+
+        let mut f = match fs::File::open(path) {
+            Ok(f) => f,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Some(atry!(
+            Self::new_from_toml(&mut f);
+            ["couldn\'t load `{}`", path.display()]
+        )))
+    }
+
+    /// Render this lockfile as TOML text.
+    pub fn to_toml(&self) -> Result<String> {
+        let lock = syntax::TomlLockfile {
+            bundle: syntax::TomlLockBundle {
+                location: self.bundle_loc.clone(),
+                digest: self.bundle_digest.clone(),
+            },
+            files: self.files.clone(),
+        };
+
+        Ok(format!(
+            "{LOCKFILE_HEADER}{}",
+            toml::to_string_pretty(&lock)?
+        ))
+    }
+
+    /// Write this lockfile to *path*, replacing any existing file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = self.to_toml()?;
+        atry!(
+            fs::write(path, text);
+            ["couldn\'t write `{}`", path.display()]
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut lock = Lockfile {
+            bundle_loc: "https://example.com/bundle.ttb".to_owned(),
+            bundle_digest: "1".repeat(64),
+            files: BTreeMap::new(),
+        };
+
+        let text = lock.to_toml().unwrap();
+        assert!(!text.contains("[files]"));
+        let mut c = Cursor::new(text.as_bytes());
+        assert_eq!(Lockfile::new_from_toml(&mut c).unwrap(), lock);
+
+        lock.files.insert("latex.ltx".to_owned(), "2".repeat(64));
+        lock.files.insert("article.cls".to_owned(), "3".repeat(64));
+
+        let text = lock.to_toml().unwrap();
+        let mut c = Cursor::new(text.as_bytes());
+        assert_eq!(Lockfile::new_from_toml(&mut c).unwrap(), lock);
+    }
+
+    #[test]
+    fn unknown_keys_rejected() {
+        const TOML: &str = r#"
+        [bundle]
+        location = "na"
+        digest = "00"
+        extra = 1
+        "#;
+
+        let mut c = Cursor::new(TOML.as_bytes());
+        assert!(Lockfile::new_from_toml(&mut c).is_err());
+    }
+}
@@ -1,16 +1,17 @@
 // Copyright 2020-2023 the Tectonic Project
 // Licensed under the MIT License.
 
-//! This file defines the syntax of Tectonic.toml,
-//! which is parsed using serde.
+//! This file defines the syntax of Tectonic.toml and Tectonic.lock,
+//! which are parsed using serde.
 //!
-//! This module is only used by [`crate::document::Document`]
+//! This module is only used by [`crate::document::Document`],
+//! [`crate::workspace::Workspace`], and [`crate::lockfile::Lockfile`]
 
-use std::path::PathBuf;
+use std::{collections::BTreeMap, fmt, path::PathBuf};
 
 use crate::document::{
-    BuildTargetType, InputFile, OutputProfile, DEFAULT_INDEX_FILE, DEFAULT_POSTAMBLE_FILE,
-    DEFAULT_PREAMBLE_FILE,
+    BuildPass, BuildTargetType, InputFile, OutputProfile, PdfConformance, PdfEncryption,
+    DEFAULT_INDEX_FILE, DEFAULT_POSTAMBLE_FILE, DEFAULT_PREAMBLE_FILE,
 };
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -29,11 +30,42 @@ pub struct TomlDocument {
     pub outputs: Vec<TomlOutputProfile>,
 }
 
+/// A `Tectonic.toml` file at the root of a multi-document workspace.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlWorkspace {
+    pub workspace: TomlWorkspaceSection,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlWorkspaceSection {
+    pub members: Vec<String>,
+}
+
+/// A `Tectonic.lock` file.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlLockfile {
+    pub bundle: TomlLockBundle,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlLockBundle {
+    pub location: String,
+    pub digest: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct TomlDocSection {
     pub name: String,
     pub bundle: String,
+    pub bundle_overlays: Option<Vec<String>>,
     pub metadata: Option<toml::Value>,
     pub extra_paths: Option<Vec<PathBuf>>,
 }
@@ -102,6 +134,18 @@ pub struct TomlOutputProfile {
     pub shell_escape: Option<bool>,
     pub shell_escape_cwd: Option<String>,
     pub synctex: Option<bool>,
+    pub synctex_relative_paths: Option<bool>,
+    pub paper_size: Option<String>,
+    pub pdf_conformance: Option<TomlPdfConformance>,
+    pub output_intent_profile: Option<String>,
+    pub output_condition: Option<String>,
+    pub encryption: Option<TomlPdfEncryption>,
+    pub reruns: Option<usize>,
+    pub pass: Option<TomlBuildPass>,
+    pub min_crossrefs: Option<u32>,
+    pub deterministic_mode: Option<bool>,
+    pub extra_paths: Option<Vec<PathBuf>>,
+    pub keep_intermediates: Option<bool>,
 
     // We cannot handle these two input variants with an enum.
     // The ideal solution requires #[serde(flatten)],
@@ -167,6 +211,21 @@ impl From<&TomlOutputProfile> for OutputProfile {
             shell_escape: val.shell_escape.unwrap_or(shell_escape_default),
             shell_escape_cwd: val.shell_escape_cwd.clone(),
             synctex: val.synctex.unwrap_or(synctex_default),
+            synctex_relative_paths: val.synctex_relative_paths.unwrap_or(false),
+            paper_size: val.paper_size.clone(),
+            pdf_conformance: val
+                .pdf_conformance
+                .map(PdfConformance::from)
+                .unwrap_or_default(),
+            output_intent_profile: val.output_intent_profile.clone(),
+            output_condition: val.output_condition.clone(),
+            encryption: val.encryption.as_ref().map(PdfEncryption::from),
+            reruns: val.reruns,
+            pass: val.pass.map(BuildPass::from).unwrap_or_default(),
+            min_crossrefs: val.min_crossrefs,
+            deterministic_mode: val.deterministic_mode.unwrap_or(false),
+            extra_paths: val.extra_paths.clone().unwrap_or_default(),
+            keep_intermediates: val.keep_intermediates.unwrap_or(false),
         }
     }
 }
@@ -185,6 +244,36 @@ impl From<&OutputProfile> for TomlOutputProfile {
         let shell_escape = if !rt.shell_escape { None } else { Some(true) };
         let shell_escape_cwd = rt.shell_escape_cwd.clone();
         let synctex = if !rt.synctex { None } else { Some(true) };
+        let synctex_relative_paths = if !rt.synctex_relative_paths {
+            None
+        } else {
+            Some(true)
+        };
+        let pass = if rt.pass == BuildPass::Default {
+            None
+        } else {
+            Some(TomlBuildPass::from(&rt.pass))
+        };
+        let pdf_conformance = if rt.pdf_conformance == PdfConformance::None {
+            None
+        } else {
+            Some(TomlPdfConformance::from(&rt.pdf_conformance))
+        };
+        let deterministic_mode = if !rt.deterministic_mode {
+            None
+        } else {
+            Some(true)
+        };
+        let extra_paths = if rt.extra_paths.is_empty() {
+            None
+        } else {
+            Some(rt.extra_paths.clone())
+        };
+        let keep_intermediates = if !rt.keep_intermediates {
+            None
+        } else {
+            Some(true)
+        };
 
         TomlOutputProfile {
             name: rt.name.clone(),
@@ -194,6 +283,18 @@ impl From<&OutputProfile> for TomlOutputProfile {
             shell_escape,
             shell_escape_cwd,
             synctex,
+            synctex_relative_paths,
+            paper_size: rt.paper_size.clone(),
+            pdf_conformance,
+            output_intent_profile: rt.output_intent_profile.clone(),
+            output_condition: rt.output_condition.clone(),
+            encryption: rt.encryption.as_ref().map(TomlPdfEncryption::from),
+            reruns: rt.reruns,
+            pass,
+            min_crossrefs: rt.min_crossrefs,
+            deterministic_mode,
+            extra_paths,
+            keep_intermediates,
             preamble_file: None,
             index_file: None,
             postamble_file: None,
@@ -227,3 +328,105 @@ impl From<&BuildTargetType> for TomlBuildTargetType {
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TomlBuildPass {
+    #[serde(rename = "default")]
+    Default,
+
+    #[serde(rename = "tex")]
+    Tex,
+
+    #[serde(rename = "bibtex_first")]
+    BibtexFirst,
+}
+
+impl From<TomlBuildPass> for BuildPass {
+    fn from(val: TomlBuildPass) -> BuildPass {
+        match val {
+            TomlBuildPass::Default => BuildPass::Default,
+            TomlBuildPass::Tex => BuildPass::Tex,
+            TomlBuildPass::BibtexFirst => BuildPass::BibtexFirst,
+        }
+    }
+}
+
+impl From<&BuildPass> for TomlBuildPass {
+    fn from(s: &BuildPass) -> Self {
+        match s {
+            BuildPass::Default => TomlBuildPass::Default,
+            BuildPass::Tex => TomlBuildPass::Tex,
+            BuildPass::BibtexFirst => TomlBuildPass::BibtexFirst,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TomlPdfConformance {
+    #[serde(rename = "none")]
+    None,
+
+    #[serde(rename = "pdfa-2b")]
+    PdfA2b,
+
+    #[serde(rename = "pdfx-4")]
+    PdfX4,
+}
+
+impl From<TomlPdfConformance> for PdfConformance {
+    fn from(val: TomlPdfConformance) -> PdfConformance {
+        match val {
+            TomlPdfConformance::None => PdfConformance::None,
+            TomlPdfConformance::PdfA2b => PdfConformance::PdfA2b,
+            TomlPdfConformance::PdfX4 => PdfConformance::PdfX4,
+        }
+    }
+}
+
+impl From<&PdfConformance> for TomlPdfConformance {
+    fn from(s: &PdfConformance) -> Self {
+        match s {
+            PdfConformance::None => TomlPdfConformance::None,
+            PdfConformance::PdfA2b => TomlPdfConformance::PdfA2b,
+            PdfConformance::PdfX4 => TomlPdfConformance::PdfX4,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlPdfEncryption {
+    pub owner_password: Option<String>,
+    pub user_password: Option<String>,
+    pub key_length: Option<u32>,
+    pub permissions: Option<Vec<String>>,
+}
+
+/// Like [`PdfEncryption`], this doesn't show the passwords.
+impl fmt::Debug for TomlPdfEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&PdfEncryption::from(self), f)
+    }
+}
+
+impl From<&TomlPdfEncryption> for PdfEncryption {
+    fn from(val: &TomlPdfEncryption) -> PdfEncryption {
+        PdfEncryption {
+            owner_password: val.owner_password.clone(),
+            user_password: val.user_password.clone(),
+            key_length: val.key_length,
+            permissions: val.permissions.clone(),
+        }
+    }
+}
+
+impl From<&PdfEncryption> for TomlPdfEncryption {
+    fn from(rt: &PdfEncryption) -> Self {
+        TomlPdfEncryption {
+            owner_password: rt.owner_password.clone(),
+            user_password: rt.user_password.clone(),
+            key_length: rt.key_length,
+            permissions: rt.permissions.clone(),
+        }
+    }
+}
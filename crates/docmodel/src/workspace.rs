@@ -3,91 +3,302 @@
 
 //! A Tectonic document-build workspace.
 //!
-//! For the time being, this is just a thin wrapper to provide access to a
-//! `Document` instance. This API exists to future-proof a bit for a potential
-//! world where one workspace can contain multiple documents.
+//! A workspace is a directory tree containing one or more documents. In the
+//! simplest case, the workspace root contains a `Tectonic.toml` file defining a
+//! single document. Alternatively, the root `Tectonic.toml` file can contain a
+//! `[workspace]` section listing several member directories, each of which
+//! contains a `Tectonic.toml` file defining a document of its own.
 
 use std::{
     env,
     error::Error,
     fmt, fs,
     io::{self, Write},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
 };
 use tectonic_errors::prelude::*;
 
-use crate::document::Document;
+use crate::{document::Document, syntax};
 
 /// A Tectonic workspace.
 ///
-/// For the time being, a Workspace is just a thin wrapper to provide access to
-/// a `Document` instance. In the future, it might become possible for one
-/// workspace to contain multiple documents.
+/// A workspace contains one or more documents. If it was opened from a
+/// directory within one of its documents, that document is the workspace’s
+/// “current” document, which commands should operate on by default.
 ///
 /// In most cases, you will want to create a [`Workspace`] by opening an
 /// existing one using [`Workspace::open_from_environment`].
 #[derive(Debug)]
 pub struct Workspace {
     /// The root directory of the workspace.
-    #[allow(dead_code)] // We expect to use this eventually.
     root_dir: PathBuf,
 
-    /// This workspace's document. In the future, there might be more than one.
-    doc: Document,
+    /// This workspace's documents, in the order that they are listed in the
+    /// workspace's `Tectonic.toml` file. There is always at least one.
+    docs: Vec<Document>,
+
+    /// The index of the document containing the directory that the workspace
+    /// was opened from, if any.
+    current: Option<usize>,
+}
+
+/// The contents of a `Tectonic.toml` file.
+enum Manifest {
+    /// The file defines a single document.
+    Document(Document),
+
+    /// The file defines a multi-document workspace with the given member
+    /// directories.
+    Workspace(Vec<PathBuf>),
 }
 
 impl Workspace {
+    /// Get the root directory of the workspace.
+    ///
+    /// This is the directory containing the toplevel `Tectonic.toml` file. For a
+    /// single-document workspace, it is the same as the document's
+    /// [`Document::src_dir`].
+    pub fn root_dir(&self) -> &Path {
+        &self.root_dir
+    }
+
+    /// Get all of the documents in the workspace.
+    pub fn documents(&self) -> &[Document] {
+        &self.docs
+    }
+
     /// Get the first document in the workspace.
     ///
-    /// Right now, workspaces in fact only include one document. That may change
-    /// in the future.
+    /// For a multi-document workspace, this is the first member listed in the
+    /// workspace's `Tectonic.toml` file.
     pub fn first_document(&self) -> &Document {
-        &self.doc
+        &self.docs[0]
     }
 
     /// Get the first document in the workspace, mutably.
     ///
-    /// Right now, workspaces in fact only include one document. That may change
-    /// in the future.
+    /// For a multi-document workspace, this is the first member listed in the
+    /// workspace's `Tectonic.toml` file.
     pub fn first_document_mut(&mut self) -> &mut Document {
-        &mut self.doc
+        &mut self.docs[0]
+    }
+
+    /// Get the document containing the directory that the workspace was opened
+    /// from, if any.
+    ///
+    /// For a single-document workspace, this is always the document. For a
+    /// multi-document workspace opened from its root directory, there is no
+    /// current document.
+    pub fn current_document(&self) -> Option<&Document> {
+        self.current.map(|i| &self.docs[i])
+    }
+
+    /// Get the document with the given name, if it exists.
+    pub fn document_by_name(&self, name: &str) -> Option<&Document> {
+        self.docs.iter().find(|d| d.name == name)
+    }
+
+    /// Get the documents that a command should operate on.
+    ///
+    /// If *name* is specified, the document with that name is selected, and an
+    /// error is returned if there is no such document. Otherwise, the current
+    /// document is selected if there is one, and all documents are selected if
+    /// there isn't.
+    pub fn select_documents(&self, name: Option<&str>) -> Result<Vec<&Document>> {
+        if let Some(name) = name {
+            return match self.document_by_name(name) {
+                Some(doc) => Ok(vec![doc]),
+                None => {
+                    let names: Vec<_> = self.docs.iter().map(|d| d.name.as_str()).collect();
+                    bail!(
+                        "no document named `{}` in this workspace (available: {})",
+                        name,
+                        names.join(", ")
+                    )
+                }
+            };
+        }
+
+        Ok(match self.current_document() {
+            Some(doc) => vec![doc],
+            None => self.docs.iter().collect(),
+        })
     }
 
     /// Open up a workspace based on the current process environment.
     ///
-    /// This function searches the current directory and its parents for a
-    /// `Tectonic.toml` file. Because workspaces can currently only contain a
-    /// single document, the search stops when the first such file is found. If
-    /// no such file is found, an error downcastable into
-    /// [`NoWorkspaceFoundError`] is returned.
+    /// This is [`Self::open_from_dir`] applied to the current directory.
     pub fn open_from_environment() -> Result<Self> {
-        let initial_dir = env::current_dir()?;
+        Self::open_from_dir(env::current_dir()?)
+    }
+
+    /// Open up a workspace containing the specified directory.
+    ///
+    /// This function searches the directory and its parents for a
+    /// `Tectonic.toml` file. If that file defines a multi-document workspace,
+    /// that workspace is opened. If it defines a document, the search continues
+    /// upwards for a workspace listing that document as a member; if none is
+    /// found, a single-document workspace is opened. If no `Tectonic.toml` file
+    /// is found at all, an error downcastable into [`NoWorkspaceFoundError`] is
+    /// returned.
+    pub fn open_from_dir<P: Into<PathBuf>>(initial_dir: P) -> Result<Self> {
+        let initial_dir = initial_dir.into();
+        let mut dir = initial_dir.clone();
+
+        loop {
+            match read_manifest(&dir)? {
+                Some(Manifest::Workspace(members)) => {
+                    return Self::open_members(dir, &members, &initial_dir);
+                }
+
+                Some(Manifest::Document(doc)) => {
+                    // Parent directories that don't contain a valid workspace
+                    // file are no business of ours, so errors here are ignored.
+                    let mut parent = dir.clone();
 
-        let mut root_dir = initial_dir.clone();
-        root_dir.push("tmp"); // simplifies loop logic
+                    while parent.pop() {
+                        if let Ok(Some(Manifest::Workspace(members))) = read_manifest(&parent) {
+                            if members.iter().any(|m| parent.join(m) == dir) {
+                                return Self::open_members(parent, &members, &initial_dir);
+                            }
 
-        while root_dir.pop() {
-            root_dir.push("Tectonic.toml");
+                            break;
+                        }
+                    }
 
-            let mut doc_file = match fs::File::open(&root_dir) {
-                Ok(f) => f,
-                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
-                    root_dir.pop(); // remove "Tectonic.toml"
-                    continue; // this will pop up one directory and try again
+                    return Ok(Workspace {
+                        root_dir: dir,
+                        docs: vec![doc],
+                        current: Some(0),
+                    });
                 }
-                Err(e) => return Err(e.into()),
-            };
 
-            root_dir.pop();
-            let mut doc_build_dir = root_dir.clone();
-            doc_build_dir.push("build");
-            let doc = Document::new_from_toml(root_dir.clone(), doc_build_dir, &mut doc_file)?;
+                None => {}
+            }
 
-            return Ok(Workspace { root_dir, doc });
+            if !dir.pop() {
+                break;
+            }
         }
 
         Err(NoWorkspaceFoundError { initial_dir }.into())
     }
+
+    /// Load the members of a multi-document workspace.
+    fn open_members(root_dir: PathBuf, members: &[PathBuf], initial_dir: &Path) -> Result<Self> {
+        let mut docs: Vec<Document> = Vec::new();
+        let mut current: Option<usize> = None;
+
+        for member in members {
+            let doc_dir = root_dir.join(member);
+
+            let doc = match read_manifest(&doc_dir)? {
+                Some(Manifest::Document(doc)) => doc,
+                Some(Manifest::Workspace(_)) => bail!(
+                    "workspace member `{}` is itself a workspace, which is not supported",
+                    member.display()
+                ),
+                None => bail!(
+                    "workspace member `{}` does not contain a `Tectonic.toml` file",
+                    member.display()
+                ),
+            };
+
+            if docs.iter().any(|d| d.name == doc.name) {
+                bail!(
+                    "duplicated document name `{}` in workspace `{}`",
+                    doc.name,
+                    root_dir.display()
+                );
+            }
+
+            // If members are nested, the innermost one containing the initial
+            // directory is the current document.
+            if initial_dir.starts_with(&doc_dir) {
+                let deeper = match current {
+                    Some(i) => doc_dir.starts_with(docs[i].src_dir()),
+                    None => true,
+                };
+
+                if deeper {
+                    current = Some(docs.len());
+                }
+            }
+
+            docs.push(doc);
+        }
+
+        Ok(Workspace {
+            root_dir,
+            docs,
+            current,
+        })
+    }
+}
+
+/// Read the `Tectonic.toml` file in *dir*, if there is one.
+fn read_manifest(dir: &Path) -> Result<Option<Manifest>> {
+    let toml_path = dir.join("Tectonic.toml");
+
+    let toml_text = match fs::read_to_string(&toml_path) {
+        Ok(t) => t,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let table: toml::Table = atry!(
+        toml::from_str(&toml_text);
+        ["couldn\'t parse `{}`", toml_path.display()]
+    );
+
+    if !table.contains_key("workspace") {
+        let doc = atry!(
+            Document::new_from_toml(dir, dir.join("build"), &mut toml_text.as_bytes());
+            ["couldn\'t load document from `{}`", toml_path.display()]
+        );
+        return Ok(Some(Manifest::Document(doc)));
+    }
+
+    let ws: syntax::TomlWorkspace = atry!(
+        toml::from_str(&toml_text);
+        ["couldn\'t load workspace from `{}`", toml_path.display()]
+    );
+
+    let mut members = Vec::new();
+
+    for member in ws.workspace.members {
+        let path = PathBuf::from(&member);
+
+        // Members must live inside the workspace, and we want to be able to
+        // compare member paths against the current directory textually.
+        let ok = path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            && path.components().any(|c| matches!(c, Component::Normal(_)));
+
+        if !ok {
+            bail!(
+                "workspace member `{}` in `{}` must be a relative path inside the workspace",
+                member,
+                toml_path.display()
+            );
+        }
+
+        members.push(
+            path.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect(),
+        );
+    }
+
+    if members.is_empty() {
+        bail!(
+            "workspace `{}` must list at least one member",
+            toml_path.display()
+        );
+    }
+
+    Ok(Some(Manifest::Workspace(members)))
 }
 
 /// An error for when the environment does not seem to contain a Tectonic
@@ -114,6 +325,9 @@ impl Error for NoWorkspaceFoundError {}
 pub struct WorkspaceCreator {
     /// The root directory of the workspace to be created.
     pub(crate) root_dir: PathBuf,
+
+    /// Bundles to layer on top of the new document's main bundle.
+    pub(crate) bundle_overlays: Vec<String>,
 }
 
 impl WorkspaceCreator {
@@ -121,9 +335,17 @@ impl WorkspaceCreator {
     pub fn new<P: Into<PathBuf>>(root_dir: P) -> Self {
         WorkspaceCreator {
             root_dir: root_dir.into(),
+            bundle_overlays: Vec::new(),
         }
     }
 
+    /// Set the bundles to layer on top of the new document's main bundle, in
+    /// priority order.
+    pub fn bundle_overlays(&mut self, locs: Vec<String>) -> &mut Self {
+        self.bundle_overlays = locs;
+        self
+    }
+
     /// Consume this object and attempt to create the new workspace.
     pub fn create(self, bundle_loc: String, extra_paths: Vec<PathBuf>) -> Result<Workspace> {
         let doc = Document::create_for(&self, bundle_loc, extra_paths)?;
@@ -176,7 +398,83 @@ impl WorkspaceCreator {
 
         Ok(Workspace {
             root_dir: self.root_dir,
-            doc,
+            docs: vec![doc],
+            current: Some(0),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_doc(dir: &Path, name: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Tectonic.toml"),
+            format!(
+                "[doc]\nname = \"{name}\"\nbundle = \"na\"\n\n[[output]]\nname = \"o\"\ntype = \"pdf\"\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn single_document() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+        write_doc(root, "solo");
+
+        let ws = Workspace::open_from_dir(root.join("src")).unwrap();
+        assert_eq!(ws.root_dir(), root);
+        assert_eq!(ws.documents().len(), 1);
+        assert_eq!(ws.current_document().unwrap().name, "solo");
+    }
+
+    #[test]
+    fn multiple_documents() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+        fs::write(
+            root.join("Tectonic.toml"),
+            "[workspace]\nmembers = [\"thesis\", \"papers/first\"]\n",
+        )
+        .unwrap();
+        write_doc(&root.join("thesis"), "thesis");
+        write_doc(&root.join("papers").join("first"), "first");
+
+        let ws = Workspace::open_from_dir(root).unwrap();
+        assert_eq!(ws.documents().len(), 2);
+        assert!(ws.current_document().is_none());
+        assert_eq!(ws.select_documents(None).unwrap().len(), 2);
+        assert_eq!(ws.select_documents(Some("first")).unwrap()[0].name, "first");
+        assert!(ws.select_documents(Some("nope")).is_err());
+
+        let ws = Workspace::open_from_dir(root.join("papers").join("first").join("src")).unwrap();
+        assert_eq!(ws.root_dir(), root);
+        assert_eq!(ws.documents().len(), 2);
+        let selected = ws.select_documents(None).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "first");
+    }
+
+    #[test]
+    fn bad_members() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+
+        fs::write(
+            root.join("Tectonic.toml"),
+            "[workspace]\nmembers = [\"../elsewhere\"]\n",
+        )
+        .unwrap();
+        assert!(Workspace::open_from_dir(root).is_err());
+
+        fs::write(
+            root.join("Tectonic.toml"),
+            "[workspace]\nmembers = [\"missing\"]\n",
+        )
+        .unwrap();
+        assert!(Workspace::open_from_dir(root).is_err());
+    }
+}
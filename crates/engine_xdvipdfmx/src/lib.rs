@@ -18,10 +18,355 @@
 //! [Tectonic]: https://tectonic-typesetting.github.io/
 //! [`tectonic`]: https://docs.rs/tectonic/
 
-use std::{ffi::CString, time::SystemTime};
+use std::{ffi::CString, fmt, str::FromStr, time::SystemTime};
 use tectonic_bridge_core::{CoreBridgeLauncher, EngineAbortedError};
 use tectonic_errors::prelude::*;
 
+/// A standard that the output PDF can be made to conform to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PdfConformance {
+    /// Plain PDF, with no conformance claimed.
+    #[default]
+    None,
+
+    /// PDF/A-2b (ISO 19005-2, level B), for long-term archiving.
+    PdfA2b,
+
+    /// PDF/X-4 (ISO 15930-7), for print production.
+    PdfX4,
+}
+
+impl PdfConformance {
+    /// The ICC profile used for the output intent if none is specified, and
+    /// the name of its output condition.
+    ///
+    /// These are the profiles from the `colorprofiles` package: sRGB for
+    /// PDF/A, and the FOGRA39 coated-paper CMYK profile for PDF/X.
+    pub fn default_output_intent(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            PdfConformance::None => None,
+            PdfConformance::PdfA2b => Some(("sRGB.icc", "sRGB IEC61966-2.1")),
+            PdfConformance::PdfX4 => Some(("FOGRA39L_coated.icc", "FOGRA39")),
+        }
+    }
+
+    fn c_value(self) -> libc::c_int {
+        match self {
+            PdfConformance::None => 0,
+            PdfConformance::PdfA2b => 1,
+            PdfConformance::PdfX4 => 2,
+        }
+    }
+}
+
+impl fmt::Display for PdfConformance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdfConformance::None => f.write_str("plain PDF"),
+            PdfConformance::PdfA2b => f.write_str("PDF/A-2b"),
+            PdfConformance::PdfX4 => f.write_str("PDF/X-4"),
+        }
+    }
+}
+
+/// The kinds of problems that keep a PDF from conforming to a standard.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ViolationKind {
+    /// The document is encrypted.
+    Encryption,
+
+    /// A font is not embedded.
+    FontNotEmbedded,
+
+    /// An action, such as JavaScript or launching a program, is not allowed.
+    ForbiddenAction,
+
+    /// An annotation is not allowed, or has disallowed flags.
+    ForbiddenAnnotation,
+
+    /// Device colours are used that don't match the output intent.
+    ColorSpace,
+
+    /// The PDF version is too new for the standard.
+    PdfVersion,
+
+    /// Required document metadata is missing or invalid.
+    Metadata,
+
+    /// The output intent's ICC profile is missing or unsuitable.
+    OutputIntent,
+
+    /// A problem that this version of the crate doesn't know about.
+    Other,
+}
+
+impl ViolationKind {
+    fn from_c(kind: libc::c_int) -> Self {
+        match kind {
+            1 => ViolationKind::Encryption,
+            2 => ViolationKind::FontNotEmbedded,
+            3 => ViolationKind::ForbiddenAction,
+            4 => ViolationKind::ForbiddenAnnotation,
+            5 => ViolationKind::ColorSpace,
+            6 => ViolationKind::PdfVersion,
+            7 => ViolationKind::Metadata,
+            8 => ViolationKind::OutputIntent,
+            _ => ViolationKind::Other,
+        }
+    }
+}
+
+/// One way in which the output PDF fails to conform to a standard.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConformanceViolation {
+    /// What kind of problem this is.
+    pub kind: ViolationKind,
+
+    /// A description of the problem, such as the name of an unembedded font.
+    pub detail: String,
+}
+
+/// The error returned when the output PDF fails to conform to the requested
+/// standard.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConformanceError {
+    /// The standard that was requested.
+    pub conformance: PdfConformance,
+
+    /// The problems that were found, in the order they were found.
+    pub violations: Vec<ConformanceViolation>,
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the output does not conform to {}:", self.conformance)?;
+
+        for v in &self.violations {
+            write!(f, "\n  - {}", v.detail)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConformanceError {}
+
+/// What a reader of an encrypted PDF may do without the owner password.
+///
+/// These are the user access permissions of the PDF standard. PDF viewers are
+/// trusted to honor them; they don't change how the document is encrypted.
+/// The default allows printing, modifying, copying and annotating, as
+/// `xdvipdfmx` does by default.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PdfPermissions {
+    /// Print the document, possibly at a degraded quality.
+    pub print: bool,
+
+    /// Modify the document other than by the operations below.
+    pub modify: bool,
+
+    /// Copy or otherwise extract text and graphics.
+    pub copy: bool,
+
+    /// Add or modify annotations and fill in form fields.
+    pub annotate: bool,
+
+    /// Fill in form fields, even if `annotate` is not allowed.
+    pub fill_forms: bool,
+
+    /// Extract text and graphics for accessibility purposes.
+    pub accessibility: bool,
+
+    /// Assemble the document: insert, rotate or delete pages, and create
+    /// bookmarks or thumbnails.
+    pub assemble: bool,
+
+    /// Print the document at full quality.
+    pub print_high_quality: bool,
+}
+
+impl PdfPermissions {
+    /// Permissions that allow everything.
+    pub fn all() -> Self {
+        PdfPermissions {
+            print: true,
+            modify: true,
+            copy: true,
+            annotate: true,
+            fill_forms: true,
+            accessibility: true,
+            assemble: true,
+            print_high_quality: true,
+        }
+    }
+
+    /// Permissions that allow nothing.
+    pub fn none() -> Self {
+        PdfPermissions {
+            print: false,
+            modify: false,
+            copy: false,
+            annotate: false,
+            fill_forms: false,
+            accessibility: false,
+            assemble: false,
+            print_high_quality: false,
+        }
+    }
+
+    /// The value of the `/P` entry of the encryption dictionary.
+    fn bits(self) -> u32 {
+        let mut bits = 0;
+
+        for (allowed, bit) in [
+            (self.print, 1 << 2),
+            (self.modify, 1 << 3),
+            (self.copy, 1 << 4),
+            (self.annotate, 1 << 5),
+            (self.fill_forms, 1 << 8),
+            (self.accessibility, 1 << 9),
+            (self.assemble, 1 << 10),
+            (self.print_high_quality, 1 << 11),
+        ] {
+            if allowed {
+                bits |= bit;
+            }
+        }
+
+        bits
+    }
+
+    /// Allow just the named things, such as `print` or `copy`.
+    ///
+    /// The names are those of the fields of this struct. The name `all` allows
+    /// everything.
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> StdResult<Self, String> {
+        let mut perms = PdfPermissions::none();
+
+        for name in names {
+            match name.trim() {
+                "print" => perms.print = true,
+                "modify" => perms.modify = true,
+                "copy" => perms.copy = true,
+                "annotate" => perms.annotate = true,
+                "fill_forms" => perms.fill_forms = true,
+                "accessibility" => perms.accessibility = true,
+                "assemble" => perms.assemble = true,
+                "print_high_quality" => perms.print_high_quality = true,
+                "all" => perms = PdfPermissions::all(),
+                "" => {}
+                other => return Err(format!("unknown PDF permission `{other}`")),
+            }
+        }
+
+        Ok(perms)
+    }
+}
+
+impl Default for PdfPermissions {
+    fn default() -> Self {
+        PdfPermissions {
+            print: true,
+            modify: true,
+            copy: true,
+            annotate: true,
+            ..PdfPermissions::none()
+        }
+    }
+}
+
+impl FromStr for PdfPermissions {
+    type Err = String;
+
+    /// Parse a comma-separated list of names, as for
+    /// [`from_names`](Self::from_names).
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        PdfPermissions::from_names(s.split(','))
+    }
+}
+
+/// Settings for encrypting the output PDF.
+///
+/// The `Debug` output of this type doesn't show the passwords.
+#[derive(Clone, Eq, PartialEq)]
+pub struct PdfEncryption {
+    /// The password that gives full access to the document. If empty, the
+    /// user password is used.
+    pub owner_password: String,
+
+    /// The password needed to open the document. If empty, anyone can open
+    /// it, subject to the permissions.
+    pub user_password: String,
+
+    /// The length of the encryption key in bits: a multiple of 8 from 40 to
+    /// 128, or 256. 128-bit keys use AES, shorter ones RC4. 256-bit keys use
+    /// AES-256. The output PDF version is raised as far as the cipher needs,
+    /// up to 1.7 for 256-bit keys, rather than weakening the cipher.
+    pub key_bits: u32,
+
+    /// What a reader may do without the owner password.
+    pub permissions: PdfPermissions,
+}
+
+impl fmt::Debug for PdfEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |pw: &str| if pw.is_empty() { "" } else { "<redacted>" };
+
+        f.debug_struct("PdfEncryption")
+            .field("owner_password", &redacted(&self.owner_password))
+            .field("user_password", &redacted(&self.user_password))
+            .field("key_bits", &self.key_bits)
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+impl Default for PdfEncryption {
+    fn default() -> Self {
+        PdfEncryption {
+            owner_password: String::new(),
+            user_password: String::new(),
+            key_bits: 128,
+            permissions: PdfPermissions::default(),
+        }
+    }
+}
+
+impl PdfEncryption {
+    /// Check that the settings can be used by the engine.
+    fn validate(&self) -> Result<()> {
+        let kb = self.key_bits;
+
+        if !(kb == 256 || ((40..=128).contains(&kb) && kb.is_multiple_of(8))) {
+            bail!("unsupported PDF encryption key length {kb}: it must be a multiple of 8 from 40 to 128, or 256");
+        }
+
+        for (what, pw) in [
+            ("owner", &self.owner_password),
+            ("user", &self.user_password),
+        ] {
+            if pw.len() > MAX_PASSWORD_LEN {
+                bail!("the PDF {what} password may be at most {MAX_PASSWORD_LEN} bytes long");
+            }
+
+            if pw.contains('\0') {
+                bail!("the PDF {what} password may not contain NUL characters");
+            }
+
+            // Shorter keys use the older password scheme, which only works
+            // with ASCII text.
+            if kb != 256 && !pw.chars().all(|c| (' '..='~').contains(&c)) {
+                bail!("the PDF {what} password may only contain printable ASCII characters unless the key length is 256 bits");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The longest password that the engine accepts, in bytes.
+const MAX_PASSWORD_LEN: usize = 127;
+
 /// A struct for invoking the `xdvipdfmx` engine.
 ///
 /// This struct has a fairly straightforward "builder" interface: you create it,
@@ -39,6 +384,10 @@ pub struct XdvipdfmxEngine {
     enable_compression: bool,
     deterministic_tags: bool,
     build_date: SystemTime,
+    conformance: PdfConformance,
+    output_intent_profile: Option<String>,
+    output_condition: Option<String>,
+    encryption: Option<PdfEncryption>,
 }
 
 impl Default for XdvipdfmxEngine {
@@ -48,6 +397,10 @@ impl Default for XdvipdfmxEngine {
             enable_compression: true,
             deterministic_tags: false,
             build_date: SystemTime::UNIX_EPOCH,
+            conformance: PdfConformance::None,
+            output_intent_profile: None,
+            output_condition: None,
+            encryption: None,
         }
     }
 }
@@ -93,6 +446,46 @@ impl XdvipdfmxEngine {
         self
     }
 
+    /// Set the standard that the output PDF should conform to.
+    ///
+    /// The default is [`PdfConformance::None`]. Otherwise, the output gets an
+    /// output intent and XMP metadata, and [`process()`](Self::process) fails
+    /// with a [`ConformanceError`] if the document uses features that the
+    /// standard forbids, such as unembedded fonts or JavaScript.
+    pub fn conformance(&mut self, conformance: PdfConformance) -> &mut Self {
+        self.conformance = conformance;
+        self
+    }
+
+    /// Set the name of the ICC profile to embed as the output intent.
+    ///
+    /// The profile is looked up like any other input file. The default
+    /// depends on the conformance level; see
+    /// [`PdfConformance::default_output_intent`].
+    pub fn output_intent_profile(&mut self, profile: String) -> &mut Self {
+        self.output_intent_profile = Some(profile);
+        self
+    }
+
+    /// Set the identifier of the output condition that the output intent's
+    /// profile characterizes, such as `"FOGRA39"`.
+    pub fn output_condition(&mut self, condition: String) -> &mut Self {
+        self.output_condition = Some(condition);
+        self
+    }
+
+    /// Encrypt the output PDF with the given passwords and permissions.
+    ///
+    /// By default, the output is only encrypted if the document asks for it
+    /// with a `pdf:encrypt` special. These settings take precedence over any
+    /// such special. Encryption is not allowed by the PDF/A and PDF/X
+    /// standards, so it can't be combined with a [conformance
+    /// level](Self::conformance).
+    pub fn encryption(&mut self, encryption: PdfEncryption) -> &mut Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
     /// Run xdvipdfmx.
     ///
     /// The *launcher* parameter gives overarching environmental context in
@@ -103,6 +496,9 @@ impl XdvipdfmxEngine {
     /// file, containing extended features needed for XeTeX Unicode processing.
     ///
     /// The *pdf* parameter gives the name of the output PDF file to create.
+    ///
+    /// If a conformance level was set and the output doesn't meet it, the
+    /// returned error is a [`ConformanceError`].
     pub fn process(
         &mut self,
         launcher: &mut CoreBridgeLauncher,
@@ -114,6 +510,28 @@ impl XdvipdfmxEngine {
             ["paper_spec may not contain internal NULs"]
         );
 
+        let (default_profile, default_condition) =
+            self.conformance.default_output_intent().unwrap_or(("", ""));
+
+        let profile_str = atry!(
+            CString::new(self.output_intent_profile.as_deref().unwrap_or(default_profile));
+            ["output intent profile name may not contain internal NULs"]
+        );
+
+        let condition_str = atry!(
+            CString::new(self.output_condition.as_deref().unwrap_or(default_condition));
+            ["output condition may not contain internal NULs"]
+        );
+
+        let encryption = self.encryption.clone().unwrap_or_default();
+        encryption.validate()?;
+
+        // These can't contain NULs, as just checked.
+        let owner_password_str = CString::new(encryption.owner_password).unwrap();
+        let user_password_str = CString::new(encryption.user_password).unwrap();
+
+        let mut violations: Vec<ConformanceViolation> = Vec::new();
+
         let config = c_api::XdvipdfmxConfig {
             paperspec: paperspec_str.as_c_str().as_ptr(),
             enable_compression: u8::from(self.enable_compression),
@@ -123,6 +541,16 @@ impl XdvipdfmxEngine {
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .expect("invalid build date")
                 .as_secs(),
+            conformance: self.conformance.c_value(),
+            icc_profile: profile_str.as_ptr(),
+            output_condition: condition_str.as_ptr(),
+            report_violation: Some(c_api::report_violation),
+            violation_ctx: &mut violations as *mut Vec<ConformanceViolation> as *mut libc::c_void,
+            enable_encryption: u8::from(self.encryption.is_some()),
+            key_bits: encryption.key_bits as libc::c_int,
+            permissions: encryption.permissions.bits(),
+            owner_password: owner_password_str.as_ptr(),
+            user_password: user_password_str.as_ptr(),
         };
 
         let cdvi = CString::new(dvi)?;
@@ -141,7 +569,17 @@ impl XdvipdfmxEngine {
             } else {
                 Ok(())
             }
-        })
+        })?;
+
+        if !violations.is_empty() {
+            return Err(ConformanceError {
+                conformance: self.conformance,
+                violations,
+            }
+            .into());
+        }
+
+        Ok(())
     }
 }
 
@@ -149,8 +587,11 @@ impl XdvipdfmxEngine {
 pub mod c_api {
     // If you change the interfaces here, rerun cbindgen as described in the README!
 
+    use std::ffi::CStr;
     use tectonic_bridge_core::CoreBridgeState;
 
+    use super::{ConformanceViolation, ViolationKind};
+
     #[derive(Debug)]
     #[repr(C)]
     pub struct XdvipdfmxConfig {
@@ -158,6 +599,40 @@ pub mod c_api {
         pub enable_compression: libc::c_uchar,
         pub deterministic_tags: libc::c_uchar,
         pub build_date: u64,
+        pub conformance: libc::c_int,
+        pub icc_profile: *const libc::c_char,
+        pub output_condition: *const libc::c_char,
+        pub report_violation: Option<
+            unsafe extern "C" fn(
+                ctx: *mut libc::c_void,
+                kind: libc::c_int,
+                detail: *const libc::c_char,
+            ),
+        >,
+        pub violation_ctx: *mut libc::c_void,
+        pub enable_encryption: libc::c_uchar,
+        pub key_bits: libc::c_int,
+        pub permissions: u32,
+        pub owner_password: *const libc::c_char,
+        pub user_password: *const libc::c_char,
+    }
+
+    /// Collect a conformance violation reported by the engine. *ctx* must
+    /// point to a `Vec<ConformanceViolation>`.
+    pub unsafe extern "C" fn report_violation(
+        ctx: *mut libc::c_void,
+        kind: libc::c_int,
+        detail: *const libc::c_char,
+    ) {
+        // SAFETY: The engine passes back the context pointer that we gave it.
+        let violations = unsafe { &mut *(ctx as *mut Vec<ConformanceViolation>) };
+        // SAFETY: The engine always passes a valid C string.
+        let detail = unsafe { CStr::from_ptr(detail) };
+
+        violations.push(ConformanceViolation {
+            kind: ViolationKind::from_c(kind),
+            detail: detail.to_string_lossy().into_owned(),
+        });
     }
 
     #[allow(improper_ctypes)] // for CoreBridgeState
@@ -46,6 +46,7 @@
 #include "dpx-fontmap.h"
 #include "dpx-mem.h"
 #include "dpx-mpost.h"
+#include "dpx-pdfconform.h"
 #include "dpx-pdfdev.h"
 #include "dpx-pdfdoc.h"
 #include "dpx-pdfencrypt.h"
@@ -331,7 +332,9 @@ dvipdfmx_main (
   bool quiet,
   unsigned int verbose,
   time_t build_date,
-  const char *paperspec)
+  const char *paperspec,
+  const struct pdf_conform_setting *conform,
+  const struct pdf_enc_setting *encrypt)
 {
   double dvi2pts;
   const char *creator = NULL;
@@ -379,6 +382,7 @@ dvipdfmx_main (
   select_paper(paperspec);
 
   bookmark_open = 0;
+  do_encryption = 0;
   key_bits = 40;
   permission = 0x003C;
   font_dpi = 600;
@@ -435,6 +439,28 @@ dvipdfmx_main (
     settings.encrypt.oplain     = oplain;
   }
 
+  /* Tectonic: encryption requested through the API overrides any
+   * `pdf:encrypt` special. Keys longer than 40 bits need PDF 1.4, AES-128
+   * needs PDF 1.5 and AES-256 needs PDF 1.7. If the document asks for an
+   * older version, raise it rather than let check_version() in pdfencrypt
+   * quietly fall back to a weaker cipher. */
+  if (encrypt) {
+    int min_minor = 0;
+
+    settings.enable_encrypt = 1;
+    settings.encrypt = *encrypt;
+
+    if (encrypt->key_size == 256)
+      min_minor = 7;
+    else if (encrypt->key_size == 128)
+      min_minor = 5;
+    else if (encrypt->key_size > 40)
+      min_minor = 4;
+
+    if (settings.ver_major == 1 && settings.ver_minor < min_minor)
+      settings.ver_minor = min_minor;
+  }
+
   settings.object.compression_level = compress ? compression_level : 0;
 
   if (opt_flags & OPT_PDFOBJ_NO_OBJSTM) {
@@ -467,6 +493,8 @@ dvipdfmx_main (
   settings.device.precision   = pdfdecimaldigits;
   settings.device.ignore_colors = ignore_colors;
 
+  settings.conform = *conform;
+
   set_distiller_template(filter_template);
 
   /* Initialize PDF document creation routine. */
@@ -501,7 +529,9 @@ tt_engine_xdvipdfmx_main(
   const char *dviname,
   const char *pdfname
 ) {
-  int rv;
+  int rv, i;
+  struct pdf_conform_setting conform;
+  struct pdf_enc_setting encrypt;
 
   if (setjmp(*ttbc_global_engine_enter(api))) {
     ttbc_global_engine_exit();
@@ -511,6 +541,18 @@ tt_engine_xdvipdfmx_main(
   /* See ttstub_input_get_mtime() in tectonic_bridge_core about bridging time_t
    * over FFI. */
 
+  conform.conformance      = config->conformance;
+  conform.icc_profile      = config->icc_profile;
+  conform.output_condition = config->output_condition;
+
+  memset(&encrypt, 0, sizeof(struct pdf_enc_setting));
+  encrypt.key_size         = config->key_bits;
+  encrypt.permission       = config->permissions;
+  encrypt.oplain           = config->owner_password;
+  encrypt.uplain           = config->user_password;
+  encrypt.use_aes          = 1;
+  encrypt.encrypt_metadata = 1;
+
   rv = dvipdfmx_main(
     pdfname,
     dviname,
@@ -522,9 +564,20 @@ tt_engine_xdvipdfmx_main(
     false, /* quiet */
     0, /* verbose */
     (time_t) config->build_date,
-    config->paperspec
+    config->paperspec,
+    &conform,
+    config->enable_encryption ? &encrypt : NULL
   );
 
+  if (config->report_violation) {
+    for (i = 0; i < pdf_conform_num_violations(); i++) {
+      const char *detail = NULL;
+      int kind = pdf_conform_get_violation(i, &detail);
+
+      config->report_violation(config->violation_ctx, kind, detail);
+    }
+  }
+
   ttbc_global_engine_exit();
   return rv;
 }
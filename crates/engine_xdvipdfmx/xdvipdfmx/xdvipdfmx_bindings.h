@@ -11,6 +11,16 @@ typedef struct {
   unsigned char enable_compression;
   unsigned char deterministic_tags;
   uint64_t build_date;
+  int conformance;
+  const char *icc_profile;
+  const char *output_condition;
+  void (*report_violation)(void *ctx, int kind, const char *detail);
+  void *violation_ctx;
+  unsigned char enable_encryption;
+  int key_bits;
+  uint32_t permissions;
+  const char *owner_password;
+  const char *user_password;
 } XdvipdfmxConfig;
 
 #ifdef __cplusplus
@@ -127,12 +127,15 @@ impl TexEngine {
 
     /// Configure the engine to use "semantic pagination".
     ///
-    /// **Important:** this mode is essentially unimplemented.
-    ///
-    /// The goal of this mode is to set up the engine to create HTML-friendly
-    /// output by altering how paragraphs and pages are constructed. When this
-    /// mode is activated, the engine output type changes from XDV to SPX
-    /// (although the two formats are quite similar).
+    /// This mode sets up the engine to create HTML-friendly output by altering
+    /// how paragraphs and pages are constructed. Paragraphs are not broken
+    /// into lines, except where the document forces a break: each is set as
+    /// one line at its natural width, so that the output can reflow. Pages
+    /// are likewise only broken where the document forces a break, and the
+    /// page box given to the output routine is as tall as its contents. A page
+    /// that would grow taller than `\maxdimen` is broken early, with a
+    /// warning. When this mode is activated, the engine output type changes
+    /// from XDV to SPX (although the two formats are quite similar).
     ///
     /// The default is false.
     pub fn semantic_pagination(&mut self, enabled: bool) -> &mut Self {
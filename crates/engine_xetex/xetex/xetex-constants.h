@@ -10,6 +10,7 @@
 
 #define MIN_HALFWORD -0x0FFFFFFF
 #define MAX_HALFWORD  0x3FFFFFFF
+#define MAX_DIMEN     0x3FFFFFFF /* \maxdimen, "the largest legal dimension" */
 
 #define TEX_NULL     MIN_HALFWORD /* a null "pointer" */
 #define TEX_INFINITY 0x7FFFFFFF /* "the largest positive value that TeX knows" */
@@ -348,6 +348,7 @@ scaled_t delta;
 int synctex_enabled;
 bool used_tectonic_coda_tokens;
 bool semantic_pagination_enabled;
+bool packing_page_box;
 bool gave_char_warning_help;
 
 /* These ought to live in xetex-pagebuilder.c but are shared a lot: */
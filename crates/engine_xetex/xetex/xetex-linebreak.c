@@ -5,9 +5,10 @@
 /* Customizations for Tectonic:
  *
  * In semantic pagination mode, we don't actually linebreak paragraphs. We
- * just set them as one big line at its natural width no matter what. In the
- * context of this algorithm that means that try_break is always a no-op
- * unless we're trying to break at the very end of the paragraph.
+ * just set them as one big line at its natural width no matter what, except
+ * where the document forces a break (e.g. with \break). In the context of
+ * this algorithm that means that try_break is a no-op unless we're trying to
+ * break at the very end of the paragraph or at a forced break.
  */
 
 #include "xetex-core.h"
@@ -1415,11 +1416,6 @@ try_break(int32_t pi, small_number break_type)
     scaled_t shortfall;
     scaled_t g = 0;
 
-    /* Tectonic: no-op except at the end of the paragraph. We know we're at
-     * the very end of the paragraph when cur_p is TEX_NULL. */
-    if (semantic_pagination_enabled && cur_p != TEX_NULL)
-        return;
-
     if (abs(pi) >= INF_PENALTY) {
         if (pi > 0)
             return;
@@ -1427,6 +1423,12 @@ try_break(int32_t pi, small_number break_type)
         pi = EJECT_PENALTY;
     }
 
+    /* Tectonic: no-op except at the end of the paragraph and at forced
+     * breaks. We know we're at the very end of the paragraph when cur_p is
+     * TEX_NULL. */
+    if (semantic_pagination_enabled && cur_p != TEX_NULL && pi != EJECT_PENALTY)
+        return;
+
     no_break_yet = true;
     prev_r = ACTIVE_LIST;
     old_l = 0;
@@ -1733,7 +1735,8 @@ try_break(int32_t pi, small_number break_type)
          * break" */
 
         /* Tectonic: if we got here, we must be "considering" a linebreak
-         * at the very end of the paragraph. How amazing, it's a perfect fit!
+         * at the very end of the paragraph or at a forced break. How amazing,
+         * it's a perfect fit!
          */
 
         if (semantic_pagination_enabled) {
@@ -20,8 +20,86 @@ capture_to_diagnostic(ttbc_diagnostic_t *diagnostic)
     current_diagnostic = diagnostic;
 }
 
+// Encode part of the input buffer as a newly allocated UTF-8 string.
+static char *
+buffer_to_utf8(int32_t start, int32_t end)
+{
+    char *text = xmalloc((end > start ? end - start : 0) * 4 + 1);
+    int32_t i, j = 0;
+
+    for (i = start; i < end; i++) {
+        uint32_t c = buffer[i];
+
+        if (c >= 0x110000 || (c >= 0xD800 && c <= 0xDFFF))
+            c = 0xFFFD;
+
+        if (c < 0x80) {
+            text[j++] = c;
+        } else if (c < 0x800) {
+            text[j++] = 0xC0 | (c >> 6);
+            text[j++] = 0x80 | (c & 0x3F);
+        } else if (c < 0x10000) {
+            text[j++] = 0xE0 | (c >> 12);
+            text[j++] = 0x80 | ((c >> 6) & 0x3F);
+            text[j++] = 0x80 | (c & 0x3F);
+        } else {
+            text[j++] = 0xF0 | (c >> 18);
+            text[j++] = 0x80 | ((c >> 12) & 0x3F);
+            text[j++] = 0x80 | ((c >> 6) & 0x3F);
+            text[j++] = 0x80 | (c & 0x3F);
+        }
+    }
+
+    text[j] = '\0';
+    return text;
+}
+
+// Add the innermost line being read from a file as context: the text up to
+// the current position, then the rest of the line. This mirrors the "l.NNN"
+// lines printed by show_context.
 static void
-diagnostic_print_file_line(ttbc_diagnostic_t *diagnostic)
+diagnostic_add_source_context(ttbc_diagnostic_t *diagnostic)
+{
+    input_state_t *state = NULL;
+    int32_t p, j, loc;
+    char *text;
+
+    if (cur_input.state != TOKEN_LIST && cur_input.name > 17) {
+        state = &cur_input;
+    } else {
+        for (p = input_ptr - 1; p >= 0; p--) {
+            if (input_stack[p].state != TOKEN_LIST && input_stack[p].name > 17) {
+                state = &input_stack[p];
+                break;
+            }
+        }
+    }
+
+    if (state == NULL)
+        return;
+
+    if (buffer[state->limit] == INTPAR(end_line_char))
+        j = state->limit;
+    else
+        j = state->limit + 1;
+
+    loc = state->loc;
+    if (loc < state->start)
+        loc = state->start;
+    if (loc > j)
+        loc = j;
+
+    text = buffer_to_utf8(state->start, loc);
+    ttbc_diag_add_context(diagnostic, text);
+    free(text);
+
+    text = buffer_to_utf8(loc, j);
+    ttbc_diag_add_context(diagnostic, text);
+    free(text);
+}
+
+static void
+diagnostic_set_location(ttbc_diagnostic_t *diagnostic)
 {
     // Add file/line number information
     // This duplicates logic from print_file_line
@@ -30,25 +108,25 @@ diagnostic_print_file_line(ttbc_diagnostic_t *diagnostic)
     while (level > 0 && full_source_filename_stack[level] == 0)
         level--;
 
-    if (level == 0) {
-        ttbc_diag_append(diagnostic, "!");
-    } else {
+    if (level > 0) {
         int32_t source_line = line;
         if (level != in_open) {
             source_line = line_stack[level + 1];
         }
 
         char* filename = gettexstring(full_source_filename_stack[level]);
-        ttstub_diag_printf(diagnostic, "%s:%d: ", filename, source_line);
+        ttbc_diag_set_location(diagnostic, filename, source_line);
         free(filename);
     }
+
+    diagnostic_add_source_context(diagnostic);
 }
 
 ttbc_diagnostic_t *
 diagnostic_begin_capture_warning_here(void)
 {
     ttbc_diagnostic_t *warning = ttbc_diag_begin_warning();
-    diagnostic_print_file_line(warning);
+    diagnostic_set_location(warning);
     capture_to_diagnostic(warning);
     return warning;
 }
@@ -58,7 +136,7 @@ ttbc_diagnostic_t *
 error_here_with_diagnostic(const char* message)
 {
     ttbc_diagnostic_t *error = ttbc_diag_begin_error();
-    diagnostic_print_file_line(error);
+    diagnostic_set_location(error);
     ttstub_diag_printf(error, "%s", message);
 
     if (file_line_error_style_p)
@@ -5,11 +5,15 @@
 
 /* Customizations for Tectonic:
  *
- * In semantic pagination mode, we don't run the pagebuilder routine. We just
- * directly invoke the shipout code, which in turn writes out the output vlist
- * without worrying about pages. We also behave as if holding_inserts is
- * always true: inserts are kept in the page vlist rather than being
- * processed.
+ * In semantic pagination mode, pages are only broken where the document
+ * forces a break (a penalty of -10000 or less, as from \newpage), never
+ * because the page is full, and the page is packed into box 255 at its
+ * natural height rather than \vsize. The one exception is a page that would
+ * grow taller than \maxdimen: it is broken at the last legal breakpoint
+ * before that, with a warning. The output routine still runs, so
+ * macro packages can do their usual shipout processing. We also behave as if
+ * holding_inserts is always true: inserts are kept in the page vlist rather
+ * than being processed.
  */
 
 #include "xetex-core.h"
@@ -302,7 +306,9 @@ fire_up(int32_t c)
     INTPAR(vbadness) = INF_BAD;
     save_vfuzz = DIMENPAR(vfuzz);
     DIMENPAR(vfuzz) = MAX_HALFWORD;
+    packing_page_box = true;
     BOX_REG(255) = vpackage(LLIST_link(PAGE_HEAD), best_size, EXACTLY, page_max_depth);
+    packing_page_box = false;
     INTPAR(vbadness) = save_vbadness;
     DIMENPAR(vfuzz) = save_vfuzz;
 
@@ -667,7 +673,48 @@ build_page(void)
          * there's already content on the page -- so this might be a place to
          * break the page. */
 
-        if (pi < INF_PENALTY) {
+        if (semantic_pagination_enabled) {
+            /* Tectonic: in semantic pagination mode, only forced breaks end
+             * the page, and the page is as tall as its contents. Every legal
+             * breakpoint becomes the champion while the page is no taller
+             * than \maxdimen, so that a page that never reaches a forced
+             * break can be broken at the last one that keeps it in range,
+             * rather than growing until its height overflows. */
+            if (pi < INF_PENALTY) {
+                bool too_tall = page_so_far[1] > MAX_DIMEN;
+
+                if (!too_tall || least_page_cost == AWFUL_BAD) {
+                    best_page_break = p;
+                    best_size = page_so_far[1] > MAX_DIMEN ? MAX_DIMEN : page_so_far[1];
+                    least_page_cost = 0;
+                    r = LLIST_link(PAGE_INS_HEAD);
+
+                    while (r != PAGE_INS_HEAD) {
+                        PAGE_INS_NODE_best_ins_ptr(r) = PAGE_INS_NODE_last_ins_ptr(r);
+                        r = LLIST_link(r);
+                    }
+                }
+
+                if (too_tall) {
+                    print_nl_cstr("Warning: ");
+                    diagnostic_begin_capture_warning_here();
+                    print_cstr("page grew taller than \\maxdimen without a forced break, so it was broken early");
+                    print_ln();
+
+                    if (history == HISTORY_SPOTLESS)
+                        history = HISTORY_WARNING_ISSUED;
+
+                    capture_to_diagnostic(NULL);
+                }
+
+                if (too_tall || pi <= EJECT_PENALTY) {
+                    fire_up(p);
+                    if (output_active)
+                        return;
+                    goto done;
+                }
+            }
+        } else if (pi < INF_PENALTY) {
             /*1042: "Compute the badness b of the current page, using
              * awful_bad if the box is too full." */
             if (page_so_far[1] < page_so_far[0]) {
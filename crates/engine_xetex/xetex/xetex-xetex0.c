@@ -12678,6 +12678,14 @@ int32_t vpackage(int32_t p, scaled_t h, small_number m, scaled_t l)
         mem[r + 2].b32.s1 = d;
     if (m == ADDITIONAL)
         h = x + h;
+
+    /* Tectonic: in semantic pagination mode, the page box grows to fit its
+     * contents rather than being overfull, up to \maxdimen. Other vboxes are
+     * packed as usual. */
+    if (semantic_pagination_enabled && packing_page_box && x - h > total_shrink[NORMAL]
+        && total_shrink[FIL] == 0 && total_shrink[FILL] == 0 && total_shrink[FILLL] == 0)
+        h = x > MAX_DIMEN ? MAX_DIMEN : x;
+
     mem[r + 3].b32.s1 = h;
     x = h - x;
     if (x == 0) {
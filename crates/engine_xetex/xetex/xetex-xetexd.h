@@ -722,6 +722,7 @@ extern scaled_t delta;
 extern int synctex_enabled;
 extern bool used_tectonic_coda_tokens;
 extern bool semantic_pagination_enabled;
+extern bool packing_page_box;
 extern bool gave_char_warning_help;
 
 /*:1683*/
@@ -1064,8 +1065,8 @@ void flush_math(void);
 //
 //     capture_to_diagnostic(NULL);
 //
-// The current file and line number information are prefixed to the captured
-// output.
+// The current file and line number, and the text of the line being read, are
+// attached to the diagnostic as structured location information.
 //
 // NOTE: the only reason there isn't also an _error_ version of this function is
 // that we haven't yet wired up anything that uses it.
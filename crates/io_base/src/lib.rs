@@ -13,9 +13,11 @@
 use sha2::Digest;
 use std::{
     borrow::Cow,
+    cell::RefCell,
     fs::File,
     io::{self, Cursor, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    rc::Rc,
 };
 use tectonic_errors::{
     anyhow::{bail, ensure},
@@ -586,6 +588,60 @@ impl<P: IoProvider + ?Sized> IoProvider for Box<P> {
     }
 }
 
+impl<P: IoProvider + ?Sized> IoProvider for Rc<RefCell<P>> {
+    fn output_open_name(&mut self, name: &str) -> OpenResult<OutputHandle> {
+        self.borrow_mut().output_open_name(name)
+    }
+
+    fn output_open_stdout(&mut self) -> OpenResult<OutputHandle> {
+        self.borrow_mut().output_open_stdout()
+    }
+
+    fn input_open_name(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<InputHandle> {
+        self.borrow_mut().input_open_name(name, status)
+    }
+
+    fn input_open_name_with_abspath(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        self.borrow_mut().input_open_name_with_abspath(name, status)
+    }
+
+    fn input_open_primary(&mut self, status: &mut dyn StatusBackend) -> OpenResult<InputHandle> {
+        self.borrow_mut().input_open_primary(status)
+    }
+
+    fn input_open_primary_with_abspath(
+        &mut self,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        self.borrow_mut().input_open_primary_with_abspath(status)
+    }
+
+    fn input_open_format(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<InputHandle> {
+        self.borrow_mut().input_open_format(name, status)
+    }
+
+    fn write_format(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        status: &mut dyn StatusBackend,
+    ) -> Result<()> {
+        self.borrow_mut().write_format(name, data, status)
+    }
+}
+
 // Some generically helpful InputFeatures impls
 
 impl InputFeatures for Cursor<Vec<u8>> {
@@ -602,6 +658,20 @@ impl InputFeatures for Cursor<Vec<u8>> {
     }
 }
 
+impl InputFeatures for Cursor<Rc<[u8]>> {
+    fn get_size(&mut self) -> Result<usize> {
+        Ok(self.get_ref().len())
+    }
+
+    fn get_unix_mtime(&mut self) -> Result<Option<i64>> {
+        Ok(None)
+    }
+
+    fn try_seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        Ok(self.seek(pos)?)
+    }
+}
+
 // Helpful.
 
 /// Try to open a file on the fileystem, returning an `OpenResult` type
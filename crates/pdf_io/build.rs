@@ -110,6 +110,7 @@ fn main() {
         "pdf_io/dpx-numbers.c",
         "pdf_io/dpx-otl_opt.c",
         "pdf_io/dpx-pdfcolor.c",
+        "pdf_io/dpx-pdfconform.c",
         "pdf_io/dpx-pdfdev.c",
         "pdf_io/dpx-pdfdoc.c",
         "pdf_io/dpx-pdfdraw.c",
@@ -41,6 +41,7 @@
 #include "dpx-dpxutil.h"
 #include "dpx-error.h"
 #include "dpx-mem.h"
+#include "dpx-pdfconform.h"
 #include "dpx-pdfobj.h"
 
 #define PDF_CID_SUPPORT_MIN 2
@@ -368,6 +369,11 @@ pdf_font_cidfont_lookup_cache (pdf_font *fonts, int count, const char *map_name,
   has_csi   = get_cidsysinfo(&opt.csi, map_name, fmap_opt);
   opt.stemv = fmap_opt->stemv;
 
+  if (!opt.embed) {
+    pdf_conform_violation(PDF_VIOLATION_FONT_NOT_EMBEDDED,
+                          "font \"%s\" is not embedded", map_name);
+  }
+
   if (!has_csi && cmap_csi) {
     /*
      * No CIDSystemInfo supplied explicitly. Copy from CMap's one if available.
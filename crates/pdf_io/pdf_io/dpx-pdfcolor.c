@@ -36,6 +36,7 @@
 #include "dpx-error.h"
 #include "dpx-mem.h"
 #include "dpx-numbers.h"
+#include "dpx-pdfconform.h"
 #include "dpx-pdfdev.h"
 #include "dpx-pdfdoc.h"
 #include "dpx-pdfresource.h"
@@ -259,6 +260,7 @@ pdf_color_set_color (const pdf_color *color, char *buffer, size_t buffer_len,cha
     break;
   case PDF_COLORSPACE_TYPE_DEVICERGB:
     {
+      pdf_conform_note_colorspace(3);
       len += sprintf(buffer+len, " /DeviceRGB %c%c", 'C' | mask, 'S' | mask);
       for (i = 0; i < color->num_components; i++) {
         len += sprintf(buffer+len, " %g", ROUND(color->values[i], 0.001));
@@ -268,6 +270,7 @@ pdf_color_set_color (const pdf_color *color, char *buffer, size_t buffer_len,cha
     break;
   case PDF_COLORSPACE_TYPE_DEVICECMYK:
     {
+      pdf_conform_note_colorspace(4);
       len += sprintf(buffer+len, " /DeviceCMYK %c%c", 'C' | mask, 'S' | mask);
       for (i = 0; i < color->num_components; i++) {
         len += sprintf(buffer+len, " %g", ROUND(color->values[i], 0.001));
@@ -285,6 +288,7 @@ pdf_color_set_color (const pdf_color *color, char *buffer, size_t buffer_len,cha
     break;
   case PDF_COLORSPACE_TYPE_RGB:
     {
+      pdf_conform_note_colorspace(3);
       for (i = 0; i < color->num_components; i++) {
         len += sprintf(buffer+len, " %g", ROUND(color->values[i], 0.001));
       }
@@ -293,6 +297,7 @@ pdf_color_set_color (const pdf_color *color, char *buffer, size_t buffer_len,cha
     break;
   case PDF_COLORSPACE_TYPE_CMYK:
     {
+      pdf_conform_note_colorspace(4);
       for (i = 0; i < color->num_components; i++) {
         len += sprintf(buffer+len, " %g", ROUND(color->values[i], 0.001));
       }
@@ -0,0 +1,737 @@
+/* This is dvipdfmx, an eXtended version of dvipdfm by Mark A. Wicks.
+
+    Copyright (C) 2026 the Tectonic Project
+
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 2 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307 USA.
+*/
+
+/*
+ * PDF/A-2b and PDF/X-4 output.
+ *
+ * We don't try to repair documents: apart from the output intent, the XMP
+ * metadata, and a few dictionary entries with obvious defaults, anything
+ * that the standard forbids is recorded as a violation and left as is.
+ */
+
+#include "dpx-pdfconform.h"
+
+#include <math.h>
+#include <stdarg.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "tectonic_bridge_core.h"
+#include "dpx-dpxutil.h"
+#include "dpx-error.h"
+#include "dpx-mem.h"
+#include "dpx-numbers.h"
+
+struct conform_violation
+{
+  int   kind;
+  char *detail;
+};
+
+static struct {
+  int   level;
+  char *icc_profile;
+  char *output_condition;
+  char  document_id[48];
+
+  /* Device colour spaces used, as masks of 1 << num_components. */
+  int   used_components;
+  int   intent_components;
+
+  int   num_violations;
+  int   max_violations;
+  struct conform_violation *violations;
+} conform;
+
+static const char *forbidden_actions[] = {
+  "GoTo3DView", "Hide", "ImportData", "JavaScript", "Launch", "Movie",
+  "Rendition", "ResetForm", "SetOCGState", "Sound", "Trans",
+  NULL
+};
+
+static const char *forbidden_annots[] = {
+  "3D", "FileAttachment", "Movie", "Screen", "Sound",
+  NULL
+};
+
+static int
+name_in_list (pdf_obj *name, const char **list)
+{
+  int i;
+
+  if (!PDF_OBJ_NAMETYPE(name))
+    return 0;
+
+  for (i = 0; list[i] != NULL; i++) {
+    if (streq_ptr(pdf_name_value(name), list[i]))
+      return 1;
+  }
+
+  return 0;
+}
+
+static const char *
+conform_name (void)
+{
+  return (conform.level == PDF_CONFORMANCE_PDFX_4) ? "PDF/X-4" : "PDF/A-2b";
+}
+
+static char *
+copy_string (const char *s)
+{
+  char *r;
+
+  if (!s)
+    return NULL;
+
+  r = NEW(strlen(s) + 1, char);
+  strcpy(r, s);
+  return r;
+}
+
+void
+pdf_conform_init (const struct pdf_conform_setting *settings,
+                  int ver_major, int ver_minor, const unsigned char *id)
+{
+  int i;
+
+  for (i = 0; i < conform.num_violations; i++)
+    free(conform.violations[i].detail);
+  free(conform.violations);
+  free(conform.icc_profile);
+  free(conform.output_condition);
+  memset(&conform, 0, sizeof(conform));
+
+  conform.level = settings->conformance;
+  if (conform.level == PDF_CONFORMANCE_NONE)
+    return;
+
+  conform.icc_profile      = copy_string(settings->icc_profile);
+  conform.output_condition = copy_string(settings->output_condition);
+
+  /* XMP wants UUIDs; the file identifier is as good a source as any. */
+  snprintf(conform.document_id, sizeof(conform.document_id),
+           "uuid:%02x%02x%02x%02x-%02x%02x-%02x%02x-%02x%02x-%02x%02x%02x%02x%02x%02x",
+           id[0], id[1], id[2], id[3], id[4], id[5], id[6], id[7],
+           id[8], id[9], id[10], id[11], id[12], id[13], id[14], id[15]);
+
+  if (conform.level == PDF_CONFORMANCE_PDFA_2B && ver_major * 10 + ver_minor > 17) {
+    pdf_conform_violation(PDF_VIOLATION_PDF_VERSION,
+                          "PDF/A-2 requires PDF version 1.7 or earlier, not %d.%d",
+                          ver_major, ver_minor);
+  } else if (conform.level == PDF_CONFORMANCE_PDFX_4 && ver_major * 10 + ver_minor > 16) {
+    pdf_conform_violation(PDF_VIOLATION_PDF_VERSION,
+                          "PDF/X-4 requires PDF version 1.6 or earlier, not %d.%d",
+                          ver_major, ver_minor);
+  }
+}
+
+int
+pdf_conform_level (void)
+{
+  return conform.level;
+}
+
+void
+pdf_conform_violation (int kind, const char *fmt, ...)
+{
+  char    detail[1024];
+  va_list argp;
+  int     i;
+
+  if (conform.level == PDF_CONFORMANCE_NONE)
+    return;
+
+  va_start(argp, fmt);
+  vsnprintf(detail, sizeof(detail), fmt, argp);
+  va_end(argp);
+
+  /* The same problem tends to come up over and over again, e.g. for every
+   * link with a forbidden action. Once is enough. */
+  for (i = 0; i < conform.num_violations; i++) {
+    if (conform.violations[i].kind == kind &&
+        streq_ptr(conform.violations[i].detail, detail))
+      return;
+  }
+
+  if (conform.num_violations >= conform.max_violations) {
+    conform.max_violations += 16;
+    conform.violations = RENEW(conform.violations, conform.max_violations,
+                               struct conform_violation);
+  }
+
+  conform.violations[conform.num_violations].kind   = kind;
+  conform.violations[conform.num_violations].detail = copy_string(detail);
+  conform.num_violations++;
+}
+
+int
+pdf_conform_num_violations (void)
+{
+  return conform.num_violations;
+}
+
+int
+pdf_conform_get_violation (int idx, const char **detail)
+{
+  if (idx < 0 || idx >= conform.num_violations)
+    return 0;
+
+  *detail = conform.violations[idx].detail;
+  return conform.violations[idx].kind;
+}
+
+void
+pdf_conform_note_colorspace (int num_components)
+{
+  if (conform.level == PDF_CONFORMANCE_NONE)
+    return;
+
+  conform.used_components |= (1 << num_components);
+}
+
+void
+pdf_conform_check_colorspace (pdf_obj *colorspace)
+{
+  if (!PDF_OBJ_NAMETYPE(colorspace))
+    return;
+
+  if (streq_ptr(pdf_name_value(colorspace), "DeviceRGB")) {
+    pdf_conform_note_colorspace(3);
+  } else if (streq_ptr(pdf_name_value(colorspace), "DeviceCMYK")) {
+    pdf_conform_note_colorspace(4);
+  }
+}
+
+void
+pdf_conform_check_action (pdf_obj *action, const char *context)
+{
+  pdf_obj *next;
+
+  if (conform.level == PDF_CONFORMANCE_NONE || !PDF_OBJ_DICTTYPE(action))
+    return;
+
+  if (name_in_list(pdf_lookup_dict(action, "S"), forbidden_actions)) {
+    pdf_conform_violation(PDF_VIOLATION_FORBIDDEN_ACTION,
+                          "%s actions are not allowed (found in %s)",
+                          pdf_name_value(pdf_lookup_dict(action, "S")), context);
+  }
+
+  next = pdf_lookup_dict(action, "Next");
+  if (PDF_OBJ_DICTTYPE(next)) {
+    pdf_conform_check_action(next, context);
+  } else if (PDF_OBJ_ARRAYTYPE(next)) {
+    unsigned int i;
+
+    for (i = 0; i < pdf_array_length(next); i++)
+      pdf_conform_check_action(pdf_get_array(next, i), context);
+  }
+}
+
+void
+pdf_conform_check_annot (pdf_obj *annot_dict)
+{
+  pdf_obj *subtype, *flags;
+
+  if (conform.level == PDF_CONFORMANCE_NONE)
+    return;
+
+  subtype = pdf_lookup_dict(annot_dict, "Subtype");
+  if (name_in_list(subtype, forbidden_annots)) {
+    pdf_conform_violation(PDF_VIOLATION_FORBIDDEN_ANNOTATION,
+                          "%s annotations are not allowed",
+                          pdf_name_value(subtype));
+  }
+
+  if (pdf_lookup_dict(annot_dict, "AA")) {
+    pdf_conform_violation(PDF_VIOLATION_FORBIDDEN_ACTION,
+                          "annotations may not have additional actions (/AA)");
+  }
+
+  pdf_conform_check_action(pdf_lookup_dict(annot_dict, "A"), "an annotation");
+
+  /* PDF/A: annotations other than pop-ups must print, and be visible. */
+  if (conform.level == PDF_CONFORMANCE_PDFA_2B &&
+      !(PDF_OBJ_NAMETYPE(subtype) && streq_ptr(pdf_name_value(subtype), "Popup"))) {
+    flags = pdf_lookup_dict(annot_dict, "F");
+
+    if (!flags) {
+      pdf_add_dict(annot_dict, pdf_new_name("F"), pdf_new_number(4));
+    } else if (!PDF_OBJ_NUMBERTYPE(flags) ||
+               ((int) pdf_number_value(flags) & (1|2|4|32)) != 4) {
+      pdf_conform_violation(PDF_VIOLATION_FORBIDDEN_ANNOTATION,
+                            "annotations must be printable and visible (/F flags)");
+    }
+  }
+}
+
+void
+pdf_conform_check_page (pdf_obj *page_obj, const pdf_rect *mediabox)
+{
+  pdf_obj *trimbox;
+
+  /* PDF/X: every page needs a TrimBox or an ArtBox. Without one, the whole
+   * page is the finished product. */
+  if (conform.level != PDF_CONFORMANCE_PDFX_4)
+    return;
+
+  if (pdf_lookup_dict(page_obj, "TrimBox") || pdf_lookup_dict(page_obj, "ArtBox"))
+    return;
+
+  trimbox = pdf_new_array();
+  pdf_add_array(trimbox, pdf_new_number(ROUND(mediabox->llx, 0.01)));
+  pdf_add_array(trimbox, pdf_new_number(ROUND(mediabox->lly, 0.01)));
+  pdf_add_array(trimbox, pdf_new_number(ROUND(mediabox->urx, 0.01)));
+  pdf_add_array(trimbox, pdf_new_number(ROUND(mediabox->ury, 0.01)));
+  pdf_add_dict(page_obj, pdf_new_name("TrimBox"), trimbox);
+}
+
+/*
+ * Output intent
+ */
+
+static void
+add_output_intent (pdf_obj *catalog)
+{
+  rust_input_handle_t handle;
+  size_t   size;
+  char    *data;
+  int      num_components;
+  pdf_obj *profile, *intent, *intents;
+  const char *name = conform.icc_profile;
+
+  if (!name || !name[0]) {
+    pdf_conform_violation(PDF_VIOLATION_OUTPUT_INTENT,
+                          "no ICC profile was given for the output intent");
+    return;
+  }
+
+  handle = ttstub_input_open(name, TTBC_FILE_FORMAT_PICT, 0);
+  if (!handle) {
+    pdf_conform_violation(PDF_VIOLATION_OUTPUT_INTENT,
+                          "couldn't open the output intent's ICC profile \"%s\"", name);
+    return;
+  }
+
+  size = ttstub_input_get_size(handle);
+  data = NEW(size + 1, char);
+
+  if (size < 128 || ttstub_input_read(handle, data, size) != (ssize_t) size ||
+      memcmp(data + 36, "acsp", 4) != 0) {
+    pdf_conform_violation(PDF_VIOLATION_OUTPUT_INTENT,
+                          "\"%s\" is not a valid ICC profile", name);
+    ttstub_input_close(handle);
+    free(data);
+    return;
+  }
+
+  ttstub_input_close(handle);
+
+  if (!memcmp(data + 16, "RGB ", 4)) {
+    num_components = 3;
+  } else if (!memcmp(data + 16, "CMYK", 4)) {
+    num_components = 4;
+  } else if (!memcmp(data + 16, "GRAY", 4)) {
+    num_components = 1;
+  } else {
+    pdf_conform_violation(PDF_VIOLATION_OUTPUT_INTENT,
+                          "the ICC profile \"%s\" is not for a Gray, RGB, or CMYK colour space",
+                          name);
+    free(data);
+    return;
+  }
+
+  if (conform.level == PDF_CONFORMANCE_PDFX_4 && memcmp(data + 12, "prtr", 4) != 0) {
+    pdf_conform_violation(PDF_VIOLATION_OUTPUT_INTENT,
+                          "PDF/X requires an output (printer) ICC profile, but \"%s\" isn't one",
+                          name);
+  }
+
+  conform.intent_components = num_components;
+
+  profile = pdf_new_stream(STREAM_COMPRESS);
+  pdf_add_dict(pdf_stream_dict(profile), pdf_new_name("N"), pdf_new_number(num_components));
+  pdf_add_stream(profile, data, (int) size);
+  free(data);
+
+  intent = pdf_new_dict();
+  pdf_add_dict(intent, pdf_new_name("Type"), pdf_new_name("OutputIntent"));
+  pdf_add_dict(intent, pdf_new_name("S"),
+               pdf_new_name(conform.level == PDF_CONFORMANCE_PDFX_4 ? "GTS_PDFX" : "GTS_PDFA1"));
+  if (conform.output_condition) {
+    pdf_add_dict(intent, pdf_new_name("OutputConditionIdentifier"),
+                 pdf_new_string(conform.output_condition, strlen(conform.output_condition)));
+    pdf_add_dict(intent, pdf_new_name("Info"),
+                 pdf_new_string(conform.output_condition, strlen(conform.output_condition)));
+  } else {
+    pdf_add_dict(intent, pdf_new_name("OutputConditionIdentifier"),
+                 pdf_new_string("Custom", 6));
+  }
+  pdf_add_dict(intent, pdf_new_name("RegistryName"),
+               pdf_new_string("http://www.color.org", 20));
+  pdf_add_dict(intent, pdf_new_name("DestOutputProfile"), pdf_ref_obj(profile));
+  pdf_release_obj(profile);
+
+  intents = pdf_new_array();
+  pdf_add_array(intents, intent);
+  pdf_add_dict(catalog, pdf_new_name("OutputIntents"), intents);
+}
+
+static void
+check_device_colors (void)
+{
+  if (!conform.intent_components)
+    return; /* Already complained about the output intent. */
+
+  if ((conform.used_components & (1 << 3)) && conform.intent_components != 3) {
+    pdf_conform_violation(PDF_VIOLATION_COLOR_SPACE,
+                          "DeviceRGB colours are used, but the output intent isn't RGB");
+  }
+
+  if ((conform.used_components & (1 << 4)) && conform.intent_components != 4) {
+    pdf_conform_violation(PDF_VIOLATION_COLOR_SPACE,
+                          "DeviceCMYK colours are used, but the output intent isn't CMYK");
+  }
+}
+
+/*
+ * XMP metadata
+ */
+
+struct xmp_buf
+{
+  char    *data;
+  uint32_t len, max;
+};
+
+static void
+xmp_append_n (struct xmp_buf *b, const char *s, uint32_t n)
+{
+  if (b->len + n + 1 > b->max) {
+    b->max = b->len + n + 1024;
+    b->data = RENEW(b->data, b->max, char);
+  }
+  memcpy(b->data + b->len, s, n);
+  b->len += n;
+  b->data[b->len] = '\0';
+}
+
+static void
+xmp_append (struct xmp_buf *b, const char *s)
+{
+  xmp_append_n(b, s, (uint32_t) strlen(s));
+}
+
+static void
+xmp_append_char (struct xmp_buf *b, uint32_t c)
+{
+  char u[4];
+
+  switch (c) {
+  case '<': xmp_append(b, "&lt;");   return;
+  case '>': xmp_append(b, "&gt;");   return;
+  case '&': xmp_append(b, "&amp;");  return;
+  case '"': xmp_append(b, "&quot;"); return;
+  }
+
+  /* Control characters aren't allowed in XML. */
+  if ((c < 0x20 && c != '\t' && c != '\n' && c != '\r') || c == 0xfffe || c == 0xffff)
+    return;
+
+  if (c < 0x80) {
+    u[0] = (char) c;
+    xmp_append_n(b, u, 1);
+  } else if (c < 0x800) {
+    u[0] = (char) (0xc0 | (c >> 6));
+    u[1] = (char) (0x80 | (c & 0x3f));
+    xmp_append_n(b, u, 2);
+  } else if (c < 0x10000) {
+    u[0] = (char) (0xe0 | (c >> 12));
+    u[1] = (char) (0x80 | ((c >> 6) & 0x3f));
+    u[2] = (char) (0x80 | (c & 0x3f));
+    xmp_append_n(b, u, 3);
+  } else {
+    u[0] = (char) (0xf0 | (c >> 18));
+    u[1] = (char) (0x80 | ((c >> 12) & 0x3f));
+    u[2] = (char) (0x80 | ((c >> 6) & 0x3f));
+    u[3] = (char) (0x80 | (c & 0x3f));
+    xmp_append_n(b, u, 4);
+  }
+}
+
+/* The characters of PDFDocEncoding that differ from Latin-1. */
+static const uint16_t pdfdoc_18_1f[8] = {
+  0x02d8, 0x02c7, 0x02c6, 0x02d9, 0x02dd, 0x02db, 0x02da, 0x02dc
+};
+static const uint16_t pdfdoc_80_a0[33] = {
+  0x2022, 0x2020, 0x2021, 0x2026, 0x2014, 0x2013, 0x0192, 0x2044,
+  0x2039, 0x203a, 0x2212, 0x2030, 0x201e, 0x201c, 0x201d, 0x2018,
+  0x2019, 0x201a, 0x2122, 0xfb01, 0xfb02, 0x0141, 0x0152, 0x0160,
+  0x0178, 0x017d, 0x0131, 0x0142, 0x0153, 0x0161, 0x017e, 0xfffd,
+  0x20ac
+};
+
+/* Append a PDF text string, which is in either PDFDocEncoding or UTF-16BE
+ * with a byte order mark, as escaped UTF-8. */
+static void
+xmp_append_text (struct xmp_buf *b, pdf_obj *string)
+{
+  const unsigned char *s = pdf_string_value(string);
+  unsigned int len = pdf_string_length(string), i;
+
+  if (len >= 2 && s[0] == 0xfe && s[1] == 0xff) {
+    for (i = 2; i + 1 < len; i += 2) {
+      uint32_t c = (s[i] << 8) | s[i + 1];
+
+      if (c >= 0xd800 && c < 0xdc00 && i + 3 < len) {
+        uint32_t lo = (s[i + 2] << 8) | s[i + 3];
+
+        if (lo >= 0xdc00 && lo < 0xe000) {
+          c = 0x10000 + ((c - 0xd800) << 10) + (lo - 0xdc00);
+          i += 2;
+        }
+      }
+
+      if (c >= 0xd800 && c < 0xe000)
+        c = 0xfffd;
+
+      xmp_append_char(b, c);
+    }
+  } else {
+    for (i = 0; i < len; i++) {
+      if (s[i] >= 0x18 && s[i] <= 0x1f)
+        xmp_append_char(b, pdfdoc_18_1f[s[i] - 0x18]);
+      else if (s[i] >= 0x80 && s[i] <= 0xa0)
+        xmp_append_char(b, pdfdoc_80_a0[s[i] - 0x80]);
+      else
+        xmp_append_char(b, s[i]);
+    }
+  }
+}
+
+/* Append a PDF date string, `D:YYYYMMDDHHmmSSOHH'mm'`, in ISO 8601 form. */
+static void
+xmp_append_date (struct xmp_buf *b, pdf_obj *string)
+{
+  const char *s = pdf_string_value(string);
+  unsigned int len = pdf_string_length(string), n, i;
+  int  f[6] = { 0, 1, 1, 0, 0, 0 };
+  char out[32];
+
+  if (len >= 2 && s[0] == 'D' && s[1] == ':') {
+    s += 2;
+    len -= 2;
+  }
+
+  for (n = 0; n < len && n < 14 && s[n] >= '0' && s[n] <= '9'; n++)
+    ;
+
+  if (n < 4)
+    return;
+
+  f[0] = (s[0] - '0') * 1000 + (s[1] - '0') * 100 + (s[2] - '0') * 10 + (s[3] - '0');
+  for (i = 1; i < 6 && 4 + 2 * i <= n; i++)
+    f[i] = (s[2 + 2 * i] - '0') * 10 + (s[3 + 2 * i] - '0');
+
+  snprintf(out, sizeof(out), "%04d-%02d-%02dT%02d:%02d:%02d",
+           f[0], f[1], f[2], f[3], f[4], f[5]);
+  xmp_append(b, out);
+
+  s += n;
+  len -= n;
+
+  if (len >= 1 && s[0] == 'Z') {
+    xmp_append(b, "Z");
+  } else if (len >= 3 && (s[0] == '+' || s[0] == '-') &&
+             s[1] >= '0' && s[1] <= '9' && s[2] >= '0' && s[2] <= '9') {
+    int tz_hour = (s[1] - '0') * 10 + (s[2] - '0'), tz_min = 0;
+
+    if (len >= 6 && s[3] == '\'' && s[4] >= '0' && s[4] <= '9' && s[5] >= '0' && s[5] <= '9')
+      tz_min = (s[4] - '0') * 10 + (s[5] - '0');
+
+    /* dvipdfmx writes UTC as -00'00'. */
+    if (tz_hour == 0 && tz_min == 0) {
+      xmp_append(b, "Z");
+    } else {
+      snprintf(out, sizeof(out), "%c%02d:%02d", s[0], tz_hour, tz_min);
+      xmp_append(b, out);
+    }
+  }
+}
+
+static void
+xmp_append_entry (struct xmp_buf *b, pdf_obj *docinfo, const char *key,
+                  const char *open, const char *close, int is_date)
+{
+  pdf_obj *value = pdf_lookup_dict(docinfo, key);
+
+  if (!PDF_OBJ_STRINGTYPE(value))
+    return;
+
+  xmp_append(b, open);
+  if (is_date)
+    xmp_append_date(b, value);
+  else
+    xmp_append_text(b, value);
+  xmp_append(b, close);
+}
+
+static void
+add_xmp_metadata (pdf_obj *catalog, pdf_obj *docinfo)
+{
+  struct xmp_buf b = { NULL, 0, 0 };
+  pdf_obj *metadata;
+  char     line[128];
+
+  xmp_append(&b,
+    "<?xpacket begin=\"\xef\xbb\xbf\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n"
+    "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n"
+    " <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n"
+    "  <rdf:Description rdf:about=\"\"\n"
+    "    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n"
+    "    xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\"\n"
+    "    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n"
+    "    xmlns:xmpMM=\"http://ns.adobe.com/xap/1.0/mm/\"\n");
+
+  if (conform.level == PDF_CONFORMANCE_PDFX_4) {
+    xmp_append(&b, "    xmlns:pdfxid=\"http://www.npes.org/pdfx/ns/id/\">\n");
+  } else {
+    xmp_append(&b, "    xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n");
+  }
+
+  xmp_append(&b, "   <dc:format>application/pdf</dc:format>\n");
+  xmp_append_entry(&b, docinfo, "Title",
+                   "   <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">",
+                   "</rdf:li></rdf:Alt></dc:title>\n", 0);
+  xmp_append_entry(&b, docinfo, "Author",
+                   "   <dc:creator><rdf:Seq><rdf:li>",
+                   "</rdf:li></rdf:Seq></dc:creator>\n", 0);
+  xmp_append_entry(&b, docinfo, "Subject",
+                   "   <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">",
+                   "</rdf:li></rdf:Alt></dc:description>\n", 0);
+  xmp_append_entry(&b, docinfo, "Keywords",
+                   "   <pdf:Keywords>", "</pdf:Keywords>\n", 0);
+  xmp_append_entry(&b, docinfo, "Producer",
+                   "   <pdf:Producer>", "</pdf:Producer>\n", 0);
+  xmp_append_entry(&b, docinfo, "Creator",
+                   "   <xmp:CreatorTool>", "</xmp:CreatorTool>\n", 0);
+  xmp_append_entry(&b, docinfo, "CreationDate",
+                   "   <xmp:CreateDate>", "</xmp:CreateDate>\n", 1);
+  xmp_append_entry(&b, docinfo, "ModDate",
+                   "   <xmp:ModifyDate>", "</xmp:ModifyDate>\n", 1);
+  xmp_append_entry(&b, docinfo, "ModDate",
+                   "   <xmp:MetadataDate>", "</xmp:MetadataDate>\n", 1);
+
+  snprintf(line, sizeof(line), "   <xmpMM:DocumentID>%s</xmpMM:DocumentID>\n",
+           conform.document_id);
+  xmp_append(&b, line);
+  snprintf(line, sizeof(line), "   <xmpMM:InstanceID>%s</xmpMM:InstanceID>\n",
+           conform.document_id);
+  xmp_append(&b, line);
+
+  if (conform.level == PDF_CONFORMANCE_PDFX_4) {
+    pdf_obj *trapped = pdf_lookup_dict(docinfo, "Trapped");
+
+    if (PDF_OBJ_NAMETYPE(trapped)) {
+      snprintf(line, sizeof(line), "   <pdf:Trapped>%s</pdf:Trapped>\n",
+               pdf_name_value(trapped));
+      xmp_append(&b, line);
+    }
+
+    xmp_append(&b,
+      "   <xmpMM:VersionID>1</xmpMM:VersionID>\n"
+      "   <xmpMM:RenditionClass>default</xmpMM:RenditionClass>\n"
+      "   <pdfxid:GTS_PDFXVersion>PDF/X-4</pdfxid:GTS_PDFXVersion>\n");
+  } else {
+    xmp_append(&b,
+      "   <pdfaid:part>2</pdfaid:part>\n"
+      "   <pdfaid:conformance>B</pdfaid:conformance>\n");
+  }
+
+  xmp_append(&b,
+    "  </rdf:Description>\n"
+    " </rdf:RDF>\n"
+    "</x:xmpmeta>\n"
+    "<?xpacket end=\"w\"?>");
+
+  /* The metadata must stay readable without decoding, so no compression. */
+  metadata = pdf_new_stream(0);
+  pdf_add_dict(pdf_stream_dict(metadata), pdf_new_name("Type"), pdf_new_name("Metadata"));
+  pdf_add_dict(pdf_stream_dict(metadata), pdf_new_name("Subtype"), pdf_new_name("XML"));
+  pdf_add_stream(metadata, b.data, (int) b.len);
+  free(b.data);
+
+  if (pdf_lookup_dict(catalog, "Metadata"))
+    dpx_warning("Replacing document metadata with XMP generated for %s.", conform_name());
+
+  pdf_add_dict(catalog, pdf_new_name("Metadata"), pdf_ref_obj(metadata));
+  pdf_release_obj(metadata);
+}
+
+void
+pdf_conform_close_docinfo (pdf_obj *catalog, pdf_obj *docinfo)
+{
+  pdf_obj *value;
+
+  if (conform.level == PDF_CONFORMANCE_NONE)
+    return;
+
+  /* The dates are required, and must match in Info and XMP. */
+  if (!pdf_lookup_dict(docinfo, "CreationDate")) {
+    char now[80];
+
+    dpx_util_format_asn_date(now, 1);
+    pdf_add_dict(docinfo, pdf_new_name("CreationDate"), pdf_new_string(now, strlen(now)));
+  }
+
+  if (!pdf_lookup_dict(docinfo, "ModDate")) {
+    value = pdf_lookup_dict(docinfo, "CreationDate");
+    pdf_add_dict(docinfo, pdf_new_name("ModDate"), pdf_link_obj(value));
+  }
+
+  if (conform.level == PDF_CONFORMANCE_PDFX_4) {
+    if (!pdf_lookup_dict(docinfo, "Title")) {
+      pdf_conform_violation(PDF_VIOLATION_METADATA,
+                            "PDF/X requires a document title (/Title in the Info dictionary)");
+    }
+
+    value = pdf_lookup_dict(docinfo, "Trapped");
+    if (!value) {
+      pdf_add_dict(docinfo, pdf_new_name("Trapped"), pdf_new_name("False"));
+    } else if (!PDF_OBJ_NAMETYPE(value) ||
+               (!streq_ptr(pdf_name_value(value), "True") &&
+                !streq_ptr(pdf_name_value(value), "False"))) {
+      pdf_conform_violation(PDF_VIOLATION_METADATA,
+                            "PDF/X requires /Trapped to be /True or /False");
+    }
+  }
+
+  if (pdf_lookup_dict(catalog, "AA")) {
+    pdf_conform_violation(PDF_VIOLATION_FORBIDDEN_ACTION,
+                          "the document may not have additional actions (/AA)");
+  }
+
+  pdf_conform_check_action(pdf_lookup_dict(catalog, "OpenAction"), "the document's open action");
+
+  add_output_intent(catalog);
+  check_device_colors();
+  add_xmp_metadata(catalog, docinfo);
+}
@@ -0,0 +1,80 @@
+/* This is dvipdfmx, an eXtended version of dvipdfm by Mark A. Wicks.
+
+    Copyright (C) 2026 the Tectonic Project
+
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 2 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307 USA.
+*/
+
+#ifndef _PDFCONFORM_H_
+#define _PDFCONFORM_H_
+
+#include "tectonic_bridge_core.h"
+
+#include "dpx-pdfdev.h"
+#include "dpx-pdfobj.h"
+
+/* Tectonic: support for producing PDF/A-2b and PDF/X-4 output.
+ *
+ * When a conformance level is selected, the document gets an output intent
+ * and an XMP metadata stream matching its Info dictionary, and features that
+ * the standard forbids are recorded as violations as they are encountered.
+ * The caller fetches the violations once the document has been closed.
+ */
+
+enum pdf_conformance
+{
+  PDF_CONFORMANCE_NONE    = 0,
+  PDF_CONFORMANCE_PDFA_2B = 1,
+  PDF_CONFORMANCE_PDFX_4  = 2
+};
+
+/* These values must align with `ViolationKind` in the xdvipdfmx engine crate. */
+enum pdf_conform_violation_kind
+{
+  PDF_VIOLATION_ENCRYPTION           = 1,
+  PDF_VIOLATION_FONT_NOT_EMBEDDED    = 2,
+  PDF_VIOLATION_FORBIDDEN_ACTION     = 3,
+  PDF_VIOLATION_FORBIDDEN_ANNOTATION = 4,
+  PDF_VIOLATION_COLOR_SPACE          = 5,
+  PDF_VIOLATION_PDF_VERSION          = 6,
+  PDF_VIOLATION_METADATA             = 7,
+  PDF_VIOLATION_OUTPUT_INTENT        = 8
+};
+
+struct pdf_conform_setting {
+  int         conformance;      /* enum pdf_conformance */
+  const char *icc_profile;      /* name of the output intent's ICC profile */
+  const char *output_condition; /* its OutputConditionIdentifier */
+};
+
+void pdf_conform_init  (const struct pdf_conform_setting *settings,
+                        int ver_major, int ver_minor, const unsigned char *id);
+int  pdf_conform_level (void);
+
+PRINTF_FUNC(2,3) void pdf_conform_violation (int kind, const char *fmt, ...);
+
+/* Hooks for the rest of the PDF writer. */
+void pdf_conform_note_colorspace (int num_components);
+void pdf_conform_check_colorspace (pdf_obj *colorspace);
+void pdf_conform_check_action (pdf_obj *action, const char *context);
+void pdf_conform_check_annot (pdf_obj *annot_dict);
+void pdf_conform_check_page (pdf_obj *page_obj, const pdf_rect *mediabox);
+void pdf_conform_close_docinfo (pdf_obj *catalog, pdf_obj *docinfo);
+
+/* Violations recorded since pdf_conform_init(). */
+int  pdf_conform_num_violations (void);
+int  pdf_conform_get_violation (int idx, const char **detail);
+
+#endif /* _PDFCONFORM_H_ */
@@ -44,6 +44,7 @@
 #include "dpx-mem.h"
 #include "dpx-numbers.h"
 #include "dpx-pdfcolor.h"
+#include "dpx-pdfconform.h"
 #include "dpx-pdfdev.h"
 #include "dpx-pdfdraw.h"
 #include "dpx-pdfencrypt.h"
@@ -470,6 +471,8 @@ pdf_doc_close_docinfo (pdf_doc *p)
                  pdf_new_string(now, strlen(now)));
   }
 
+  pdf_conform_close_docinfo(p->root.dict, docinfo);
+
   pdf_release_obj(docinfo);
   p->info = NULL;
 
@@ -577,6 +580,9 @@ doc_flush_page (pdf_doc *p, pdf_page *page, pdf_obj *parent_ref)
     pdf_add_dict(page->page_obj, pdf_new_name("MediaBox"),  mediabox);
   }
 
+  pdf_conform_check_page(page->page_obj,
+                         (page->flags & USE_MY_MEDIABOX) ? &page->cropbox : &p->pages.mediabox);
+
   count = 0;
   contents_array = pdf_new_array();
   if (page->content_refs[0]) { /* global bop */
@@ -1444,6 +1450,8 @@ pdf_doc_bookmarks_add (pdf_obj *dict, int is_open)
 
 #define BMOPEN(b,p) (((b) < 0) ? (((p)->outlines.current_depth > (p)->options.outline_open_depth) ? 0 : 1) : (b))
 
+  pdf_conform_check_action(pdf_lookup_dict(dict, "A"), "a bookmark");
+
   item->dict    = dict;
   item->first   = NULL;
   item->is_open = BMOPEN(is_open, p);
@@ -1542,6 +1550,10 @@ pdf_doc_add_names (const char *category,
     dpx_warning("Unknown name dictionary category \"%s\".", category);
     return -1;
   }
+  if (streq_ptr(category, "JavaScript")) {
+    pdf_conform_violation(PDF_VIOLATION_FORBIDDEN_ACTION,
+                          "document-level JavaScript is not allowed");
+  }
   if (!p->names[i].data) {
     p->names[i].data = pdf_new_name_tree();
   }
@@ -1774,6 +1786,8 @@ pdf_doc_add_annot (unsigned page_no, const pdf_rect *rect,
   pdf_add_array(rect_array, pdf_new_number(ROUND(rect->ury, 0.001)));
   pdf_add_dict (annot_dict, pdf_new_name("Rect"), rect_array);
 
+  pdf_conform_check_annot(annot_dict);
+
   pdf_add_array(page->annots, pdf_ref_obj(annot_dict));
 
   if (new_annot)
@@ -2457,8 +2471,11 @@ pdf_open_document (const char *filename,
 
   pdf_doc_init_catalog(p);
 
+  pdf_conform_init(&settings.conform, settings.ver_major, settings.ver_minor, id1);
+
   /* After Catalog is created... */
   if (settings.enable_encrypt) {
+    pdf_conform_violation(PDF_VIOLATION_ENCRYPTION, "encryption is not allowed");
     pdf_out_set_encrypt(settings.encrypt.key_size, settings.encrypt.permission,
                         settings.encrypt.oplain, settings.encrypt.uplain,
                         1, 1);
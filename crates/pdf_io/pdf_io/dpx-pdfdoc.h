@@ -29,6 +29,7 @@
 
 #include "dpx-pdfobj.h"
 #include "dpx-pdfdev.h"
+#include "dpx-pdfconform.h"
 
 enum pdf_page_boundary
 {
@@ -77,6 +78,7 @@ struct pdf_setting
     struct pdf_enc_setting encrypt;
     struct pdf_dev_setting device;
     struct pdf_obj_setting object;
+    struct pdf_conform_setting conform;
 };
 
 void pdf_open_document (const char *filename,
@@ -43,6 +43,7 @@
 #include "dpx-error.h"
 #include "dpx-mem.h"
 #include "dpx-pdfencoding.h"
+#include "dpx-pdfconform.h"
 #include "dpx-pdflimits.h"
 #include "dpx-pdfobj.h"
 #include "dpx-pkfont.h"
@@ -176,6 +177,8 @@ pdf_flush_font (pdf_font *font)
       break;
     default:
       if (font->flags & PDF_FONT_FLAG_NOEMBED) {
+        pdf_conform_violation(PDF_VIOLATION_FONT_NOT_EMBEDDED,
+                              "font \"%s\" is not embedded", font->fontname);
         pdf_add_dict(font->resource, pdf_new_name("BaseFont"), pdf_new_name(font->fontname));
         if (font->descriptor) {
           pdf_add_dict(font->descriptor, pdf_new_name("FontName"), pdf_new_name(font->fontname));
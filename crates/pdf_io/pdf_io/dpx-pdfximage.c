@@ -39,6 +39,7 @@
 #include "dpx-mem.h"
 #include "dpx-mfileio.h"
 #include "dpx-pdfdev.h"
+#include "dpx-pdfconform.h"
 #include "dpx-pdfdraw.h"
 #include "dpx-pdfnames.h"
 #include "dpx-pdfobj.h"
@@ -535,6 +536,7 @@ pdf_ximage_set_image (pdf_ximage *I, void *image_info, pdf_obj *resource)
                      pdf_new_number(info->bits_per_component));
     if (I->attr.dict)
         pdf_merge_dict(dict, I->attr.dict);
+    pdf_conform_check_colorspace(pdf_lookup_dict(dict, "ColorSpace"));
 
     if (I->ident) {
         int error;
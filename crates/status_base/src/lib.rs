@@ -7,7 +7,12 @@
 //! frameworks, but we do have some extra hooks to help support a nice user
 //! experience for the Tectonic CLI tool.
 
-use std::{cmp::Ordering, fmt::Arguments, result::Result as StdResult, str::FromStr};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Arguments},
+    result::Result as StdResult,
+    str::FromStr,
+};
 use tectonic_errors::Error;
 
 pub mod plain;
@@ -27,6 +32,60 @@ pub enum MessageKind {
     Error,
 }
 
+/// A structured diagnostic message issued by one of the engines.
+///
+/// Unlike a message passed to [`StatusBackend::report`], a diagnostic keeps
+/// track of where in the TeX source it arose, if the engine knows.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The severity of the diagnostic.
+    pub kind: MessageKind,
+
+    /// The name of the source file that was being read when the diagnostic
+    /// was issued, if known.
+    pub file: Option<String>,
+
+    /// The line number within `file` that was being read when the diagnostic
+    /// was issued, if known.
+    pub line: Option<u32>,
+
+    /// Lines of source context, in the same style as TeX’s own error
+    /// messages: the text of the current line up to the point where the
+    /// engine stopped reading, then the rest of the line.
+    pub context: Vec<String>,
+
+    /// The text of the diagnostic, which may span multiple lines.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic of the specified kind, with no location
+    /// information or text.
+    pub fn new(kind: MessageKind) -> Self {
+        Diagnostic {
+            kind,
+            file: None,
+            line: None,
+            context: Vec::new(),
+            message: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Format the diagnostic as a flat message in the `file:line: message`
+    /// style. The context lines are not included.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{file}:{line}: ")?,
+            (Some(file), None) => write!(f, "{file}: ")?,
+            _ => {}
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
 /// A setting regarding which messages to display.
 #[repr(usize)]
 #[non_exhaustive]
@@ -119,6 +178,14 @@ pub trait StatusBackend {
         )
     }
 
+    /// Report a structured diagnostic issued by one of the engines.
+    ///
+    /// The default implementation delegates to `report()`, prefixing the
+    /// message with its source location, if known.
+    fn report_diagnostic(&mut self, diag: &Diagnostic) {
+        self.report(diag.kind, format_args!("{diag}"), None)
+    }
+
     /// This is used to print TeX engine logs after it encountered errors. This
     /// should print the provided output, which may span many lines, with some
     /// clear delineation.
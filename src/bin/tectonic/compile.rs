@@ -5,12 +5,19 @@
 //! "V1" / "rustc-like" Tectonic command-line interface, as well as the
 //! `compile` subcommand of the "V2" / "cargo-like" interface.
 
-use clap::Parser;
-use std::path::{Path, PathBuf};
+use clap::{Args, Parser};
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+};
 use tectonic_bridge_core::{SecuritySettings, SecurityStance};
+use tectonic_docmodel::document::PdfEncryption;
 
 use tectonic::{
     config::{maybe_return_test_bundle, PersistentConfig},
+    docmodel::engine_pdf_encryption,
     driver::{OutputFormat, PassSetting, ProcessingSession, ProcessingSessionBuilder},
     errmsg,
     errors::{ErrorKind, Result},
@@ -47,6 +54,10 @@ pub struct CompileOptions {
     #[arg(long, name = "dest_path")]
     makefile_rules: Option<PathBuf>,
 
+    /// Write a JSON report of the files accessed and passes run to <report_path>
+    #[arg(long, name = "report_path")]
+    report: Option<PathBuf>,
+
     /// Which engines to run
     #[arg(long, default_value = "default")]
     pass: PassSetting,
@@ -67,6 +78,9 @@ pub struct CompileOptions {
     #[arg(long)]
     synctex: bool,
 
+    #[command(flatten)]
+    encryption: EncryptionArgs,
+
     /// Tell the engine that no file at <hide_path> exists, if it tries to read it
     #[arg(long, name = "hide_path")]
     hide: Option<Vec<PathBuf>>,
@@ -88,6 +102,154 @@ pub struct CompileOptions {
     unstable: Vec<UnstableArg>,
 }
 
+/// The environment variable that can give the PDF user password instead of
+/// `--user-password`.
+const USER_PASSWORD_VAR: &str = "TECTONIC_PDF_USER_PASSWORD";
+
+/// The environment variable that can give the PDF owner password instead of
+/// `--owner-password`.
+const OWNER_PASSWORD_VAR: &str = "TECTONIC_PDF_OWNER_PASSWORD";
+
+/// Command-line options for encrypting PDF output, shared with `-X build`.
+///
+/// The passwords can also come from the environment, which keeps them out of
+/// the process list, but only once something else has turned encryption on.
+/// The `Debug` output of this type doesn't show them.
+#[derive(Args, Clone, Default, Eq, PartialEq)]
+pub(crate) struct EncryptionArgs {
+    /// Encrypt the PDF output, with any passwords not given as options taken
+    /// from the environment
+    #[arg(long)]
+    encrypt: bool,
+
+    /// Encrypt the PDF output, requiring this password to open it
+    #[arg(long, name = "user_password")]
+    user_password: Option<String>,
+
+    /// Encrypt the PDF output, with this password giving full access to it
+    #[arg(long, name = "owner_password")]
+    owner_password: Option<String>,
+
+    /// Encrypt the PDF output with a key of this many bits [default: 128]
+    #[arg(long, name = "bits")]
+    encrypt_key_bits: Option<u32>,
+
+    /// Encrypt the PDF output, allowing readers without the owner password
+    /// only these actions, such as "print,copy"
+    #[arg(long, name = "permission_list")]
+    permissions: Option<String>,
+}
+
+impl fmt::Debug for EncryptionArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |pw: &Option<String>| pw.as_ref().map(|_| "<redacted>");
+
+        f.debug_struct("EncryptionArgs")
+            .field("encrypt", &self.encrypt)
+            .field("user_password", &redacted(&self.user_password))
+            .field("owner_password", &redacted(&self.owner_password))
+            .field("encrypt_key_bits", &self.encrypt_key_bits)
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+impl EncryptionArgs {
+    /// Whether any of these options turns encryption on.
+    fn enabled(&self) -> bool {
+        self.encrypt
+            || self.user_password.is_some()
+            || self.owner_password.is_some()
+            || self.encrypt_key_bits.is_some()
+            || self.permissions.is_some()
+    }
+
+    /// The password given by an environment variable, if it's set.
+    fn env_password(var: &str) -> Option<String> {
+        env::var(var).ok().filter(|v| !v.is_empty())
+    }
+
+    /// The encryption settings given on the command line, if any, with the
+    /// passwords that aren't given there taken from the environment. The
+    /// environment alone doesn't turn encryption on.
+    pub(crate) fn settings(&self) -> Option<PdfEncryption> {
+        if !self.enabled() {
+            return None;
+        }
+
+        Some(PdfEncryption {
+            owner_password: self
+                .owner_password
+                .clone()
+                .or_else(|| Self::env_password(OWNER_PASSWORD_VAR)),
+            user_password: self
+                .user_password
+                .clone()
+                .or_else(|| Self::env_password(USER_PASSWORD_VAR)),
+            key_length: self.encrypt_key_bits,
+            permissions: self
+                .permissions
+                .as_ref()
+                .map(|p| p.split(',').map(|n| n.trim().to_owned()).collect()),
+        })
+    }
+
+    /// The passwords given in the environment, to be used if the output
+    /// profile turns encryption on, if any.
+    pub(crate) fn env_passwords() -> Option<PdfEncryption> {
+        let owner_password = Self::env_password(OWNER_PASSWORD_VAR);
+        let user_password = Self::env_password(USER_PASSWORD_VAR);
+
+        if owner_password.is_none() && user_password.is_none() {
+            return None;
+        }
+
+        Some(PdfEncryption {
+            owner_password,
+            user_password,
+            ..Default::default()
+        })
+    }
+
+    /// Add these options to the arguments of another invocation of this
+    /// program. The passwords aren't added, since other users can see the
+    /// arguments of a process; see [`Self::password_vars`]. Instead,
+    /// `--encrypt` tells the other invocation to take them from its
+    /// environment.
+    pub(crate) fn push_args(&self, args: &mut Vec<OsString>) {
+        if self.enabled() {
+            args.push("--encrypt".into());
+        }
+
+        if let Some(p) = &self.permissions {
+            args.push("--permissions".into());
+            args.push(p.into());
+        }
+
+        if let Some(bits) = self.encrypt_key_bits {
+            args.push("--encrypt-key-bits".into());
+            args.push(bits.to_string().into());
+        }
+    }
+
+    /// The environment variables that pass the passwords given by these
+    /// options to another invocation of this program.
+    pub(crate) fn password_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = Vec::new();
+
+        for (value, var) in [
+            (&self.user_password, USER_PASSWORD_VAR),
+            (&self.owner_password, OWNER_PASSWORD_VAR),
+        ] {
+            if let Some(v) = value {
+                vars.push((var, v.clone()));
+            }
+        }
+
+        vars
+    }
+}
+
 // TODO: deprecate v1 interface and move this to v2cli/commands
 
 //impl TectonicCommand for CompileOptions {
@@ -127,6 +289,14 @@ impl CompileOptions {
             sess_builder.makefile_output_path(p);
         }
 
+        if let Some(p) = self.report {
+            sess_builder.report_output_path(p);
+        }
+
+        if let Some(e) = self.encryption.settings() {
+            sess_builder.pdf_encryption(engine_pdf_encryption(&e)?);
+        }
+
         // Input and path setup
 
         let input_path = self.input;
@@ -18,6 +18,9 @@ use tectonic::{
     unstable_opts,
 };
 
+#[cfg(feature = "serialization")]
+use tectonic::status::json::JsonStatusBackend;
+
 mod compile;
 
 #[cfg(feature = "serialization")]
@@ -52,6 +55,10 @@ struct CliOptions {
     #[arg(long = "color", default_value = "auto")]
     cli_color: CliColor,
 
+    /// The format of status messages
+    #[arg(long = "message-format", default_value = "human")]
+    message_format: MessageFormat,
+
     #[command(flatten)]
     compile: compile::CompileOptions,
 }
@@ -78,6 +85,18 @@ impl CliColor {
     }
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+enum MessageFormat {
+    /// Messages intended for people to read.
+    #[value(name = "human")]
+    Human,
+
+    /// One JSON object per message, for consumption by other tools.
+    #[cfg(feature = "serialization")]
+    #[value(name = "json")]
+    Json,
+}
+
 #[derive(Parser)]
 struct PeekUnstableOptions {
     #[arg(name = "option", short = 'Z')]
@@ -88,6 +107,10 @@ struct PeekUnstableOptions {
 }
 
 fn main() {
+    // If we were started to run an engine on behalf of a processing session,
+    // do that and exit before touching the arguments or standard output.
+    tectonic::worker::run_worker_if_requested();
+
     let os_args: Vec<_> = env::args_os().collect();
 
     // A hack so that you can just run `tectonic -Z help` without getting a
@@ -170,10 +193,19 @@ fn main() {
     // something I'd be relatively OK with since it'd only affect the progam
     // UI, not the processing results).
 
-    let mut status = if args.cli_color.should_enable() {
-        Box::new(TermcolorStatusBackend::new(args.chatter_level)) as Box<dyn StatusBackend>
-    } else {
-        Box::new(PlainStatusBackend::new(args.chatter_level)) as Box<dyn StatusBackend>
+    let mut status = match args.message_format {
+        #[cfg(feature = "serialization")]
+        MessageFormat::Json => {
+            Box::new(JsonStatusBackend::new(args.chatter_level)) as Box<dyn StatusBackend>
+        }
+
+        MessageFormat::Human if args.cli_color.should_enable() => {
+            Box::new(TermcolorStatusBackend::new(args.chatter_level)) as Box<dyn StatusBackend>
+        }
+
+        MessageFormat::Human => {
+            Box::new(PlainStatusBackend::new(args.chatter_level)) as Box<dyn StatusBackend>
+        }
     };
 
     // Now that we've got colorized output, pass off to the inner function ...
@@ -1,16 +1,34 @@
 use clap::Args;
+use serde_json::Value;
+use std::{
+    env,
+    ffi::OsString,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 use tectonic::{
     config::is_config_test_mode_activated,
     config::PersistentConfig,
-    docmodel::{DocumentExt, DocumentSetupOptions},
+    docmodel::{DocumentExt, DocumentSetupOptions, LockPolicy},
+    errmsg,
     errors::Result,
-    tt_error, tt_note,
+    tt_error, tt_note, tt_warning,
 };
 use tectonic_bridge_core::{SecuritySettings, SecurityStance};
-use tectonic_docmodel::workspace::Workspace;
-use tectonic_status_base::StatusBackend;
+use tectonic_docmodel::{document::Document, workspace::Workspace};
+use tectonic_status_base::{Diagnostic, MessageKind, StatusBackend};
 
-use crate::v2cli::{CommandCustomizations, TectonicCommand};
+use crate::{
+    compile::EncryptionArgs,
+    v2cli::{CommandCustomizations, TectonicCommand},
+};
 
 /// `build`: Build a document
 #[derive(Debug, Eq, PartialEq, Args, Clone)]
@@ -39,13 +57,72 @@ pub struct BuildCommand {
     #[arg(long)]
     open: bool,
 
+    /// Don't use or update the incremental build cache
+    #[arg(long)]
+    no_build_cache: bool,
+
+    /// Only warn if the bundle doesn't match the document's Tectonic.lock
+    #[arg(long)]
+    allow_lock_mismatch: bool,
+
     /// Specify a target to be used by the build
     #[arg(long, help = "Specify the target of the build.")]
     target: Option<String>,
 
+    /// Build only the workspace document with this name
+    #[arg(long = "doc", name = "doc_name")]
+    doc: Option<String>,
+
     /// Use this URL to find resource files instead of the default
     #[arg(long, short)]
     bundle: Option<String>,
+
+    /// Write a JSON report of the files accessed and passes run to this path
+    #[arg(long, name = "report_path")]
+    report: Option<PathBuf>,
+
+    /// Write a Ninja/GCC-style depfile listing the build's dependencies to this path
+    #[arg(long, name = "depfile_path")]
+    depfile: Option<PathBuf>,
+
+    /// Write a JSON manifest of the build's dependencies to this path
+    #[arg(long, name = "manifest_path")]
+    dep_manifest: Option<PathBuf>,
+
+    #[command(flatten)]
+    encryption: EncryptionArgs,
+
+    /// Build up to this many outputs at once, each in its own process (0 means one per CPU)
+    #[arg(long, short = 'j', default_value_t = 1)]
+    jobs: usize,
+}
+
+/// One output of one document, to be built.
+struct Target<'a> {
+    doc: &'a Document,
+    output_name: &'a str,
+
+    /// The tag used to name per-output files, if there is more than one
+    /// target.
+    tag: Option<String>,
+}
+
+impl Target<'_> {
+    /// The label used to identify this target in status messages.
+    fn label(&self, multiple_docs: bool) -> String {
+        if multiple_docs {
+            format!("{}/{}", self.doc.name, self.output_name)
+        } else {
+            self.output_name.to_owned()
+        }
+    }
+
+    fn per_output(&self, p: &Path) -> PathBuf {
+        match &self.tag {
+            Some(tag) => path_for_output(p, tag),
+            None => p.to_owned(),
+        }
+    }
 }
 
 impl TectonicCommand for BuildCommand {
@@ -59,7 +136,46 @@ impl TectonicCommand for BuildCommand {
             tt_note!(status, "using workspace bundle configuration");
         }
         let ws = Workspace::open_from_environment()?;
-        let doc = ws.first_document();
+        let docs = ws.select_documents(self.doc.as_deref())?;
+
+        // If we're going to build more than one output, each one needs its own
+        // report and dependency files. If those outputs come from more than one
+        // document, the document names need to go into the file names too.
+        let multiple_docs = docs.len() > 1;
+        let mut targets = Vec::new();
+
+        for doc in docs {
+            for output_name in doc.output_names() {
+                if let Some(out) = self.target.as_ref() {
+                    if out != output_name {
+                        continue;
+                    }
+                }
+
+                targets.push(Target {
+                    doc,
+                    output_name,
+                    tag: if multiple_docs {
+                        Some(format!("{}.{}", doc.name, output_name))
+                    } else {
+                        Some(output_name.to_owned())
+                    },
+                });
+            }
+        }
+
+        if targets.len() == 1 {
+            targets[0].tag = None;
+        }
+
+        let n_jobs = match self.jobs {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+
+        if n_jobs > 1 && targets.len() > 1 {
+            return self.build_parallel(&targets, multiple_docs, n_jobs, status);
+        }
 
         // Default to allowing insecure since it would be super duper annoying
         // to have to pass `--trusted` every time to build a personal document
@@ -73,23 +189,55 @@ impl TectonicCommand for BuildCommand {
 
         let mut setup_options =
             DocumentSetupOptions::new_with_security(SecuritySettings::new(stance));
-        setup_options.only_cached(self.only_cached);
+        setup_options
+            .only_cached(self.only_cached)
+            .build_cache(!self.no_build_cache);
 
-        for output_name in doc.output_names() {
-            if let Some(out) = self.target.as_ref() {
-                if out != output_name {
-                    continue;
-                }
+        if self.allow_lock_mismatch {
+            setup_options.lock_policy(LockPolicy::Warn);
+        }
+
+        if let Some(e) = self.encryption.settings() {
+            setup_options.pdf_encryption(e);
+        } else if let Some(p) = EncryptionArgs::env_passwords() {
+            setup_options.pdf_passwords(p);
+        }
+
+        let mut last_doc = None;
+
+        for target in &targets {
+            let doc = target.doc;
+            let output_name = target.output_name;
+
+            if multiple_docs && last_doc != Some(&doc.name) {
+                tt_note!(status, "building document `{}`", doc.name);
+                last_doc = Some(&doc.name);
             }
 
             let mut builder = doc.setup_session(output_name, &setup_options, status)?;
 
             builder
                 .format_cache_path(config.format_cache_path()?)
-                .keep_intermediates(self.keep_intermediates)
                 .keep_logs(self.keep_logs)
                 .print_stdout(self.print_stdout);
 
+            // The profile may already ask for intermediates to be kept.
+            if self.keep_intermediates {
+                builder.keep_intermediates(true);
+            }
+
+            if let Some(p) = self.report.as_ref() {
+                builder.report_output_path(target.per_output(p));
+            }
+
+            if let Some(p) = self.depfile.as_ref() {
+                builder.depfile_output_path(target.per_output(p));
+            }
+
+            if let Some(p) = self.dep_manifest.as_ref() {
+                builder.dependency_manifest_path(target.per_output(p));
+            }
+
             crate::compile::run_and_report(builder, status)?;
 
             if self.open {
@@ -114,3 +262,312 @@ impl TectonicCommand for BuildCommand {
         Ok(0)
     }
 }
+
+/// Something that happened in a worker process.
+enum WorkerEvent {
+    /// A line of standard output.
+    Stdout(usize, String),
+
+    /// The complete standard error output.
+    Stderr(usize, String),
+
+    /// The worker finished.
+    Done(usize, bool, Duration),
+}
+
+impl BuildCommand {
+    /// Build several targets at once.
+    ///
+    /// The engines keep global state, so they can't be run on several threads
+    /// at once. Instead, each target is built by a worker process that runs
+    /// this same program, with its status messages sent to us as JSON so that
+    /// they can be relayed through our own status backend.
+    fn build_parallel(
+        &self,
+        targets: &[Target],
+        multiple_docs: bool,
+        n_jobs: usize,
+        status: &mut dyn StatusBackend,
+    ) -> Result<i32> {
+        let exe = env::current_exe()?;
+
+        // The `nextonic` binary is always in V2 mode.
+        let v2_default = exe
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.contains("nextonic"));
+
+        let worker_args: Vec<Vec<OsString>> = targets
+            .iter()
+            .map(|t| self.worker_args(t, v2_default))
+            .collect();
+        let labels: Vec<String> = targets.iter().map(|t| t.label(multiple_docs)).collect();
+        let worker_vars = self.encryption.password_vars();
+
+        let n_jobs = n_jobs.min(targets.len());
+        tt_note!(
+            status,
+            "building {} outputs with up to {} parallel jobs",
+            targets.len(),
+            n_jobs
+        );
+
+        let next_job = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
+        let mut n_failed = 0;
+
+        thread::scope(|scope| {
+            for _ in 0..n_jobs {
+                let tx = tx.clone();
+                let next_job = &next_job;
+                let exe = &exe;
+                let worker_args = &worker_args;
+                let worker_vars = &worker_vars;
+
+                scope.spawn(move || loop {
+                    let index = next_job.fetch_add(1, Ordering::SeqCst);
+
+                    if index >= worker_args.len() {
+                        break;
+                    }
+
+                    run_worker(index, exe, &worker_args[index], worker_vars, &tx);
+                });
+            }
+
+            // Only the workers should be holding senders now, so the loop
+            // below will end once they have all finished.
+            drop(tx);
+
+            for event in rx {
+                match event {
+                    WorkerEvent::Stdout(index, line) => relay_line(&labels[index], &line, status),
+
+                    WorkerEvent::Stderr(index, text) => {
+                        if !text.trim().is_empty() {
+                            tt_warning!(
+                                status,
+                                "[{}] worker process printed: {}",
+                                labels[index],
+                                text.trim_end()
+                            );
+                        }
+                    }
+
+                    WorkerEvent::Done(index, success, elapsed) => {
+                        let secs = elapsed.as_secs_f32();
+
+                        if success {
+                            tt_note!(
+                                status,
+                                "[{}] built `{}` in {:.1} s",
+                                labels[index],
+                                targets[index]
+                                    .doc
+                                    .output_main_file(targets[index].output_name)
+                                    .display(),
+                                secs
+                            );
+                        } else {
+                            tt_error!(
+                                status,
+                                "[{}] build failed after {:.1} s",
+                                labels[index],
+                                secs
+                            );
+                            n_failed += 1;
+                        }
+                    }
+                }
+            }
+        });
+
+        if n_failed > 0 {
+            return Err(errmsg!(
+                "{} of {} outputs failed to build",
+                n_failed,
+                targets.len()
+            ));
+        }
+
+        Ok(0)
+    }
+
+    /// Get the command-line arguments for a worker process that builds just
+    /// one target.
+    fn worker_args(&self, target: &Target, v2_default: bool) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+
+        if !v2_default {
+            args.push("-X".into());
+        }
+
+        // Notes from the workers would just be noise, since they're all
+        // interleaved; we report the important bits ourselves.
+        for a in [
+            "--chatter",
+            "minimal",
+            "--message-format",
+            "json",
+            "build",
+            "--doc",
+            &target.doc.name,
+            "--target",
+            target.output_name,
+        ] {
+            args.push(a.into());
+        }
+
+        for (flag, a) in [
+            (self.untrusted, "--untrusted"),
+            (self.only_cached, "--only-cached"),
+            (self.keep_intermediates, "--keep-intermediates"),
+            (self.keep_logs, "--keep-logs"),
+            (self.print_stdout, "--print"),
+            (self.open, "--open"),
+            (self.no_build_cache, "--no-build-cache"),
+            (self.allow_lock_mismatch, "--allow-lock-mismatch"),
+        ] {
+            if flag {
+                args.push(a.into());
+            }
+        }
+
+        for (path, a) in [
+            (&self.report, "--report"),
+            (&self.depfile, "--depfile"),
+            (&self.dep_manifest, "--dep-manifest"),
+        ] {
+            if let Some(p) = path {
+                args.push(a.into());
+                args.push(target.per_output(p).into());
+            }
+        }
+
+        self.encryption.push_args(&mut args);
+        args
+    }
+}
+
+/// Run one worker process, sending what it does to *tx*. Secrets are passed
+/// in the environment variables *vars*, not in *args*.
+fn run_worker(
+    index: usize,
+    exe: &Path,
+    args: &[OsString],
+    vars: &[(&str, String)],
+    tx: &mpsc::Sender<WorkerEvent>,
+) {
+    let start = Instant::now();
+
+    let mut child = match Command::new(exe)
+        .args(args)
+        .envs(vars.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = tx.send(WorkerEvent::Stderr(
+                index,
+                format!("failed to launch `{}`: {}", exe.display(), e),
+            ));
+            let _ = tx.send(WorkerEvent::Done(index, false, start.elapsed()));
+            return;
+        }
+    };
+
+    // Read standard error on a thread of its own, so that the worker can't get
+    // stuck writing to it while we're waiting on standard output.
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr_thread = thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text);
+        text
+    });
+
+    for line in BufReader::new(child.stdout.take().unwrap()).lines() {
+        let Ok(line) = line else { break };
+        let _ = tx.send(WorkerEvent::Stdout(index, line));
+    }
+
+    let stderr_text = stderr_thread.join().unwrap_or_default();
+    let _ = tx.send(WorkerEvent::Stderr(index, stderr_text));
+
+    let success = child.wait().is_ok_and(|s| s.success());
+    let _ = tx.send(WorkerEvent::Done(index, success, start.elapsed()));
+}
+
+/// Relay a line of output from a worker through our status backend.
+///
+/// Workers emit their status messages as JSON, as documented in
+/// `tectonic::status::json`. Anything else, such as the engine output printed
+/// with `--print`, is passed along as a note.
+fn relay_line(label: &str, line: &str, status: &mut dyn StatusBackend) {
+    let Ok(event) = serde_json::from_str::<Value>(line) else {
+        tt_note!(status, "[{}] {}", label, line);
+        return;
+    };
+
+    let kind = match event["kind"].as_str() {
+        Some("warning") => MessageKind::Warning,
+        Some("error") => MessageKind::Error,
+        _ => MessageKind::Note,
+    };
+
+    let message = event["message"].as_str().unwrap_or_default();
+
+    match event["type"].as_str() {
+        Some("diagnostic") => {
+            let mut diag = Diagnostic::new(kind);
+            diag.message = format!("[{label}] {message}");
+            diag.file = event["file"].as_str().map(str::to_owned);
+            diag.line = event["line"].as_u64().and_then(|l| u32::try_from(l).ok());
+
+            if let Some(context) = event["context"].as_array() {
+                diag.context = context
+                    .iter()
+                    .filter_map(|c| c.as_str().map(str::to_owned))
+                    .collect();
+            }
+
+            status.report_diagnostic(&diag);
+        }
+
+        Some("error_log") => {
+            status.dump_error_logs(event["log"].as_str().unwrap_or_default().as_bytes());
+        }
+
+        _ => {
+            let mut text = format!("[{label}] {message}");
+
+            if let Some(causes) = event["causes"].as_array() {
+                for cause in causes.iter().filter_map(|c| c.as_str()) {
+                    text.push_str("\n  caused by: ");
+                    text.push_str(cause);
+                }
+            }
+
+            status.report(kind, format_args!("{text}"), None);
+        }
+    }
+}
+
+/// Derive the path of a per-output file (such as a report) for one output of a
+/// multi-output build, by inserting a tag naming the output before the
+/// extension: `report.json` becomes `report.default.json`.
+fn path_for_output(base: &Path, output_name: &str) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let name = match base.extension() {
+        Some(ext) => format!("{stem}.{output_name}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.{output_name}"),
+    };
+
+    base.with_file_name(name)
+}
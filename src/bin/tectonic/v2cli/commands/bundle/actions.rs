@@ -1,6 +1,6 @@
 use super::{
     create::{BundleCreateCommand, BundleFormat},
-    pack::bundlev1::BundleV1,
+    pack::{bundlev1::BundleV1, zip::BundleZip},
     select::{picker::FilePicker, spec::BundleSpec},
 };
 use anyhow::{Context, Result};
@@ -115,7 +115,7 @@ pub(super) fn pack(cli: &BundleCreateCommand) -> Result<()> {
         return Ok(());
     }
 
-    let target_name = format!("{}.ttb", &bundle_config.bundle.name);
+    let target_name = format!("{}.{}", &bundle_config.bundle.name, cli.format.extension());
     let target = build_dir.join(&target_name);
     if target.exists() {
         if target.is_file() {
@@ -127,10 +127,11 @@ pub(super) fn pack(cli: &BundleCreateCommand) -> Result<()> {
         }
     }
 
+    let content_dir = build_dir.join("content");
+
     match cli.format {
-        BundleFormat::BundleV1 => {
-            BundleV1::make(Box::new(File::create(target)?), build_dir.clone())?
-        }
+        BundleFormat::BundleV1 => BundleV1::make(Box::new(File::create(target)?), content_dir)?,
+        BundleFormat::Zip => BundleZip::make(Box::new(File::create(target)?), content_dir)?,
     }
 
     Ok(())
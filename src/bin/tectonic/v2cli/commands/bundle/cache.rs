@@ -0,0 +1,275 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `bundle cache` subcommands: inspection and cleanup of the local cache
+//! of bundle files.
+
+use clap::{Parser, Subcommand};
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use tectonic::{config::PersistentConfig, errmsg, errors::Result, tt_error, tt_note};
+use tectonic_bundles::cache::{CacheDirectory, CachedBundle};
+use tectonic_io_base::digest::DigestData;
+use tectonic_status_base::StatusBackend;
+
+use crate::v2cli::CommandCustomizations;
+
+/// `bundle cache`: Inspect and clean up the local bundle cache
+#[derive(Debug, Parser)]
+pub struct BundleCacheCommand {
+    #[command(subcommand)]
+    command: BundleCacheCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum BundleCacheCommands {
+    #[command(name = "list")]
+    /// List the bundles with data in the cache
+    List,
+
+    #[command(name = "du")]
+    /// Show how much disk space each cached bundle uses
+    Du,
+
+    #[command(name = "gc")]
+    /// Delete cached bundle data that are no longer needed
+    Gc(BundleCacheGcCommand),
+
+    #[command(name = "verify")]
+    /// Check cached files against the hashes in their bundle's index
+    Verify(BundleCacheVerifyCommand),
+}
+
+#[derive(Debug, Parser)]
+struct BundleCacheGcCommand {
+    /// Also delete data for bundles that haven't been used in this many days
+    #[arg(long, value_name = "DAYS")]
+    older_than: Option<u64>,
+
+    /// Report what would be deleted, without deleting anything
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+#[derive(Debug, Parser)]
+struct BundleCacheVerifyCommand {
+    /// Delete cached files that fail the check, so that they will be fetched again
+    #[arg(long)]
+    fix: bool,
+
+    /// The digest of the bundle to check [default: all cached bundles]
+    digest: Option<String>,
+}
+
+impl BundleCacheCommand {
+    pub fn customize(&self, cc: &mut CommandCustomizations) {
+        match self.command {
+            BundleCacheCommands::List | BundleCacheCommands::Du => cc.always_stderr = true,
+            BundleCacheCommands::Gc(_) | BundleCacheCommands::Verify(_) => {}
+        }
+    }
+
+    pub fn execute(self, _config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        let cache = CacheDirectory::new(None)?;
+        let bundles = cache.bundles()?;
+
+        match self.command {
+            BundleCacheCommands::List => {
+                for b in &bundles {
+                    let locations = if b.locations.is_empty() {
+                        "(unreferenced)".to_owned()
+                    } else {
+                        b.locations.join(", ")
+                    };
+
+                    println!(
+                        "{}  {:>6} files  {:<16}  {}",
+                        b.digest,
+                        b.n_files,
+                        format_last_used(b),
+                        locations
+                    );
+                }
+            }
+
+            BundleCacheCommands::Du => {
+                let mut total = 0;
+
+                for b in &bundles {
+                    println!("{:>10}  {}", format_size(b.size), b.digest);
+                    total += b.size;
+                }
+
+                println!("{:>10}  total", format_size(total));
+            }
+
+            BundleCacheCommands::Gc(c) => return c.execute(&cache, &bundles, status),
+            BundleCacheCommands::Verify(c) => return c.execute(&cache, &bundles, status),
+        }
+
+        Ok(0)
+    }
+}
+
+impl BundleCacheGcCommand {
+    fn execute(
+        self,
+        cache: &CacheDirectory,
+        bundles: &[CachedBundle],
+        status: &mut dyn StatusBackend,
+    ) -> Result<i32> {
+        // A cutoff too far in the past to represent is clamped to the epoch,
+        // which no bundle is older than.
+        let cutoff = self.older_than.map(|days| {
+            days.checked_mul(86400)
+                .and_then(|secs| SystemTime::now().checked_sub(Duration::from_secs(secs)))
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        });
+        let mut freed = 0;
+        let mut n_removed = 0;
+
+        for b in bundles {
+            let reason = if b.locations.is_empty() {
+                "unreferenced".to_owned()
+            } else {
+                match (cutoff, b.last_used) {
+                    (Some(cutoff), Some(t)) if t < cutoff => {
+                        format!("not used in {} days", self.older_than.unwrap())
+                    }
+                    _ => continue,
+                }
+            };
+
+            if self.dry_run {
+                tt_note!(
+                    status,
+                    "would delete {} ({}, {})",
+                    b.digest,
+                    reason,
+                    format_size(b.size)
+                );
+            } else {
+                cache.remove_bundle(&b.digest)?;
+                tt_note!(
+                    status,
+                    "deleted {} ({}, {})",
+                    b.digest,
+                    reason,
+                    format_size(b.size)
+                );
+            }
+
+            freed += b.size;
+            n_removed += 1;
+        }
+
+        let verb = if self.dry_run { "would free" } else { "freed" };
+        tt_note!(
+            status,
+            "{} {} in {} bundle(s)",
+            verb,
+            format_size(freed),
+            n_removed
+        );
+        Ok(0)
+    }
+}
+
+impl BundleCacheVerifyCommand {
+    fn execute(
+        self,
+        cache: &CacheDirectory,
+        bundles: &[CachedBundle],
+        status: &mut dyn StatusBackend,
+    ) -> Result<i32> {
+        let digests: Vec<DigestData> = match &self.digest {
+            Some(text) => {
+                let digest = match DigestData::from_str(text) {
+                    Ok(d) => d,
+                    Err(e) => return Err(errmsg!("invalid bundle digest `{}`: {}", text, e)),
+                };
+
+                if !bundles.iter().any(|b| b.digest == digest) {
+                    return Err(errmsg!("no data for bundle `{}` in the cache", text));
+                }
+
+                vec![digest]
+            }
+
+            None => bundles.iter().map(|b| b.digest).collect(),
+        };
+
+        let mut n_corrupt = 0;
+
+        for digest in &digests {
+            let report = cache.verify_bundle(digest, self.fix)?;
+
+            for path in &report.corrupt {
+                if self.fix {
+                    tt_error!(
+                        status,
+                        "{}: `{}` was corrupt and has been deleted",
+                        digest,
+                        path
+                    );
+                } else {
+                    tt_error!(status, "{}: `{}` is corrupt", digest, path);
+                }
+            }
+
+            tt_note!(
+                status,
+                "{}: checked {} file(s), {} corrupt, {} without hashes",
+                digest,
+                report.n_checked,
+                report.corrupt.len(),
+                report.n_unhashed
+            );
+
+            n_corrupt += report.corrupt.len();
+        }
+
+        Ok(if n_corrupt > 0 && !self.fix { 1 } else { 0 })
+    }
+}
+
+/// Format a byte count for humans.
+fn format_size(size: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1024 {
+        return format!("{size} B");
+    }
+
+    let mut value = size as f64 / 1024.;
+    let mut unit = UNITS[0];
+
+    for u in &UNITS[1..] {
+        if value < 1024. {
+            break;
+        }
+
+        value /= 1024.;
+        unit = u;
+    }
+
+    format!("{value:.1} {unit}")
+}
+
+/// Describe when a bundle was last used.
+fn format_last_used(b: &CachedBundle) -> String {
+    let Some(t) = b.last_used else {
+        return "never used".to_owned();
+    };
+
+    match SystemTime::now().duration_since(t) {
+        Ok(d) => match d.as_secs() / 86400 {
+            0 => "used today".to_owned(),
+            1 => "used 1 day ago".to_owned(),
+            n => format!("used {n} days ago"),
+        },
+        Err(_) => "used today".to_owned(),
+    }
+}
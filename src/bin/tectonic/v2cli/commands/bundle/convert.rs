@@ -0,0 +1,219 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `bundle convert` subcommand: convert a bundle between the TTB, ZIP,
+//! and directory layouts.
+//!
+//! All three layouts hold the same thing: the content directory produced by
+//! `bundle create`, including its `FILELIST`, `SEARCH`, and `SHA256SUM`
+//! metadata. So we unpack the input into a content directory if it isn't one
+//! already, and then pack that with the same code that `bundle create` uses.
+//! Since `FILELIST` is carried over unchanged, so is the bundle's digest.
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+use tectonic::{config::PersistentConfig, errors::Result, tt_note};
+use tectonic_bundles::detect_bundle;
+use tectonic_io_base::OpenResult;
+use tectonic_status_base::{NoopStatusBackend, StatusBackend};
+use tracing::info;
+
+use super::pack::{bundlev1::BundleV1, zip::BundleZip};
+use crate::v2cli::CommandCustomizations;
+
+/// The metadata files that every bundle must carry.
+const METADATA_FILES: &[&str] = &["FILELIST", "SEARCH", "SHA256SUM"];
+
+/// `bundle convert`: Convert a bundle between the TTB, ZIP, and directory
+/// layouts
+#[derive(Debug, Parser)]
+pub struct BundleConvertCommand {
+    /// The bundle to convert: a `.ttb` or `.zip` file, or a directory
+    input: PathBuf,
+
+    /// Where to write the converted bundle. Its layout is chosen by its
+    /// extension: `.ttb` or `.zip` for a file, and anything else for a
+    /// directory, which must not exist or be empty.
+    output: PathBuf,
+}
+
+impl BundleConvertCommand {
+    pub fn customize(&self, cc: &mut CommandCustomizations) {
+        cc.always_stderr = true;
+    }
+
+    pub fn execute(self, _config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        convert(&self.input, &self.output)?;
+        tt_note!(
+            status,
+            "converted `{}` to `{}`",
+            self.input.display(),
+            self.output.display()
+        );
+        Ok(0)
+    }
+}
+
+/// The layout of a converted bundle.
+enum OutputLayout {
+    Ttb,
+    Zip,
+    Dir,
+}
+
+impl OutputLayout {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ttb") => Self::Ttb,
+            Some("zip") => Self::Zip,
+            _ => Self::Dir,
+        }
+    }
+}
+
+pub(super) fn convert(input: &Path, output: &Path) -> anyhow::Result<()> {
+    let layout = OutputLayout::of(output);
+
+    match layout {
+        OutputLayout::Ttb | OutputLayout::Zip => {
+            if output.exists() {
+                bail!("output `{}` already exists", output.display());
+            }
+        }
+
+        OutputLayout::Dir => {
+            if output.is_file() || (output.is_dir() && fs::read_dir(output)?.next().is_some()) {
+                bail!(
+                    "output directory `{}` already exists and isn't empty",
+                    output.display()
+                );
+            }
+        }
+    }
+
+    // Directory bundles are content directories already; other kinds are
+    // unpacked into a temporary one.
+    let _tempdir;
+
+    let content_dir = if input.is_dir() {
+        input.to_path_buf()
+    } else {
+        let tempdir = tempfile::tempdir()?;
+        unpack(input, tempdir.path())?;
+        let dir = tempdir.path().to_path_buf();
+        _tempdir = tempdir;
+        dir
+    };
+
+    for name in METADATA_FILES {
+        if !content_dir.join(name).is_file() {
+            bail!(
+                "`{}` is missing its `{name}` file; only bundles made by `bundle create` can be converted",
+                input.display()
+            );
+        }
+    }
+
+    match layout {
+        OutputLayout::Ttb => {
+            info!("writing ttbv1 bundle `{}`", output.display());
+            BundleV1::make(Box::new(File::create(output)?), content_dir)?
+        }
+
+        OutputLayout::Zip => {
+            info!("writing zip bundle `{}`", output.display());
+            BundleZip::make(Box::new(File::create(output)?), content_dir)?
+        }
+
+        OutputLayout::Dir => {
+            info!("writing directory bundle `{}`", output.display());
+            copy_content(&content_dir, output)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract the files of a TTB or ZIP bundle into `dest`. The bundle's
+/// `FILELIST` lists all of them, including itself and the other metadata.
+fn unpack(input: &Path, dest: &Path) -> anyhow::Result<()> {
+    let Some(mut bundle) = detect_bundle(input.to_string_lossy().into_owned(), false, None)? else {
+        bail!("`{}` doesn't specify a valid bundle", input.display());
+    };
+
+    info!("unpacking bundle `{}`", input.display());
+    let mut status = NoopStatusBackend::default();
+
+    let filelist = match bundle.input_open_name("FILELIST", &mut status) {
+        OpenResult::Ok(mut ih) => {
+            let mut text = String::new();
+            ih.read_to_string(&mut text)?;
+            text
+        }
+        OpenResult::NotAvailable => bail!(
+            "`{}` has no `FILELIST` file; only bundles made by `bundle create` can be converted",
+            input.display()
+        ),
+        OpenResult::Err(e) => return Err(e),
+    };
+
+    for line in filelist.lines() {
+        let Some((_hash, path)) = line.split_once(' ') else {
+            bail!("malformed filelist line");
+        };
+
+        if !Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            bail!("bundle file path `{path}` is not a plain relative path");
+        }
+
+        let mut ih = match bundle.input_open_name(path, &mut status) {
+            OpenResult::Ok(ih) => ih,
+            OpenResult::NotAvailable => bail!("couldn't find bundle file `{path}`"),
+            OpenResult::Err(e) => return Err(e),
+        };
+
+        let target = dest.join(path);
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file =
+            File::create(&target).with_context(|| format!("while writing bundle file `{path}`"))?;
+        io::copy(&mut ih, &mut file)?;
+    }
+
+    Ok(())
+}
+
+/// Copy the files listed in a content directory's `FILELIST` into `dest`.
+fn copy_content(content_dir: &Path, dest: &Path) -> anyhow::Result<()> {
+    let filelist = BufReader::new(File::open(content_dir.join("FILELIST"))?);
+
+    for line in filelist.lines() {
+        let line = line?;
+
+        let Some((_hash, path)) = line.split_once(' ') else {
+            bail!("malformed filelist line");
+        };
+
+        let target = dest.join(path);
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::copy(content_dir.join(path), &target)
+            .with_context(|| format!("while copying bundle file `{path}`"))?;
+    }
+
+    Ok(())
+}
@@ -74,19 +74,35 @@ pub struct BundleCreateCommand {
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum BundleFormat {
+    /// A ttbv1 bundle, which can be read efficiently over the network
     #[value(name = "v1")]
     BundleV1,
+
+    /// A ZIP archive of the bundle's content directory
+    #[value(name = "zip")]
+    Zip,
 }
 
 impl Display for BundleFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::BundleV1 => write!(f, "v1")?,
+            Self::Zip => write!(f, "zip")?,
         }
         Ok(())
     }
 }
 
+impl BundleFormat {
+    /// The file extension of bundles in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::BundleV1 => "ttb",
+            Self::Zip => "zip",
+        }
+    }
+}
+
 impl TectonicCommand for BundleCreateCommand {
     fn customize(&self, cc: &mut CommandCustomizations) {
         cc.always_stderr = true;
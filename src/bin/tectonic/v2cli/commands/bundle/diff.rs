@@ -0,0 +1,211 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `bundle diff` subcommand: compare the contents of two bundles, such as
+//! two versions of the same bundle.
+
+use clap::Parser;
+use serde_json::json;
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+};
+use tectonic::{config::PersistentConfig, errmsg, errors::Result, tt_note};
+use tectonic_bundles::{detect_bundle, Bundle};
+use tectonic_io_base::{
+    digest::{self, DigestData},
+    OpenResult,
+};
+use tectonic_status_base::StatusBackend;
+
+use crate::v2cli::CommandCustomizations;
+
+/// File extensions for which `--text` shows the changes.
+const TEXT_DIFF_EXTENSIONS: &[&str] = &[".sty", ".cls"];
+
+/// `bundle diff`: Compare the contents of two bundles
+#[derive(Debug, Parser)]
+pub struct BundleDiffCommand {
+    /// Use only resource files cached locally
+    #[arg(short = 'C', long)]
+    only_cached: bool,
+
+    /// Show the changes to `.sty` and `.cls` files as unified diffs
+    #[arg(long)]
+    text: bool,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// The URL or path of the old bundle
+    old: String,
+
+    /// The URL or path of the new bundle
+    new: String,
+}
+
+impl BundleDiffCommand {
+    pub fn customize(&self, cc: &mut CommandCustomizations) {
+        cc.always_stderr = true;
+    }
+
+    pub fn execute(self, _config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        let mut old = self.open(&self.old)?;
+        let mut new = self.open(&self.new)?;
+        let old_files = list_files(old.as_mut(), status)?;
+        let new_files = list_files(new.as_mut(), status)?;
+
+        let added: Vec<&String> = new_files
+            .keys()
+            .filter(|p| !old_files.contains_key(*p))
+            .collect();
+        let removed: Vec<&String> = old_files
+            .keys()
+            .filter(|p| !new_files.contains_key(*p))
+            .collect();
+        let changed: Vec<&String> = old_files
+            .iter()
+            .filter(|(p, h)| new_files.get(*p).is_some_and(|nh| nh != *h))
+            .map(|(p, _)| p)
+            .collect();
+
+        let mut diffs = BTreeMap::new();
+
+        if self.text {
+            for path in &changed {
+                if TEXT_DIFF_EXTENSIONS.iter().any(|e| path.ends_with(e)) {
+                    let a = read_text(old.as_mut(), path, status)?;
+                    let b = read_text(new.as_mut(), path, status)?;
+                    let diff = TextDiff::from_lines(&a, &b)
+                        .unified_diff()
+                        .header(&format!("a/{path}"), &format!("b/{path}"))
+                        .to_string();
+                    diffs.insert(path.as_str(), diff);
+                }
+            }
+        }
+
+        if self.json {
+            let mut report = json!({
+                "added": added,
+                "removed": removed,
+                "changed": changed,
+            });
+
+            if self.text {
+                report["diffs"] = json!(diffs);
+            }
+
+            println!("{report:#}");
+        } else {
+            for path in &added {
+                println!("A  {path}");
+            }
+
+            for path in &removed {
+                println!("D  {path}");
+            }
+
+            for path in &changed {
+                println!("M  {path}");
+            }
+
+            for diff in diffs.values() {
+                print!("\n{diff}");
+            }
+        }
+
+        tt_note!(
+            status,
+            "{} file(s) added, {} removed, {} changed",
+            added.len(),
+            removed.len(),
+            changed.len()
+        );
+        Ok(0)
+    }
+
+    fn open(&self, loc: &str) -> Result<Box<dyn Bundle>> {
+        match detect_bundle(loc.to_owned(), self.only_cached, None)? {
+            Some(b) => Ok(b),
+            None => Err(errmsg!("`{}` doesn't specify a valid bundle", loc)),
+        }
+    }
+}
+
+/// Get the files in a bundle, mapped to the hex SHA-256 digests of their
+/// contents.
+///
+/// TTB bundles list the digests of their files in `FILELIST`, so for them,
+/// only that one file needs to be read. Files that it marks as `nohash` are
+/// bundle metadata, which is expected to change between versions, and are
+/// left out. Other files are hashed directly.
+fn list_files(
+    bundle: &mut dyn Bundle,
+    status: &mut dyn StatusBackend,
+) -> Result<BTreeMap<String, String>> {
+    let listed = match bundle.input_open_name("FILELIST", status) {
+        OpenResult::Ok(mut ih) => {
+            let mut text = String::new();
+            ih.read_to_string(&mut text)?;
+            parse_filelist(&text).unwrap_or_default()
+        }
+        OpenResult::NotAvailable => HashMap::new(),
+        OpenResult::Err(e) => return Err(e.into()),
+    };
+
+    let mut files = BTreeMap::new();
+
+    for path in bundle.all_files() {
+        let hash = match listed.get(&path) {
+            Some(None) => continue,
+            Some(Some(h)) => h.clone(),
+            None => {
+                let mut ih = bundle.input_open_name(&path, status).must_exist()?;
+                let mut dc = digest::create();
+                std::io::copy(&mut ih, &mut dc)?;
+                DigestData::from(dc).to_string()
+            }
+        };
+
+        files.insert(path, hash);
+    }
+
+    Ok(files)
+}
+
+/// Parse the lines of a TTB `FILELIST`, which have the form `<hash> <path>`.
+/// Returns `None` if the text doesn't look like a `FILELIST`.
+fn parse_filelist(text: &str) -> Option<HashMap<String, Option<String>>> {
+    text.lines()
+        .map(|line| {
+            let (hash, path) = line.split_once(' ')?;
+
+            let hash = match hash {
+                "nohash" => None,
+                h if h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                    Some(h.to_ascii_lowercase())
+                }
+                _ => return None,
+            };
+
+            Some((path.to_owned(), hash))
+        })
+        .collect()
+}
+
+/// Read a bundle file as text, for diffing.
+fn read_text(
+    bundle: &mut dyn Bundle,
+    path: &str,
+    status: &mut dyn StatusBackend,
+) -> Result<String> {
+    let mut data = Vec::new();
+    bundle
+        .input_open_name(path, status)
+        .must_exist()?
+        .read_to_end(&mut data)?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
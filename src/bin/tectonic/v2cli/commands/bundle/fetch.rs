@@ -0,0 +1,195 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `bundle fetch` subcommand: download bundle files into the local cache
+//! ahead of time, so that later builds can run with `--only-cached`.
+
+use clap::Parser;
+use tectonic::{
+    config::PersistentConfig,
+    docmodel::{DocumentExt, DocumentSetupOptions},
+    errmsg,
+    errors::Result,
+    tt_note, tt_warning,
+};
+use tectonic_bridge_core::{SecuritySettings, SecurityStance};
+use tectonic_bundles::{detect_bundle, Bundle};
+use tectonic_docmodel::workspace::Workspace;
+use tectonic_status_base::StatusBackend;
+
+use super::get_a_bundle;
+use crate::v2cli::CommandCustomizations;
+
+/// `bundle fetch`: Download bundle files into the local cache
+#[derive(Debug, Parser)]
+pub struct BundleFetchCommand {
+    /// Cache the files used to build the workspace's documents
+    #[arg(long, conflicts_with_all = ["all", "bundle", "specs", "section"])]
+    build: bool,
+
+    /// With `--build`, only build the workspace document with this name
+    #[arg(long = "doc", name = "doc_name", requires = "build")]
+    doc: Option<String>,
+
+    /// With `--build`, disable all known-insecure features
+    #[arg(long, requires = "build")]
+    untrusted: bool,
+
+    /// Cache every file in the bundle
+    #[arg(long, conflicts_with_all = ["specs", "section"])]
+    all: bool,
+
+    /// Cache the files in the directories listed in this `[SEARCH:...]`
+    /// section of the bundle's index
+    #[arg(long = "section", name = "section")]
+    sections: Vec<String>,
+
+    /// Use this URL to find resource files instead of the workspace's bundle
+    #[arg(long, short)]
+    bundle: Option<String>,
+
+    /// Cache the files in these directories, given in search-path syntax
+    /// (e.g. `/tex/latex//`)
+    #[arg(name = "specs")]
+    specs: Vec<String>,
+}
+
+impl BundleFetchCommand {
+    pub fn customize(&self, _cc: &mut CommandCustomizations) {}
+
+    pub fn execute(self, config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        if self.build {
+            return self.fetch_for_build(config, status);
+        }
+
+        if !self.all && self.specs.is_empty() && self.sections.is_empty() {
+            return Err(errmsg!(
+                "specify `--build`, `--all`, `--section`, or at least one search-path specification"
+            ));
+        }
+
+        let mut bundle = match &self.bundle {
+            Some(loc) => match detect_bundle(loc.clone(), false, None)? {
+                Some(b) => b,
+                None => return Err(errmsg!("`{}` doesn't specify a valid bundle", loc)),
+            },
+            None => get_a_bundle(config, false, status)?,
+        };
+
+        let all = self.all;
+        let mut specs = self.specs;
+
+        for name in &self.sections {
+            specs.extend(bundle.search_section(name)?);
+        }
+
+        let n = bundle.prefetch(
+            &mut |path| all || specs.iter().any(|s| spec_matches(s, path)),
+            status,
+        )?;
+
+        if n == 0 {
+            tt_warning!(status, "no bundle files matched");
+        } else {
+            tt_note!(
+                status,
+                "{} bundle file(s) are cached and ready for offline use",
+                n
+            );
+        }
+
+        Ok(0)
+    }
+
+    /// Build the workspace's documents without writing any outputs. Every
+    /// bundle file that the builds read ends up in the cache.
+    fn fetch_for_build(
+        self,
+        config: PersistentConfig,
+        status: &mut dyn StatusBackend,
+    ) -> Result<i32> {
+        let ws = Workspace::open_from_environment()?;
+        let docs = ws.select_documents(self.doc.as_deref())?;
+
+        let stance = if self.untrusted {
+            SecurityStance::DisableInsecures
+        } else {
+            SecurityStance::MaybeAllowInsecures
+        };
+
+        // The build cache has to be bypassed, since a build that it skips
+        // won't fetch anything.
+        let mut setup_options =
+            DocumentSetupOptions::new_with_security(SecuritySettings::new(stance));
+        setup_options.only_cached(false).build_cache(false);
+
+        for doc in docs {
+            for output_name in doc.output_names() {
+                tt_note!(
+                    status,
+                    "fetching files for document `{}`, output `{}`",
+                    doc.name,
+                    output_name
+                );
+
+                let mut builder = doc.setup_session(output_name, &setup_options, status)?;
+                builder
+                    .format_cache_path(config.format_cache_path()?)
+                    .do_not_write_output_files();
+                crate::compile::run_and_report(builder, status)?;
+            }
+        }
+
+        tt_note!(status, "all of the files needed for the build are cached");
+        Ok(0)
+    }
+}
+
+/// Check whether a bundle file path lies within a directory given in
+/// search-path syntax. A specification ending in `//` matches its directory
+/// and all of its subdirectories; otherwise, only files directly inside the
+/// directory match.
+fn spec_matches(spec: &str, path: &str) -> bool {
+    let (dir, recursive) = match spec.strip_suffix("//") {
+        Some(d) => (d, true),
+        None => (spec.strip_suffix('/').unwrap_or(spec), false),
+    };
+
+    let dir = dir.trim_start_matches('/');
+    let parent = path.rsplit_once('/').map_or("", |(p, _)| p);
+
+    if recursive {
+        dir.is_empty()
+            || parent == dir
+            || parent
+                .strip_prefix(dir)
+                .is_some_and(|rest| rest.starts_with('/'))
+    } else {
+        parent == dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spec_matches;
+
+    #[test]
+    fn recursive_spec() {
+        assert!(spec_matches("/tex/latex//", "tex/latex/article.cls"));
+        assert!(spec_matches("/tex/latex//", "tex/latex/base/size10.clo"));
+        assert!(!spec_matches("/tex/latex//", "tex/latexmk/foo.tex"));
+        assert!(!spec_matches("/tex/latex//", "tex/plain/plain.tex"));
+        assert!(spec_matches("//", "SEARCH"));
+        assert!(spec_matches("//", "tex/plain/plain.tex"));
+    }
+
+    #[test]
+    fn non_recursive_spec() {
+        assert!(spec_matches("/tex/latex/", "tex/latex/article.cls"));
+        assert!(spec_matches("/tex/latex", "tex/latex/article.cls"));
+        assert!(!spec_matches("/tex/latex/", "tex/latex/base/size10.clo"));
+        assert!(!spec_matches("/tex/latex/", "tex/latexmk/foo.tex"));
+        assert!(spec_matches("/", "SEARCH"));
+        assert!(!spec_matches("/", "tex/plain/plain.tex"));
+    }
+}
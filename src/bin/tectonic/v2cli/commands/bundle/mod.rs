@@ -1,5 +1,10 @@
+use cache::BundleCacheCommand;
 use clap::{Parser, Subcommand};
+use convert::BundleConvertCommand;
 use create::BundleCreateCommand;
+use diff::BundleDiffCommand;
+use fetch::BundleFetchCommand;
+use serve::BundleServeCommand;
 use tectonic::{
     config::PersistentConfig,
     docmodel::{DocumentExt, DocumentSetupOptions},
@@ -9,13 +14,20 @@ use tectonic::{
 use tectonic_bundles::Bundle;
 use tectonic_docmodel::workspace::Workspace;
 use tectonic_status_base::StatusBackend;
+use update::BundleUpdateCommand;
 
 use crate::v2cli::{CommandCustomizations, TectonicCommand};
 
 mod actions;
+mod cache;
+mod convert;
 mod create;
+mod diff;
+mod fetch;
 mod pack;
 mod select;
+mod serve;
+mod update;
 
 fn get_a_bundle(
     _config: PersistentConfig,
@@ -26,7 +38,7 @@ fn get_a_bundle(
 
     match Workspace::open_from_environment() {
         Ok(ws) => {
-            let doc = ws.first_document();
+            let doc = ws.current_document().unwrap_or_else(|| ws.first_document());
             let mut options: DocumentSetupOptions = Default::default();
             options.only_cached(only_cached);
             doc.bundle(&options)
@@ -58,14 +70,38 @@ pub struct BundleCommand {
 
 #[derive(Debug, Subcommand)]
 enum BundleCommands {
+    #[command(name = "cache")]
+    /// Inspect and clean up the local bundle cache
+    Cache(BundleCacheCommand),
+
     #[command(name = "cat")]
     /// Dump the contents of a file in the bundle
     Cat(BundleCatCommand),
 
+    #[command(name = "convert")]
+    /// Convert a bundle between the TTB, ZIP, and directory layouts
+    Convert(BundleConvertCommand),
+
+    #[command(name = "diff")]
+    /// Compare the contents of two bundles
+    Diff(BundleDiffCommand),
+
+    #[command(name = "fetch")]
+    /// Download bundle files into the local cache ahead of time
+    Fetch(BundleFetchCommand),
+
     #[command(name = "search")]
     /// Filter the list of filenames contained in the bundle
     Search(BundleSearchCommand),
 
+    #[command(name = "serve")]
+    /// Share a local TTB bundle over HTTP
+    Serve(BundleServeCommand),
+
+    #[command(name = "update")]
+    /// Pin documents to the current version of their bundles in Tectonic.lock
+    Update(BundleUpdateCommand),
+
     #[command(name = "create")]
     /// Create a new bundle
     Create(BundleCreateCommand),
@@ -74,16 +110,28 @@ enum BundleCommands {
 impl TectonicCommand for BundleCommand {
     fn customize(&self, cc: &mut CommandCustomizations) {
         match &self.command {
+            BundleCommands::Cache(c) => c.customize(cc),
             BundleCommands::Cat(c) => c.customize(cc),
+            BundleCommands::Convert(c) => c.customize(cc),
+            BundleCommands::Diff(c) => c.customize(cc),
+            BundleCommands::Fetch(c) => c.customize(cc),
             BundleCommands::Search(c) => c.customize(cc),
+            BundleCommands::Serve(c) => c.customize(cc),
+            BundleCommands::Update(c) => c.customize(cc),
             BundleCommands::Create(c) => c.customize(cc),
         }
     }
 
     fn execute(self, config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
         match self.command {
+            BundleCommands::Cache(c) => c.execute(config, status),
             BundleCommands::Cat(c) => c.execute(config, status),
+            BundleCommands::Convert(c) => c.execute(config, status),
+            BundleCommands::Diff(c) => c.execute(config, status),
+            BundleCommands::Fetch(c) => c.execute(config, status),
             BundleCommands::Search(c) => c.execute(config, status),
+            BundleCommands::Serve(c) => c.execute(config, status),
+            BundleCommands::Update(c) => c.execute(config, status),
             BundleCommands::Create(c) => c.execute(config, status),
         }
     }
@@ -61,8 +61,8 @@ pub struct BundleV1 {
 }
 
 impl BundleV1 {
-    pub fn make(target: Box<dyn WriteSeek>, build_dir: PathBuf) -> Result<()> {
-        let mut bundle = BundleV1::new(target, build_dir)?;
+    pub fn make(target: Box<dyn WriteSeek>, content_dir: PathBuf) -> Result<()> {
+        let mut bundle = BundleV1::new(target, content_dir)?;
 
         bundle.add_files()?;
         bundle.write_index()?;
@@ -71,11 +71,11 @@ impl BundleV1 {
         Ok(())
     }
 
-    fn new(target: Box<dyn WriteSeek>, build_dir: PathBuf) -> Result<BundleV1> {
+    fn new(target: Box<dyn WriteSeek>, content_dir: PathBuf) -> Result<BundleV1> {
         Ok(BundleV1 {
             filelist: Vec::new(),
             target,
-            content_dir: build_dir.join("content"),
+            content_dir,
             index_start: 0,
             index_gzip_len: 0,
             index_real_len: 0,
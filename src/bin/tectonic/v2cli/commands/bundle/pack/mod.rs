@@ -1 +1,2 @@
 pub mod bundlev1;
+pub mod zip;
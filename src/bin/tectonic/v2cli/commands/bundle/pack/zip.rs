@@ -0,0 +1,65 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+};
+use tracing::info;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::bundlev1::WriteSeek;
+
+/// A ZIP bundle.
+///
+/// Unlike a ttbv1 bundle, a ZIP bundle has no index of its own: it is simply
+/// an archive of the content directory. Readers find files using the
+/// `SEARCH` file, which is included along with `FILELIST` and `SHA256SUM`.
+pub struct BundleZip {
+    zip: ZipWriter<Box<dyn WriteSeek>>,
+    content_dir: PathBuf,
+}
+
+impl BundleZip {
+    pub fn make(target: Box<dyn WriteSeek>, content_dir: PathBuf) -> Result<()> {
+        let mut bundle = BundleZip {
+            zip: ZipWriter::new(target),
+            content_dir,
+        };
+
+        bundle.add_files()?;
+        bundle.zip.finish()?;
+
+        Ok(())
+    }
+
+    fn add_files(&mut self) -> Result<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let filelist_file = File::open(self.content_dir.join("FILELIST"))?;
+        let reader = BufReader::new(filelist_file);
+
+        info!("Building zip bundle...");
+
+        let mut count = 0usize;
+
+        for line in reader.lines() {
+            let line = line?;
+
+            // FILELIST lines are `<hash> <path>`, and paths may contain spaces.
+            let Some((_hash, path)) = line.split_once(' ') else {
+                bail!("malformed filelist line");
+            };
+
+            let mut file = File::open(self.content_dir.join(path))
+                .with_context(|| format!("while opening bundle file `{path}`"))?;
+
+            self.zip.start_file(path, options)?;
+            io::copy(&mut file, &mut self.zip)?;
+            count += 1;
+        }
+
+        info!("Wrote {count} files");
+
+        Ok(())
+    }
+}
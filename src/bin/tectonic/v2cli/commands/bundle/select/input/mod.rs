@@ -1,5 +1,6 @@
 mod dir;
 mod tar;
+mod texlive;
 
 use anyhow::Result;
 use std::{io::Read, path::PathBuf};
@@ -12,6 +13,7 @@ trait BundleInput {
 pub enum Input {
     Directory(dir::DirBundleInput),
     Tarball(tar::TarBundleInput),
+    TexLive(texlive::TexLiveBundleInput),
 }
 
 impl<'a> Input {
@@ -23,6 +25,12 @@ impl<'a> Input {
         Ok(Self::Tarball(tar::TarBundleInput::new(path, root)?))
     }
 
+    pub fn new_texlive(path: PathBuf, tlpdb: Option<PathBuf>, packages: &[String]) -> Result<Self> {
+        Ok(Self::TexLive(texlive::TexLiveBundleInput::new(
+            path, tlpdb, packages,
+        )?))
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn iter_files(
         &'a mut self,
@@ -30,6 +38,7 @@ impl<'a> Input {
         match self {
             Self::Directory(x) => Box::new(x.iter_files()),
             Self::Tarball(x) => Box::new(x.iter_files()),
+            Self::TexLive(x) => Box::new(x.iter_files()),
         }
     }
 
@@ -37,6 +46,14 @@ impl<'a> Input {
         match self {
             Self::Directory(_) => None,
             Self::Tarball(x) => Some(x.hash()),
+            Self::TexLive(_) => None,
+        }
+    }
+
+    pub fn package_list(&self) -> Option<String> {
+        match self {
+            Self::TexLive(x) => Some(x.package_list()),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,307 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::{self, File},
+    io::Read,
+    path::PathBuf,
+};
+use tracing::{info, warn};
+
+use super::BundleInput;
+
+/// The part of a TeX Live package record that we care about.
+#[derive(Default)]
+struct TlpdbPackage {
+    revision: String,
+    depends: Vec<String>,
+    runfiles: Vec<String>,
+}
+
+/// Parse a TeX Live package database.
+///
+/// A tlpdb is a sequence of records separated by blank lines. Each line of a
+/// record is a key followed by a value, except that lines starting with a
+/// space continue the file list started by the preceding `*files` key.
+fn parse_tlpdb(text: &str) -> Result<HashMap<String, TlpdbPackage>> {
+    let mut packages = HashMap::new();
+    let mut current: Option<(String, TlpdbPackage)> = None;
+    let mut in_runfiles = false;
+
+    for (n, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            if let Some((name, pkg)) = current.take() {
+                packages.insert(name, pkg);
+            }
+
+            in_runfiles = false;
+            continue;
+        }
+
+        if let Some(file) = line.strip_prefix(' ') {
+            // File paths can't contain spaces, but they may be followed by
+            // attributes.
+            if in_runfiles {
+                if let (Some((_, pkg)), Some(path)) = (current.as_mut(), file.split(' ').next()) {
+                    pkg.runfiles.push(path.to_owned());
+                }
+            }
+
+            continue;
+        }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        in_runfiles = key == "runfiles";
+
+        match (key, current.as_mut()) {
+            ("name", _) => {
+                if let Some((name, pkg)) = current.take() {
+                    packages.insert(name, pkg);
+                }
+
+                current = Some((value.to_owned(), TlpdbPackage::default()));
+            }
+
+            (_, None) => bail!("tlpdb line {} is outside of a package record", n + 1),
+            ("revision", Some((_, pkg))) => pkg.revision = value.to_owned(),
+            ("depend", Some((_, pkg))) => pkg.depends.push(value.to_owned()),
+            _ => {}
+        }
+    }
+
+    if let Some((name, pkg)) = current.take() {
+        packages.insert(name, pkg);
+    }
+
+    Ok(packages)
+}
+
+/// Select the given packages and collections, and everything that they depend
+/// on, from a parsed package database. Returns the names and revisions of the
+/// selected packages, and the sorted paths of their run files within the
+/// `texmf-dist` tree.
+fn select_packages(
+    db: &HashMap<String, TlpdbPackage>,
+    requested: &[String],
+) -> Result<(BTreeMap<String, String>, Vec<String>)> {
+    let mut packages = BTreeMap::new();
+    let mut files = Vec::new();
+    let mut queue: VecDeque<(&str, Option<&str>)> =
+        requested.iter().map(|p| (p.as_str(), None)).collect();
+
+    while let Some((name, wanted_by)) = queue.pop_front() {
+        // Architecture-specific packages hold binaries, which we don't want.
+        if name.ends_with(".ARCH") || packages.contains_key(name) {
+            continue;
+        }
+
+        let Some(pkg) = db.get(name) else {
+            match wanted_by {
+                None => bail!("package `{name}` is not in the TeX Live package database"),
+                Some(p) => {
+                    warn!("package `{name}`, needed by `{p}`, is not installed; skipping");
+                    continue;
+                }
+            }
+        };
+
+        packages.insert(name.to_owned(), pkg.revision.clone());
+
+        for f in &pkg.runfiles {
+            // Network package databases use a placeholder for the tree.
+            let f = match f.strip_prefix("RELOC/") {
+                Some(rest) => format!("texmf-dist/{rest}"),
+                None => f.clone(),
+            };
+
+            if f.starts_with("texmf-dist/") {
+                files.push(f);
+            }
+        }
+
+        queue.extend(pkg.depends.iter().map(|d| (d.as_str(), Some(name))));
+    }
+
+    files.sort();
+    files.dedup();
+
+    Ok((packages, files))
+}
+
+/// Files selected from an installed TeX Live distribution.
+pub struct TexLiveBundleInput {
+    /// The root of the installation, which contains `texmf-dist`.
+    root: PathBuf,
+
+    /// The selected files, relative to `root`.
+    files: Vec<String>,
+
+    /// The names and revisions of the selected packages.
+    packages: BTreeMap<String, String>,
+}
+
+impl TexLiveBundleInput {
+    /// Select the run files of the given packages and collections, and of
+    /// everything that they depend on, from the TeX Live installation rooted
+    /// at `root`.
+    pub fn new(root: PathBuf, tlpdb: Option<PathBuf>, requested: &[String]) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("while opening TeX Live installation {root:?}"))?;
+        let tlpdb = tlpdb.unwrap_or_else(|| root.join("tlpkg/texlive.tlpdb"));
+
+        info!("reading TeX Live package database {tlpdb:?}");
+        let text = fs::read_to_string(&tlpdb)
+            .with_context(|| format!("while reading TeX Live package database {tlpdb:?}"))?;
+        let db = parse_tlpdb(&text)?;
+
+        let (packages, files) = select_packages(&db, requested)?;
+
+        for f in &files {
+            if !root.join(f).is_file() {
+                bail!("TeX Live file `{f}` is listed in the package database but is not installed");
+            }
+        }
+
+        info!(
+            "selected {} files from {} TeX Live packages",
+            files.len(),
+            packages.len()
+        );
+
+        Ok(Self {
+            root,
+            files,
+            packages,
+        })
+    }
+
+    /// A list of the selected packages and their revisions, one per line.
+    pub fn package_list(&self) -> String {
+        self.packages
+            .iter()
+            .map(|(name, rev)| format!("{name} {rev}\n"))
+            .collect()
+    }
+}
+
+impl BundleInput for TexLiveBundleInput {
+    fn iter_files(&mut self) -> impl Iterator<Item = Result<(String, Box<dyn Read + '_>)>> {
+        // Only files under `texmf-dist/` are ever selected.
+        self.files.iter().map(|f| {
+            let path = f.strip_prefix("texmf-dist/").unwrap_or(f);
+
+            Ok((
+                path.to_owned(),
+                Box::new(File::open(self.root.join(f))?) as Box<dyn Read>,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TLPDB: &str = "\
+name collection-basic
+category Collection
+revision 100
+depend plain
+depend tex.ARCH
+depend collection-extra
+
+name collection-extra
+category Collection
+revision 300
+depend collection-basic
+depend not-installed
+
+name plain
+category Package
+revision 200
+docfiles size=1
+ texmf-dist/doc/plain/plain.pdf
+runfiles size=3
+ RELOC/tex/plain/base/plain.tex
+ texmf-dist/tex/plain/base/plain.ini details=\"Format\"
+ tlpkg/tlpostcode/plain.pl
+
+name tex.x86_64-linux
+category Package
+revision 400
+binfiles arch=x86_64-linux size=1
+ bin/x86_64-linux/tex
+";
+
+    fn requested(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn parse() {
+        let db = parse_tlpdb(TLPDB).unwrap();
+        assert_eq!(db.len(), 4);
+
+        let plain = &db["plain"];
+        assert_eq!(plain.revision, "200");
+        assert!(plain.depends.is_empty());
+        assert_eq!(
+            plain.runfiles,
+            [
+                "RELOC/tex/plain/base/plain.tex",
+                "texmf-dist/tex/plain/base/plain.ini",
+                "tlpkg/tlpostcode/plain.pl",
+            ]
+        );
+
+        let basic = &db["collection-basic"];
+        assert_eq!(basic.depends, ["plain", "tex.ARCH", "collection-extra"]);
+        assert!(basic.runfiles.is_empty());
+    }
+
+    #[test]
+    fn parse_stray_line() {
+        assert!(parse_tlpdb("revision 1\nname foo\n").is_err());
+    }
+
+    #[test]
+    fn select_transitive() {
+        let db = parse_tlpdb(TLPDB).unwrap();
+        let (packages, files) = select_packages(&db, &requested(&["collection-basic"])).unwrap();
+
+        // The dependency cycle between the collections is harmless, the
+        // architecture-specific package is skipped, and so is the missing
+        // dependency.
+        assert_eq!(
+            packages.into_iter().collect::<Vec<_>>(),
+            [
+                ("collection-basic".to_owned(), "100".to_owned()),
+                ("collection-extra".to_owned(), "300".to_owned()),
+                ("plain".to_owned(), "200".to_owned()),
+            ]
+        );
+
+        // Only run files in the main tree are selected.
+        assert_eq!(
+            files,
+            [
+                "texmf-dist/tex/plain/base/plain.ini",
+                "texmf-dist/tex/plain/base/plain.tex",
+            ]
+        );
+    }
+
+    #[test]
+    fn select_single() {
+        let db = parse_tlpdb(TLPDB).unwrap();
+        let (packages, files) = select_packages(&db, &requested(&["plain", "plain"])).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn select_unknown() {
+        let db = parse_tlpdb(TLPDB).unwrap();
+        assert!(select_packages(&db, &requested(&["not-installed"])).is_err());
+    }
+}
@@ -435,6 +435,33 @@ impl FilePicker {
                 info!("OK, tar hash matches bundle config");
                 x
             }
+            BundleInputSource::TexLive {
+                path,
+                packages,
+                tlpdb,
+            } => {
+                let x = match Input::new_texlive(
+                    self.bundle_dir.join(path),
+                    tlpdb.as_ref().map(|p| self.bundle_dir.join(p)),
+                    packages,
+                ) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        error!("could not add source `{source}` from TeX Live");
+                        return Err(e);
+                    }
+                };
+
+                // Record exactly what went into this input
+                self.add_file(
+                    Path::new("TLPDB-PACKAGES"),
+                    source,
+                    &mut Cursor::new(x.package_list().unwrap()),
+                    &HashMap::new(),
+                )?;
+
+                x
+            }
         };
 
         for x in source_backend.iter_files() {
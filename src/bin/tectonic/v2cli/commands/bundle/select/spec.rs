@@ -69,4 +69,16 @@ pub enum BundleInputSource {
         path: PathBuf,
         root_dir: Option<PathBuf>,
     },
+
+    #[serde(rename = "texlive")]
+    TexLive {
+        /// The root of a TeX Live installation, containing `texmf-dist`
+        path: PathBuf,
+
+        /// Packages and collections to include, along with their dependencies
+        packages: Vec<String>,
+
+        /// The package database [default: `<path>/tlpkg/texlive.tlpdb`]
+        tlpdb: Option<PathBuf>,
+    },
 }
@@ -0,0 +1,471 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `bundle serve` subcommand: share a local TTB bundle over HTTP.
+//!
+//! Network TTB bundles are read entirely through HTTP range requests: the
+//! client fetches the fixed-size header, uses it to find the index, and then
+//! fetches individual files. So all that a server has to do is serve the
+//! bundle file itself with proper support for byte ranges. That's simple
+//! enough that we implement the small subset of HTTP/1.1 that's needed here
+//! rather than pulling in a web framework.
+//!
+//! A bundle whose files have all been downloaded into the local cache can be
+//! served too. Its cache directory is laid out like the bundle's content
+//! directory, so we pack it into a temporary TTB file and serve that.
+
+use clap::Parser;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tectonic::{config::PersistentConfig, ctry, errmsg, errors::Result, tt_note};
+use tectonic_bundles::{cache::CacheDirectory, ttb_fs::TTBFsBundle, Bundle};
+use tectonic_io_base::digest::DigestData;
+use tectonic_status_base::StatusBackend;
+use tracing::{info, warn};
+
+use super::convert::convert;
+use crate::v2cli::CommandCustomizations;
+
+/// How long an idle connection is kept open.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The longest request line and headers that we accept, in bytes. Clients
+/// send a handful of short headers.
+const MAX_HEAD_LEN: u64 = 16 * 1024;
+
+/// The most connections that are served at once. Each one gets a thread.
+const MAX_CONNECTIONS: usize = 64;
+
+/// `bundle serve`: Share a local TTB bundle over HTTP
+#[derive(Debug, Parser)]
+pub struct BundleServeCommand {
+    /// The address and port to listen on. Use `0.0.0.0:<port>` to make the
+    /// bundle available to other machines.
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    addr: String,
+
+    /// Serve the bundle with this digest from the local bundle cache. All of
+    /// its files must have been cached.
+    #[arg(
+        long,
+        value_name = "DIGEST",
+        conflicts_with = "bundle",
+        required_unless_present = "bundle"
+    )]
+    cache: Option<String>,
+
+    /// The TTB bundle file to serve
+    bundle: Option<PathBuf>,
+}
+
+impl BundleServeCommand {
+    pub fn customize(&self, cc: &mut CommandCustomizations) {
+        cc.always_stderr = true;
+    }
+
+    pub fn execute(self, _config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        // The packed copy of a cached bundle lives as long as the server.
+        let _tempdir;
+
+        let bundle = match (self.bundle, &self.cache) {
+            (Some(path), _) => path,
+            (None, Some(text)) => {
+                let tempdir = tempfile::tempdir()?;
+                let path = pack_cached_bundle(text, tempdir.path(), status)?;
+                _tempdir = tempdir;
+                path
+            }
+            (None, None) => return Err(errmsg!("specify a bundle file or `--cache`")),
+        };
+
+        // Make sure that clients will actually be able to use the file.
+        let digest = ctry!(
+            TTBFsBundle::open(&bundle).and_then(|mut b| b.get_digest());
+            "`{}` is not a valid TTB bundle", bundle.display()
+        );
+
+        // Clients only recognize TTB bundle URLs by their extension.
+        let Some(name) = bundle
+            .file_name()
+            .and_then(|n| n.to_str())
+            .filter(|n| n.ends_with(".ttb"))
+        else {
+            return Err(errmsg!(
+                "the name of bundle file `{}` must end in `.ttb`",
+                bundle.display()
+            ));
+        };
+
+        let served = Arc::new(ServedBundle {
+            path: bundle.clone(),
+            url_path: format!("/{name}"),
+        });
+
+        let listener = ctry!(TcpListener::bind(&self.addr); "couldn't listen on `{}`", self.addr);
+        let local_addr = listener.local_addr()?;
+
+        tt_note!(status, "serving bundle {} (digest {})", name, digest);
+        tt_note!(
+            status,
+            "the bundle URL is http://{}{}",
+            local_addr,
+            served.url_path
+        );
+
+        let active = Arc::new(AtomicUsize::new(0));
+
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("failed to accept a connection: {e}");
+                    continue;
+                }
+            };
+
+            if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                active.fetch_sub(1, Ordering::SeqCst);
+                warn!("too many connections; turning one away");
+                let _ = write_head(&mut stream, "503 Service Unavailable", 0, &[], true);
+                continue;
+            }
+
+            let slot = ConnectionSlot(active.clone());
+            let served = served.clone();
+
+            thread::spawn(move || {
+                let _slot = slot;
+                let peer = stream
+                    .peer_addr()
+                    .map_or_else(|_| "unknown peer".to_owned(), |a| a.to_string());
+
+                if let Err(e) = served.handle_connection(stream) {
+                    // Clients hanging up or going idle are routine.
+                    if !matches!(
+                        e.kind(),
+                        io::ErrorKind::BrokenPipe
+                            | io::ErrorKind::ConnectionReset
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::WouldBlock
+                    ) {
+                        warn!("error serving {peer}: {e}");
+                    }
+                }
+            });
+        }
+
+        Ok(0)
+    }
+}
+
+/// Pack the files of the cached bundle whose digest is given by *text* into a
+/// TTB file in *dir*, returning its path.
+fn pack_cached_bundle(text: &str, dir: &Path, status: &mut dyn StatusBackend) -> Result<PathBuf> {
+    let digest = match DigestData::from_str(text) {
+        Ok(d) => d,
+        Err(e) => return Err(errmsg!("invalid bundle digest `{}`: {}", text, e)),
+    };
+
+    let cache = CacheDirectory::new(None)?;
+    let bundle_dir = cache.bundle_dir(&digest);
+
+    if !bundle_dir.is_dir() {
+        return Err(errmsg!("no data for bundle `{}` in the cache", text));
+    }
+
+    let uncached = cache.uncached_files(&digest)?;
+
+    if !uncached.is_empty() {
+        return Err(errmsg!(
+            "{} of the files of bundle `{}` aren't cached; use `tectonic -X bundle fetch --all` to fetch them",
+            uncached.len(),
+            text
+        ));
+    }
+
+    tt_note!(status, "packing the cached files of bundle {}", digest);
+    let path = dir.join(format!("{digest}.ttb"));
+    convert(&bundle_dir, &path)?;
+    Ok(path)
+}
+
+/// Keeps count of the connections being served. The count is decremented when
+/// a connection's thread finishes.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A byte range requested by a client, resolved against the size of the file.
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// Send the whole file. This is also the answer to a `Range` header that
+    /// we can't or don't want to handle, as HTTP allows.
+    Whole,
+
+    /// Send the bytes from the first offset to the second, inclusive.
+    Part(u64, u64),
+
+    /// The requested range lies beyond the end of the file.
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    /// Interpret the value of a `Range` header for a file of length `len`.
+    /// Multiple ranges are legal but no Tectonic client asks for them, so we
+    /// fall back to sending the whole file in that case.
+    fn parse(header: &str, len: u64) -> Self {
+        let Some(spec) = header.trim().strip_prefix("bytes=") else {
+            return ByteRange::Whole;
+        };
+
+        if spec.contains(',') {
+            return ByteRange::Whole;
+        }
+
+        let Some((first, last)) = spec.trim().split_once('-') else {
+            return ByteRange::Whole;
+        };
+
+        let first = first.trim();
+        let last = last.trim();
+
+        // A "suffix" range, giving the number of bytes at the end of the file.
+        if first.is_empty() {
+            return match last.parse::<u64>() {
+                Ok(0) => ByteRange::Unsatisfiable,
+                Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+                Ok(n) => ByteRange::Part(len.saturating_sub(n), len - 1),
+                Err(_) => ByteRange::Whole,
+            };
+        }
+
+        let Ok(first) = first.parse::<u64>() else {
+            return ByteRange::Whole;
+        };
+
+        let last = if last.is_empty() {
+            None
+        } else {
+            match last.parse::<u64>() {
+                Ok(n) if n >= first => Some(n),
+                _ => return ByteRange::Whole,
+            }
+        };
+
+        if first >= len {
+            ByteRange::Unsatisfiable
+        } else {
+            ByteRange::Part(first, last.map_or(len - 1, |l| l.min(len - 1)))
+        }
+    }
+}
+
+/// The bundle being served.
+struct ServedBundle {
+    path: PathBuf,
+    url_path: String,
+}
+
+impl ServedBundle {
+    /// Answer requests on a connection until the client closes it.
+    fn handle_connection(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        loop {
+            let mut budget = MAX_HEAD_LEN;
+            let mut request_line = String::new();
+
+            match read_head_line(&mut reader, &mut budget, &mut request_line)? {
+                Some(0) => return Ok(()),
+                Some(_) => {}
+                None => {
+                    return write_head(
+                        &mut writer,
+                        "431 Request Header Fields Too Large",
+                        0,
+                        &[],
+                        true,
+                    )
+                }
+            }
+
+            let mut range = None;
+            let mut close = false;
+
+            loop {
+                let mut line = String::new();
+
+                match read_head_line(&mut reader, &mut budget, &mut line)? {
+                    Some(0) => return Ok(()),
+                    Some(_) => {}
+                    None => {
+                        return write_head(
+                            &mut writer,
+                            "431 Request Header Fields Too Large",
+                            0,
+                            &[],
+                            true,
+                        )
+                    }
+                }
+
+                let line = line.trim_end();
+
+                if line.is_empty() {
+                    break;
+                }
+
+                if let Some((key, value)) = line.split_once(':') {
+                    let value = value.trim();
+
+                    if key.eq_ignore_ascii_case("range") {
+                        range = Some(value.to_owned());
+                    } else if key.eq_ignore_ascii_case("connection") {
+                        close = value.eq_ignore_ascii_case("close");
+                    }
+                }
+            }
+
+            let mut words = request_line.split_whitespace();
+            let method = words.next().unwrap_or("");
+            let target = words.next().unwrap_or("");
+            let version = words.next().unwrap_or("");
+
+            // We don't bother with HTTP/1.0 keepalive.
+            close |= version != "HTTP/1.1";
+
+            info!("{method} {target} {}", range.as_deref().unwrap_or(""));
+            self.respond(&mut writer, method, target, range.as_deref(), close)?;
+
+            if close {
+                return Ok(());
+            }
+        }
+    }
+
+    fn respond(
+        &self,
+        w: &mut TcpStream,
+        method: &str,
+        target: &str,
+        range: Option<&str>,
+        close: bool,
+    ) -> io::Result<()> {
+        let head_only = method == "HEAD";
+
+        if method != "GET" && !head_only {
+            return write_head(w, "405 Method Not Allowed", 0, &["Allow: GET, HEAD"], close);
+        }
+
+        let path = target.split(['?', '#']).next().unwrap_or("");
+
+        if path != self.url_path {
+            return write_head(w, "404 Not Found", 0, &[], close);
+        }
+
+        // Reopen the file for every request, so that an updated bundle is
+        // picked up without restarting the server.
+        let mut file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("couldn't open {}: {e}", self.path.display());
+                return write_head(w, "500 Internal Server Error", 0, &[], close);
+            }
+        };
+
+        let len = file.metadata()?.len();
+
+        let (status, start, count, content_range) =
+            match range.map_or(ByteRange::Whole, |r| ByteRange::parse(r, len)) {
+                ByteRange::Whole => ("200 OK", 0, len, None),
+
+                ByteRange::Part(first, last) => (
+                    "206 Partial Content",
+                    first,
+                    last - first + 1,
+                    Some(format!("Content-Range: bytes {first}-{last}/{len}")),
+                ),
+
+                ByteRange::Unsatisfiable => {
+                    let cr = format!("Content-Range: bytes */{len}");
+                    return write_head(w, "416 Range Not Satisfiable", 0, &[&cr], close);
+                }
+            };
+
+        let mut extra = vec![
+            "Accept-Ranges: bytes",
+            "Content-Type: application/octet-stream",
+        ];
+
+        if let Some(cr) = &content_range {
+            extra.push(cr);
+        }
+
+        write_head(w, status, count, &extra, close)?;
+
+        if !head_only {
+            file.seek(SeekFrom::Start(start))?;
+            io::copy(&mut file.take(count), w)?;
+        }
+
+        w.flush()
+    }
+}
+
+/// Read one line of a request's head, counting it against *budget*. Returns
+/// the number of bytes read, which is zero at the end of the stream, or
+/// `None` if the budget runs out before the end of the line.
+fn read_head_line(
+    reader: &mut impl BufRead,
+    budget: &mut u64,
+    line: &mut String,
+) -> io::Result<Option<usize>> {
+    let n = reader.take(*budget).read_line(line)?;
+    *budget -= n as u64;
+
+    if *budget == 0 && !line.ends_with('\n') {
+        return Ok(None);
+    }
+
+    Ok(Some(n))
+}
+
+/// Write the status line and headers of a response.
+fn write_head(
+    w: &mut TcpStream,
+    status: &str,
+    content_length: u64,
+    extra: &[&str],
+    close: bool,
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {status}\r\nContent-Length: {content_length}\r\n");
+
+    for h in extra {
+        head.push_str(h);
+        head.push_str("\r\n");
+    }
+
+    if close {
+        head.push_str("Connection: close\r\n");
+    }
+
+    head.push_str("\r\n");
+    w.write_all(head.as_bytes())
+}
@@ -0,0 +1,138 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `bundle update` subcommand: pin documents to the current version of
+//! their bundles by writing their `Tectonic.lock` files.
+
+use clap::Parser;
+use std::collections::BTreeMap;
+use tectonic::{
+    config::PersistentConfig,
+    docmodel::{DocumentExt, DocumentSetupOptions, LockPolicy},
+    errors::Result,
+    tt_note,
+};
+use tectonic_bridge_core::{SecuritySettings, SecurityStance};
+use tectonic_docmodel::{document::Document, lockfile::Lockfile, workspace::Workspace};
+use tectonic_status_base::StatusBackend;
+
+use crate::v2cli::CommandCustomizations;
+
+/// `bundle update`: Pin documents to the current version of their bundles
+#[derive(Debug, Parser)]
+pub struct BundleUpdateCommand {
+    /// Also record the digests of the bundle files that the documents use,
+    /// by building them
+    #[arg(long)]
+    files: bool,
+
+    /// Only update the lockfile of the workspace document with this name
+    #[arg(long = "doc", name = "doc_name")]
+    doc: Option<String>,
+
+    /// When building, disable all known-insecure features
+    #[arg(long)]
+    untrusted: bool,
+}
+
+impl BundleUpdateCommand {
+    pub fn customize(&self, _cc: &mut CommandCustomizations) {}
+
+    pub fn execute(self, _config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        let ws = Workspace::open_from_environment()?;
+        let docs = ws.select_documents(self.doc.as_deref())?;
+
+        let stance = if self.untrusted {
+            SecurityStance::DisableInsecures
+        } else {
+            SecurityStance::MaybeAllowInsecures
+        };
+
+        // The whole point is to move past the current lockfile. The build
+        // cache has to be bypassed, since a build that it skips won't read
+        // anything from the bundle.
+        let mut setup_options =
+            DocumentSetupOptions::new_with_security(SecuritySettings::new(stance));
+        setup_options
+            .build_cache(false)
+            .lock_policy(LockPolicy::Ignore);
+
+        for doc in docs {
+            self.update_one(doc, &setup_options, status)?;
+        }
+
+        Ok(0)
+    }
+
+    fn update_one(
+        &self,
+        doc: &Document,
+        setup_options: &DocumentSetupOptions,
+        status: &mut dyn StatusBackend,
+    ) -> Result<()> {
+        let lock_path = doc.lockfile_path();
+        let old = Lockfile::load(&lock_path)?;
+        let bundle_digest = doc.bundle(setup_options)?.get_digest()?.to_string();
+
+        // Once file digests have been recorded, keep recording them.
+        let record_files = self.files || old.as_ref().is_some_and(|l| !l.files.is_empty());
+        let mut files = BTreeMap::new();
+
+        if record_files {
+            // Builds with a new bundle generate a new format from the bundle's
+            // files, so the files read while generating the format have to be
+            // recorded too. Using an empty format cache makes sure that the
+            // format is generated here rather than loaded.
+            let format_cache = tempfile::tempdir()?;
+
+            for output_name in doc.output_names() {
+                tt_note!(
+                    status,
+                    "building document `{}`, output `{}` to find the bundle files that it uses",
+                    doc.name,
+                    output_name
+                );
+
+                let mut builder = doc.setup_session(output_name, setup_options, status)?;
+                builder
+                    .format_cache_path(format_cache.path())
+                    .do_not_write_output_files();
+                let sess = crate::compile::run_and_report(builder, status)?;
+
+                for input in sess.dependency_manifest().bundle_inputs {
+                    if let Some(d) = input.digest {
+                        files.insert(input.name, d.to_string());
+                    }
+                }
+            }
+        }
+
+        let new = Lockfile {
+            bundle_loc: doc.bundle_loc.clone(),
+            bundle_digest,
+            files,
+        };
+
+        match &old {
+            None => tt_note!(status, "creating `{}`", lock_path.display()),
+
+            Some(l) if l.bundle_digest != new.bundle_digest => tt_note!(
+                status,
+                "updating `{}`: the bundle digest changes from {} to {}",
+                lock_path.display(),
+                l.bundle_digest,
+                new.bundle_digest
+            ),
+
+            Some(l) if *l == new => {
+                tt_note!(status, "`{}` is up to date", lock_path.display());
+                return Ok(());
+            }
+
+            Some(_) => tt_note!(status, "updating `{}`", lock_path.display()),
+        }
+
+        new.save(&lock_path)?;
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use tectonic::{
     ctry,
     docmodel::{DocumentExt, DocumentSetupOptions},
     driver::PassSetting,
+    errmsg,
     errors::Result,
     tt_error,
 };
@@ -26,6 +27,10 @@ pub struct DumpCommand {
     #[arg(short = 'C', long)]
     only_cached: bool,
 
+    /// Use the workspace document with this name
+    #[arg(long = "doc", name = "doc_name")]
+    doc: Option<String>,
+
     /// Use the specified output profile for the partial build
     #[arg(short = 'p', long)]
     profile: Option<String>,
@@ -47,7 +52,14 @@ impl TectonicCommand for DumpCommand {
 
     fn execute(self, config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
         let ws = Workspace::open_from_environment()?;
-        let doc = ws.first_document();
+        let doc = match ws.select_documents(self.doc.as_deref())?[..] {
+            [doc] => doc,
+            _ => {
+                return Err(errmsg!(
+                    "this workspace contains several documents; use `--doc` to choose one"
+                ))
+            }
+        };
 
         // Default to allowing insecure since it would be super duper annoying
         // to have to pass `--trusted` every time to build a personal document
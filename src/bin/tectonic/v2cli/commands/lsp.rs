@@ -0,0 +1,771 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `lsp` subcommand: a Language Server Protocol server for the current
+//! document.
+//!
+//! The server speaks JSON-RPC over standard input and output. Whenever the
+//! client saves a file, the document is rebuilt in memory and the resulting
+//! TeX and BibTeX errors and warnings are published as diagnostics. The server
+//! also completes citation keys, using the `.bib` files that BibTeX read, and
+//! cross-reference labels, using the labels recorded in the `.aux` files.
+
+use clap::Parser;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    fmt::{Arguments, Write as _},
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tectonic::{
+    config::PersistentConfig,
+    ctry,
+    docmodel::{DocumentExt, DocumentSetupOptions, LockPolicy},
+    driver::DependencyManifest,
+    errmsg,
+    errors::Result,
+    io::memory::MemoryFileCollection,
+    tt_note, tt_warning,
+};
+use tectonic_bridge_core::{SecuritySettings, SecurityStance};
+use tectonic_docmodel::{document::Document, workspace::Workspace};
+use tectonic_errors::Error as NewError;
+use tectonic_status_base::{Diagnostic, MessageKind, StatusBackend};
+use url::Url;
+
+use crate::v2cli::{CommandCustomizations, TectonicCommand};
+
+/// The JSON-RPC error code for requests that the server doesn't implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The LSP `CompletionItemKind` used for citation keys and labels.
+const COMPLETION_KIND_REFERENCE: u32 = 18;
+
+/// Matches an unfinished argument of a command at the end of a line, such as
+/// `\cite[p.~3]{smi`.
+static COMMAND_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\([A-Za-z]+)\*?(?:\[[^\]]*\])*\{[^{}]*$").unwrap());
+
+/// Matches the start of an entry in a `.bib` file.
+static BIB_ENTRY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"@\s*([A-Za-z]+)\s*[{(]\s*([^,\s{}()]+)\s*,").unwrap());
+
+/// Matches a BibTeX error message with its location.
+static BIBTEX_ERROR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?)---line (\d+) of file (.+)$").unwrap());
+
+/// Matches the location line that follows a BibTeX warning.
+static BIBTEX_WARNING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^--line (\d+) of file (.+)$").unwrap());
+
+/// `lsp`: Run a Language Server Protocol server for the current document
+#[derive(Debug, Eq, PartialEq, Parser)]
+pub struct LspCommand {
+    /// Document is untrusted -- disable all known-insecure features
+    #[arg(long)]
+    untrusted: bool,
+
+    /// Use only resource files cached locally
+    #[arg(short = 'C', long)]
+    only_cached: bool,
+
+    /// Use the specified output profile for the builds
+    #[arg(short = 'p', long)]
+    profile: Option<String>,
+}
+
+impl TectonicCommand for LspCommand {
+    fn customize(&self, cc: &mut CommandCustomizations) {
+        // Standard output carries the protocol, so everything else has to go
+        // elsewhere.
+        cc.always_stderr = true;
+    }
+
+    fn execute(self, config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        let stance = if self.untrusted {
+            SecurityStance::DisableInsecures
+        } else {
+            SecurityStance::MaybeAllowInsecures
+        };
+
+        let mut setup_options =
+            DocumentSetupOptions::new_with_security(SecuritySettings::new(stance));
+        // A stale lockfile shouldn't stop the editor from checking the
+        // document; it's reported as a warning instead.
+        setup_options
+            .only_cached(self.only_cached)
+            .lock_policy(LockPolicy::Warn);
+
+        let mut server = LspServer {
+            config,
+            setup_options,
+            profile: self.profile,
+            workspace: None,
+            open_documents: HashMap::new(),
+            published: HashSet::new(),
+            bib_paths: Vec::new(),
+            labels: Vec::new(),
+            shutdown_requested: false,
+        };
+
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+
+        while let Some(message) = read_message(&mut input)? {
+            if let Some(code) = server.handle(message, status)? {
+                return Ok(code);
+            }
+        }
+
+        // The client went away without sending `exit`.
+        Ok(if server.shutdown_requested { 0 } else { 1 })
+    }
+}
+
+/// The state of the language server.
+struct LspServer {
+    config: PersistentConfig,
+    setup_options: DocumentSetupOptions,
+    profile: Option<String>,
+    workspace: Option<Workspace>,
+
+    /// The text of the documents that the client has opened, keyed by URI.
+    open_documents: HashMap<String, String>,
+
+    /// The URIs for which we have published non-empty diagnostics.
+    published: HashSet<String>,
+
+    /// The `.bib` files read by BibTeX during the most recent build.
+    bib_paths: Vec<PathBuf>,
+
+    /// The labels defined in the `.aux` files, with their printed values.
+    labels: Vec<(String, String)>,
+
+    shutdown_requested: bool,
+}
+
+impl LspServer {
+    /// Handle one message from the client. Returns `Some(code)` if the server
+    /// should exit with the given code.
+    fn handle(&mut self, message: Value, status: &mut dyn StatusBackend) -> Result<Option<i32>> {
+        // We never send requests, so there should be no responses to handle.
+        let method = match message["method"].as_str() {
+            Some(m) => m.to_owned(),
+            None => return Ok(None),
+        };
+
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        match (method.as_str(), id) {
+            ("initialize", Some(id)) => {
+                self.initialize(params, status);
+                self.respond(
+                    id,
+                    json!({
+                        "capabilities": {
+                            "textDocumentSync": {
+                                "openClose": true,
+                                "change": 1,
+                                "save": { "includeText": false },
+                            },
+                            "completionProvider": {
+                                "triggerCharacters": ["{", ","],
+                            },
+                        },
+                        "serverInfo": {
+                            "name": "tectonic",
+                            "version": env!("CARGO_PKG_VERSION"),
+                        },
+                    }),
+                )?;
+            }
+
+            ("initialized", None) | ("textDocument/didSave", None) => self.rebuild(status)?,
+
+            ("textDocument/didOpen", None) => {
+                if let (Some(uri), Some(text)) = (
+                    params["textDocument"]["uri"].as_str(),
+                    params["textDocument"]["text"].as_str(),
+                ) {
+                    self.open_documents.insert(uri.to_owned(), text.to_owned());
+                }
+            }
+
+            ("textDocument/didChange", None) => {
+                // We only ask for full-document synchronization.
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+
+                if let (Some(uri), Some(text)) = (params["textDocument"]["uri"].as_str(), text) {
+                    self.open_documents.insert(uri.to_owned(), text.to_owned());
+                }
+            }
+
+            ("textDocument/didClose", None) => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.open_documents.remove(uri);
+                }
+            }
+
+            ("textDocument/completion", Some(id)) => {
+                let items = self.complete(params);
+                self.respond(id, Value::Array(items))?;
+            }
+
+            ("shutdown", Some(id)) => {
+                self.shutdown_requested = true;
+                self.respond(id, Value::Null)?;
+            }
+
+            ("exit", _) => return Ok(Some(if self.shutdown_requested { 0 } else { 1 })),
+
+            (_, Some(id)) => {
+                self.send(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("unsupported method `{method}`"),
+                    },
+                }))?;
+            }
+
+            // Other notifications may be ignored.
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    /// Process the `initialize` request. The workspace is searched for
+    /// starting at the client's root directory, if it gives one.
+    fn initialize(&mut self, params: &Value, status: &mut dyn StatusBackend) {
+        let root = params["rootUri"]
+            .as_str()
+            .and_then(|u| Url::parse(u).ok())
+            .and_then(|u| u.to_file_path().ok())
+            .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+
+        if let Some(root) = root {
+            if let Err(e) = env::set_current_dir(&root) {
+                tt_warning!(status, "couldn't change to the client root directory `{}`", root.display(); e.into());
+            }
+        }
+    }
+
+    /// Rebuild the document and publish the resulting diagnostics.
+    fn rebuild(&mut self, status: &mut dyn StatusBackend) -> Result<()> {
+        if self.workspace.is_none() {
+            match Workspace::open_from_environment() {
+                Ok(ws) => self.workspace = Some(ws),
+                Err(e) => {
+                    return self.show_message(
+                        MessageKind::Error,
+                        &format!("couldn't open the Tectonic workspace: {e}"),
+                    );
+                }
+            }
+        }
+
+        let ws = self.workspace.as_ref().unwrap();
+        let doc = ws.current_document().unwrap_or_else(|| ws.first_document());
+
+        let output_name = match self.profile.as_deref().or_else(|| doc.output_names().min()) {
+            Some(n) => n.to_owned(),
+            None => return Ok(()),
+        };
+
+        tt_note!(status, "building output `{}`", output_name);
+
+        let mut collector = DiagnosticCollector::default();
+        let build = self.build(doc, &output_name, &mut collector);
+
+        // If the session couldn't even be set up -- say, because the bundle
+        // is unavailable -- there won't be any diagnostics explaining why.
+        if let Err(e) = &build {
+            let causes: Vec<String> = e.iter().map(|item| item.to_string()).collect();
+            let mut diag = Diagnostic::new(MessageKind::Error);
+            diag.message = format!("couldn't build `{}`: {}", output_name, causes.join(": "));
+            collector.diagnostics.push(diag);
+        }
+
+        let mut diagnostics = BTreeMap::new();
+        let mut unlocated = Vec::new();
+        let mut any_located_errors = false;
+
+        for diag in &collector.diagnostics {
+            match diag
+                .file
+                .as_deref()
+                .and_then(|f| resolve_source(doc, build.as_ref().ok().map(|b| &b.manifest), f))
+            {
+                Some(path) => {
+                    any_located_errors |= diag.kind == MessageKind::Error;
+                    add_diagnostic(&mut diagnostics, &path, diag, "tex");
+                }
+                None => unlocated.push(diag),
+            }
+        }
+
+        if let Ok(build) = &build {
+            for (name, info) in build.files.iter() {
+                if name.ends_with(".blg") {
+                    let text = String::from_utf8_lossy(&info.data);
+
+                    for diag in parse_bibtex_log(&text) {
+                        let f = diag.file.as_deref().unwrap_or_default();
+
+                        if let Some(path) = resolve_source(doc, Some(&build.manifest), f) {
+                            add_diagnostic(&mut diagnostics, &path, &diag, "bibtex");
+                        }
+                    }
+                }
+            }
+
+            let mut labels = Vec::new();
+
+            for (name, info) in build.files.iter() {
+                if name.ends_with(".aux") {
+                    parse_aux_labels(&String::from_utf8_lossy(&info.data), &mut labels);
+                }
+            }
+
+            let bib_paths: Vec<PathBuf> = build
+                .manifest
+                .filesystem_inputs
+                .iter()
+                .filter(|p| p.extension().is_some_and(|e| e == "bib"))
+                .cloned()
+                .collect();
+
+            // If the build stopped early, it may not have gotten around to
+            // reading the files that give us completions. In that case, keep
+            // using the ones from the last build.
+            if build.succeeded || !labels.is_empty() {
+                self.labels = labels;
+            }
+
+            if build.succeeded || !bib_paths.is_empty() {
+                self.bib_paths = bib_paths;
+            }
+        }
+
+        let stale: Vec<String> = self
+            .published
+            .iter()
+            .filter(|uri| !diagnostics.contains_key(*uri))
+            .cloned()
+            .collect();
+
+        for uri in stale {
+            self.publish_diagnostics(&uri, Vec::new())?;
+        }
+
+        self.published = diagnostics.keys().cloned().collect();
+
+        for (uri, items) in diagnostics {
+            self.publish_diagnostics(&uri, items)?;
+        }
+
+        // Problems that we can't attach to a file won't be visible unless we
+        // bring them up separately. Only do so if the build failed without
+        // a more specific explanation.
+        if !any_located_errors {
+            for diag in unlocated {
+                if diag.kind == MessageKind::Error {
+                    self.show_message(diag.kind, &diag.message)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build the document in memory, recording problems in *collector*.
+    fn build(
+        &self,
+        doc: &Document,
+        output_name: &str,
+        collector: &mut DiagnosticCollector,
+    ) -> Result<BuildData> {
+        let mut builder = doc.setup_session(output_name, &self.setup_options, collector)?;
+
+        builder
+            .format_cache_path(self.config.format_cache_path()?)
+            .do_not_write_output_files();
+
+        let mut sess = builder.create(collector)?;
+        let result = sess.run(collector);
+
+        if let Err(e) = &result {
+            let mut diag = Diagnostic::new(MessageKind::Error);
+            diag.message = e.to_string();
+            collector.diagnostics.push(diag);
+        }
+
+        let manifest = sess.dependency_manifest();
+
+        Ok(BuildData {
+            succeeded: result.is_ok(),
+            manifest,
+            files: sess.into_file_data(),
+        })
+    }
+
+    /// Compute completions for a `textDocument/completion` request.
+    fn complete(&self, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or_default() as usize;
+
+        let text = match self.open_documents.get(uri) {
+            Some(t) => t.clone(),
+            None => match Url::parse(uri).ok().and_then(|u| u.to_file_path().ok()) {
+                Some(p) => fs::read_to_string(p).unwrap_or_default(),
+                None => String::new(),
+            },
+        };
+
+        let line_text = text.lines().nth(line).unwrap_or_default();
+        let prefix = utf16_prefix(line_text, character);
+
+        let command = match COMMAND_RE.captures(prefix) {
+            Some(c) => c[1].to_lowercase(),
+            None => return Vec::new(),
+        };
+
+        let entries = if command.contains("cite") {
+            let mut keys = Vec::new();
+
+            for path in &self.bib_paths {
+                if let Ok(data) = fs::read(path) {
+                    parse_bib_keys(&String::from_utf8_lossy(&data), &mut keys);
+                }
+            }
+
+            keys
+        } else if command.ends_with("ref") {
+            self.labels.clone()
+        } else {
+            return Vec::new();
+        };
+
+        let mut seen = HashSet::new();
+
+        entries
+            .into_iter()
+            .filter(|(key, _)| seen.insert(key.clone()))
+            .map(|(key, detail)| {
+                json!({
+                    "label": key,
+                    "kind": COMPLETION_KIND_REFERENCE,
+                    "detail": detail,
+                })
+            })
+            .collect()
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Value>) -> Result<()> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {
+                "uri": uri,
+                "diagnostics": diagnostics,
+            },
+        }))
+    }
+
+    fn show_message(&mut self, kind: MessageKind, message: &str) -> Result<()> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": "window/showMessage",
+            "params": {
+                "type": severity(kind),
+                "message": message,
+            },
+        }))
+    }
+
+    fn respond(&mut self, id: Value, result: Value) -> Result<()> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": result,
+        }))
+    }
+
+    fn send(&mut self, message: Value) -> Result<()> {
+        let body = message.to_string();
+        let mut output = io::stdout().lock();
+        ctry!(
+            write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+            "failed to send a message to the LSP client"
+        );
+        output.flush()?;
+        Ok(())
+    }
+}
+
+/// The results of an in-memory build.
+struct BuildData {
+    succeeded: bool,
+    manifest: DependencyManifest,
+    files: MemoryFileCollection,
+}
+
+/// A status backend that keeps track of warnings and errors so that they can
+/// be published to the client.
+#[derive(Default)]
+struct DiagnosticCollector {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl StatusBackend for DiagnosticCollector {
+    fn report(&mut self, kind: MessageKind, args: Arguments, err: Option<&NewError>) {
+        if kind == MessageKind::Note {
+            return;
+        }
+
+        let mut diag = Diagnostic::new(kind);
+        diag.message = args.to_string();
+
+        if let Some(e) = err {
+            for item in e.chain() {
+                let _ = write!(diag.message, "\ncaused by: {item}");
+            }
+        }
+
+        self.diagnostics.push(diag);
+    }
+
+    fn report_diagnostic(&mut self, diag: &Diagnostic) {
+        if diag.kind != MessageKind::Note {
+            self.diagnostics.push(diag.clone());
+        }
+    }
+
+    fn dump_error_logs(&mut self, _output: &[u8]) {}
+}
+
+/// Read one message from the client. Returns `None` at end of input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = Some(ctry!(
+                    value.trim().parse::<usize>();
+                    "invalid LSP Content-Length header `{}`", value.trim()
+                ));
+            }
+        }
+    }
+
+    let length = match length {
+        Some(l) => l,
+        None => return Err(errmsg!("LSP message is missing its Content-Length header")),
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(
+        ctry!(serde_json::from_slice(&body); "malformed LSP message"),
+    ))
+}
+
+/// Map a file name reported by one of the engines to a path on disk.
+fn resolve_source(
+    doc: &Document,
+    manifest: Option<&DependencyManifest>,
+    name: &str,
+) -> Option<PathBuf> {
+    let tex_dir = doc.src_dir().join("src");
+
+    for candidate in [name.to_owned(), format!("{name}.tex")] {
+        let path = tex_dir.join(&candidate);
+
+        if path.is_file() {
+            return Some(path);
+        }
+
+        if let Some(m) = manifest {
+            if let Some(p) = m.filesystem_inputs.iter().find(|p| p.ends_with(&candidate)) {
+                return Some(p.clone());
+            }
+        }
+    }
+
+    None
+}
+
+fn severity(kind: MessageKind) -> u32 {
+    match kind {
+        MessageKind::Error => 1,
+        MessageKind::Warning => 2,
+        MessageKind::Note => 3,
+    }
+}
+
+/// Convert a diagnostic to its LSP form and file it under the URI of *path*.
+fn add_diagnostic(
+    diagnostics: &mut BTreeMap<String, Vec<Value>>,
+    path: &Path,
+    diag: &Diagnostic,
+    source: &str,
+) {
+    let uri = match Url::from_file_path(path) {
+        Ok(u) => u.to_string(),
+        Err(_) => return,
+    };
+
+    // TeX counts lines from 1, LSP from 0. If the engine gave us the text of
+    // the line, we can highlight the whole thing; otherwise, highlight through
+    // the start of the next line.
+    let line = diag.line.unwrap_or(1).saturating_sub(1);
+
+    let end = if diag.context.is_empty() {
+        json!({ "line": line + 1, "character": 0 })
+    } else {
+        let width: usize = diag.context.iter().map(|s| s.encode_utf16().count()).sum();
+        json!({ "line": line, "character": width })
+    };
+
+    diagnostics.entry(uri).or_default().push(json!({
+        "range": {
+            "start": { "line": line, "character": 0 },
+            "end": end,
+        },
+        "severity": severity(diag.kind),
+        "source": source,
+        "message": diag.message,
+    }));
+}
+
+/// Get the part of *line* before the position *character*, which is measured
+/// in UTF-16 code units as per the LSP specification.
+fn utf16_prefix(line: &str, character: usize) -> &str {
+    let mut units = 0;
+
+    for (i, c) in line.char_indices() {
+        if units >= character {
+            return &line[..i];
+        }
+
+        units += c.len_utf16();
+    }
+
+    line
+}
+
+/// If *text* starts with a braced group, return its contents and whatever
+/// follows it.
+fn brace_group(text: &str) -> Option<(&str, &str)> {
+    if !text.starts_with('{') {
+        return None;
+    }
+
+    let mut depth = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some((&text[1..i], &text[i + 1..]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Collect the labels defined in an `.aux` file, along with their printed
+/// values.
+fn parse_aux_labels(text: &str, labels: &mut Vec<(String, String)>) {
+    for line in text.lines() {
+        let Some((key, rest)) = line.strip_prefix("\\newlabel").and_then(brace_group) else {
+            continue;
+        };
+
+        // Packages like `cleveref` define auxiliary labels of their own.
+        if key.contains('@') {
+            continue;
+        }
+
+        let value = brace_group(rest)
+            .and_then(|(data, _)| brace_group(data))
+            .map(|(value, _)| value)
+            .unwrap_or_default();
+
+        labels.push((key.to_owned(), value.to_owned()));
+    }
+}
+
+/// Collect the keys of the entries in a `.bib` file, along with their types.
+fn parse_bib_keys(text: &str, keys: &mut Vec<(String, String)>) {
+    for c in BIB_ENTRY_RE.captures_iter(text) {
+        let kind = c[1].to_lowercase();
+
+        if kind != "comment" && kind != "preamble" && kind != "string" {
+            keys.push((c[2].to_owned(), kind));
+        }
+    }
+}
+
+/// Extract the located errors and warnings from a BibTeX log.
+///
+/// BibTeX reports problems in the database files with a trailer like `---line
+/// 12 of file refs.bib` for errors, or on a separate `--line 12 of file
+/// refs.bib` line following a warning.
+fn parse_bibtex_log(text: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut last_warning = None;
+
+    for line in text.lines() {
+        if let Some(c) = BIBTEX_ERROR_RE.captures(line) {
+            let mut diag = Diagnostic::new(MessageKind::Error);
+            diag.message = c[1].to_owned();
+            diag.line = c[2].parse().ok();
+            diag.file = Some(c[3].to_owned());
+            diagnostics.push(diag);
+        } else if let Some(c) = BIBTEX_WARNING_RE.captures(line) {
+            if let Some(message) = last_warning.take() {
+                let mut diag = Diagnostic::new(MessageKind::Warning);
+                diag.message = message;
+                diag.line = c[1].parse().ok();
+                diag.file = Some(c[2].to_owned());
+                diagnostics.push(diag);
+            }
+        }
+
+        last_warning = line.strip_prefix("Warning--").map(|s| s.to_owned());
+    }
+
+    diagnostics
+}
@@ -1,6 +1,9 @@
 pub mod build;
 pub mod bundle;
 pub mod dump;
+pub mod lsp;
 pub mod new;
+pub mod serve;
 pub mod show;
+pub mod synctex;
 pub mod watch;
@@ -0,0 +1,386 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! The `serve` subcommand: a compile server that keeps its bundle and format
+//! files loaded between jobs.
+//!
+//! Clients connect to a Unix-domain socket and send compile jobs as JSON
+//! objects, one per line. The server answers each job with a JSON object on a
+//! line of its own. Connections are handled concurrently, but the jobs
+//! themselves are run one at a time by a single worker that owns the bundle.
+
+use clap::Parser;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    cell::RefCell,
+    fmt::Arguments,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use tectonic::{
+    config::{maybe_return_test_bundle, PersistentConfig},
+    driver::{OutputFormat, PassSetting, ProcessingSessionBuilder},
+    errmsg,
+    errors::Result,
+    io::format_cache::FormatMemory,
+    status::{Diagnostic, MessageKind, StatusBackend},
+};
+use tectonic_bridge_core::{SecuritySettings, SecurityStance};
+use tectonic_bundles::{detect_bundle, Bundle};
+use tectonic_errors::Error as NewError;
+
+use crate::v2cli::{CommandCustomizations, TectonicCommand};
+
+/// `serve`: Run a compile server listening on a Unix-domain socket
+#[derive(Debug, Eq, PartialEq, Parser)]
+pub struct ServeCommand {
+    /// Use this URL or path to find resource files instead of the default
+    #[arg(long, short)]
+    bundle: Option<String>,
+
+    /// Use only resource files cached locally
+    #[arg(short = 'C', long)]
+    only_cached: bool,
+
+    /// Treat all jobs as untrusted -- disable all known-insecure features
+    #[arg(long)]
+    untrusted: bool,
+
+    /// The path of the socket to listen on
+    #[arg()]
+    socket: PathBuf,
+}
+
+impl TectonicCommand for ServeCommand {
+    fn customize(&self, _cc: &mut CommandCustomizations) {}
+
+    fn execute(self, config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        let bundle = if let Some(bundle) = &self.bundle {
+            if let Ok(bundle) = maybe_return_test_bundle(Some(bundle.clone())) {
+                bundle
+            } else if let Some(bundle) = detect_bundle(bundle.clone(), self.only_cached, None)? {
+                bundle
+            } else {
+                return Err(errmsg!("`{bundle}` doesn't specify a valid bundle."));
+            }
+        } else if let Ok(bundle) = maybe_return_test_bundle(None) {
+            bundle
+        } else {
+            config.default_bundle(self.only_cached)?
+        };
+
+        let worker = Worker {
+            bundle: Rc::new(RefCell::new(bundle)),
+            formats: FormatMemory::default(),
+            format_cache_path: config.format_cache_path()?,
+            untrusted: self.untrusted,
+        };
+
+        listen(&self.socket, worker, status)
+    }
+}
+
+/// A compile job, as sent by a client.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CompileRequest {
+    /// An arbitrary value that is copied into the response.
+    id: Value,
+
+    /// The path of the file to compile.
+    input_path: Option<PathBuf>,
+
+    /// The text to compile, as an alternative to `input_path`.
+    input_buffer: Option<String>,
+
+    /// The name that TeX should use for the primary input.
+    tex_input_name: Option<String>,
+
+    /// The directory in which to place output files.
+    outdir: Option<PathBuf>,
+
+    /// The name of the format file used to initialize the TeX engine.
+    format: Option<String>,
+
+    /// The kind of output to generate.
+    outfmt: Option<String>,
+
+    /// Which engines to run.
+    pass: Option<String>,
+
+    /// Rerun the TeX engine exactly this many times after the first.
+    reruns: Option<usize>,
+
+    keep_intermediates: bool,
+    keep_logs: bool,
+    synctex: bool,
+    untrusted: bool,
+}
+
+/// The state shared by all jobs.
+struct Worker {
+    bundle: Rc<RefCell<Box<dyn Bundle>>>,
+    formats: FormatMemory,
+    format_cache_path: PathBuf,
+    untrusted: bool,
+}
+
+impl Worker {
+    /// Run one job and produce the response to send back to the client.
+    fn run_job(&mut self, line: &str) -> Value {
+        let mut messages = MessageCollector::default();
+
+        let (id, result) = match serde_json::from_str::<CompileRequest>(line) {
+            Ok(req) => (req.id.clone(), self.compile(req, &mut messages)),
+            Err(e) => (Value::Null, Err(errmsg!("malformed request: {}", e))),
+        };
+
+        let mut response = json!({
+            "id": id,
+            "success": result.is_ok(),
+            "messages": messages.messages,
+        });
+
+        match result {
+            Ok(outputs) => response["outputs"] = json!(outputs),
+            Err(e) => {
+                let causes: Vec<String> = e.iter().map(|item| item.to_string()).collect();
+                response["error"] = json!(causes.join(": "));
+            }
+        }
+
+        response
+    }
+
+    /// Compile a document, returning the paths of the files that were written.
+    fn compile(
+        &mut self,
+        req: CompileRequest,
+        status: &mut dyn StatusBackend,
+    ) -> Result<Vec<PathBuf>> {
+        let stance = if self.untrusted || req.untrusted {
+            SecurityStance::DisableInsecures
+        } else {
+            SecurityStance::MaybeAllowInsecures
+        };
+
+        let outfmt = match req.outfmt.as_deref().map(str::parse::<OutputFormat>) {
+            Some(Ok(f)) => f,
+            Some(Err(e)) => return Err(errmsg!("bad `outfmt`: {}", e)),
+            None => OutputFormat::Pdf,
+        };
+
+        let pass = match req.pass.as_deref().map(str::parse::<PassSetting>) {
+            Some(Ok(p)) => p,
+            Some(Err(e)) => return Err(errmsg!("bad `pass`: {}", e)),
+            None => PassSetting::Default,
+        };
+
+        let mut sess_builder =
+            ProcessingSessionBuilder::new_with_security(SecuritySettings::new(stance));
+        sess_builder
+            .format_name(req.format.as_deref().unwrap_or("latex"))
+            .format_cache_path(&self.format_cache_path)
+            .format_memory(self.formats.clone())
+            .bundle(Box::new(self.bundle.clone()))
+            .keep_logs(req.keep_logs)
+            .keep_intermediates(req.keep_intermediates)
+            .synctex(req.synctex)
+            .output_format(outfmt)
+            .pass(pass)
+            .build_date_from_env(false);
+
+        if let Some(r) = req.reruns {
+            sess_builder.reruns(r);
+        }
+
+        match (&req.input_path, &req.input_buffer, &req.outdir) {
+            (Some(path), None, _) => {
+                let (Some(name), Some(parent)) = (path.file_name(), path.parent()) else {
+                    return Err(errmsg!(
+                        "can't figure out the directory and basename of input path `{}`",
+                        path.display()
+                    ));
+                };
+
+                sess_builder
+                    .primary_input_path(path)
+                    .tex_input_name(&name.to_string_lossy())
+                    .output_dir(parent);
+            }
+
+            // A buffer has no directory of its own, so its outputs and
+            // any files that it references live in the output directory.
+            (None, Some(buffer), Some(outdir)) => {
+                sess_builder
+                    .primary_input_buffer(buffer.as_bytes())
+                    .tex_input_name(req.tex_input_name.as_deref().unwrap_or("texput.tex"))
+                    .filesystem_root(outdir);
+            }
+
+            (None, Some(_), None) => {
+                return Err(errmsg!(
+                    "`outdir` must be given when compiling `input_buffer`"
+                ));
+            }
+
+            _ => {
+                return Err(errmsg!(
+                    "exactly one of `input_path` and `input_buffer` must be given"
+                ));
+            }
+        }
+
+        if let Some(outdir) = &req.outdir {
+            if !outdir.is_dir() {
+                return Err(errmsg!(
+                    "output directory `{}` does not exist",
+                    outdir.display()
+                ));
+            }
+
+            sess_builder.output_dir(outdir);
+        }
+
+        let sess = crate::compile::run_and_report(sess_builder, status)?;
+        Ok(sess.dependency_manifest().outputs)
+    }
+}
+
+/// A status backend that gathers up messages so that they can be sent back
+/// to the client.
+#[derive(Default)]
+struct MessageCollector {
+    messages: Vec<Value>,
+}
+
+fn kind_name(kind: MessageKind) -> &'static str {
+    match kind {
+        MessageKind::Note => "note",
+        MessageKind::Warning => "warning",
+        MessageKind::Error => "error",
+    }
+}
+
+impl StatusBackend for MessageCollector {
+    fn report(&mut self, kind: MessageKind, args: Arguments, err: Option<&NewError>) {
+        let mut message = json!({
+            "kind": kind_name(kind),
+            "message": args.to_string(),
+        });
+
+        if let Some(e) = err {
+            let causes: Vec<String> = e.chain().map(|item| item.to_string()).collect();
+            message["causes"] = json!(causes);
+        }
+
+        self.messages.push(message);
+    }
+
+    fn report_diagnostic(&mut self, diag: &Diagnostic) {
+        let mut message = json!({
+            "kind": kind_name(diag.kind),
+            "message": diag.message,
+        });
+
+        if let Some(file) = &diag.file {
+            message["file"] = json!(file);
+        }
+
+        if let Some(line) = diag.line {
+            message["line"] = json!(line);
+        }
+
+        self.messages.push(message);
+    }
+
+    fn dump_error_logs(&mut self, output: &[u8]) {
+        self.messages.push(json!({
+            "kind": "error_log",
+            "message": String::from_utf8_lossy(output),
+        }));
+    }
+}
+
+#[cfg(unix)]
+fn listen(socket: &Path, mut worker: Worker, status: &mut dyn StatusBackend) -> Result<i32> {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        sync::mpsc,
+        thread,
+    };
+    use tectonic::{ctry, tt_note};
+
+    // A socket file left over from a previous server is harmless, but one
+    // that is still in use is not.
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(errmsg!(
+                "another server is already listening on `{}`",
+                socket.display()
+            ));
+        }
+
+        ctry!(std::fs::remove_file(socket); "couldn't remove stale socket `{}`", socket.display());
+    }
+
+    let listener = ctry!(UnixListener::bind(socket); "couldn't listen on `{}`", socket.display());
+    tt_note!(
+        status,
+        "listening for compile jobs on `{}`",
+        socket.display()
+    );
+
+    // Each connection gets a thread that reads its requests and hands them to
+    // the worker, which lives on this thread, along with a channel for the
+    // response.
+    let (job_tx, job_rx) = mpsc::channel::<(String, mpsc::Sender<Value>)>();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let job_tx = job_tx.clone();
+
+            thread::spawn(move || {
+                let mut writer = match stream.try_clone() {
+                    Ok(w) => w,
+                    Err(_) => return,
+                };
+
+                for line in BufReader::new(stream).lines() {
+                    let Ok(line) = line else { break };
+
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let (resp_tx, resp_rx) = mpsc::channel();
+
+                    if job_tx.send((line, resp_tx)).is_err() {
+                        break;
+                    }
+
+                    let Ok(response) = resp_rx.recv() else { break };
+
+                    if writeln!(writer, "{response}").is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    for (line, resp_tx) in job_rx {
+        let _ = resp_tx.send(worker.run_job(&line));
+    }
+
+    Ok(0)
+}
+
+#[cfg(not(unix))]
+fn listen(_socket: &Path, _worker: Worker, _status: &mut dyn StatusBackend) -> Result<i32> {
+    Err(errmsg!(
+        "the compile server is only available on platforms with Unix-domain sockets"
+    ))
+}
@@ -0,0 +1,198 @@
+use clap::{Args, Parser};
+use std::path::PathBuf;
+use tectonic::{config::PersistentConfig, errmsg, errors::Result, synctex::SyncTex, tt_error};
+use tectonic_docmodel::{document::Document, workspace::Workspace};
+use tectonic_status_base::StatusBackend;
+
+use crate::v2cli::{CommandCustomizations, TectonicCommand};
+
+/// `synctex`: Look up positions using the SyncTeX data of a built document.
+#[derive(Debug, Eq, PartialEq, Parser)]
+pub struct SynctexCommand {
+    #[command(subcommand)]
+    command: SynctexCommands,
+}
+
+#[derive(Debug, Eq, PartialEq, Parser)]
+enum SynctexCommands {
+    #[command(name = "view")]
+    /// Find where a source line appears in the output (forward search)
+    View(SynctexViewCommand),
+
+    #[command(name = "edit")]
+    /// Find the source line for a point in the output (inverse search)
+    Edit(SynctexEditCommand),
+}
+
+impl TectonicCommand for SynctexCommand {
+    fn customize(&self, cc: &mut CommandCustomizations) {
+        cc.always_stderr = true;
+        cc.minimal_chatter = true;
+    }
+
+    fn execute(self, _config: PersistentConfig, status: &mut dyn StatusBackend) -> Result<i32> {
+        match self.command {
+            SynctexCommands::View(c) => c.execute(status),
+            SynctexCommands::Edit(c) => c.execute(status),
+        }
+    }
+}
+
+/// Options for finding the SyncTeX data of a built document.
+#[derive(Debug, Eq, PartialEq, Args)]
+struct DocumentArgs {
+    /// Use the workspace document with this name
+    #[arg(long = "doc", name = "doc_name")]
+    doc: Option<String>,
+
+    /// Use the output of this build target
+    #[arg(long)]
+    target: Option<String>,
+}
+
+impl DocumentArgs {
+    /// Load the SyncTeX data of the selected document and target. Relative
+    /// input paths are resolved against the document's source directory.
+    fn load(&self) -> Result<SyncTex> {
+        let ws = Workspace::open_from_environment()?;
+        let doc = match ws.select_documents(self.doc.as_deref())?[..] {
+            [doc] => doc,
+            _ => {
+                return Err(errmsg!(
+                    "this workspace contains several documents; use `--doc` to choose one"
+                ))
+            }
+        };
+
+        let path = synctex_path(doc, self.target.as_deref())?;
+
+        if !path.exists() {
+            return Err(errmsg!(
+                "no SyncTeX data at `{}`; build the document with `synctex = true` in its output profile",
+                path.display()
+            ));
+        }
+
+        let mut data = SyncTex::open(&path)?;
+        data.base_dir(doc.src_dir());
+        Ok(data)
+    }
+}
+
+/// Get the path of the SyncTeX file written when building *target*, or the
+/// document's only target if it is unspecified.
+fn synctex_path(doc: &Document, target: Option<&str>) -> Result<PathBuf> {
+    let name = match target {
+        Some(t) => {
+            if !doc.outputs.contains_key(t) {
+                return Err(errmsg!("document has no build target named `{}`", t));
+            }
+            t
+        }
+
+        None => {
+            let mut names = doc.output_names();
+
+            match (names.next(), names.next()) {
+                (Some(n), None) => n,
+                (None, _) => return Err(errmsg!("document has no build targets")),
+                _ => {
+                    return Err(errmsg!(
+                        "this document has several build targets; use `--target` to choose one"
+                    ))
+                }
+            }
+        }
+    };
+
+    Ok(doc.output_main_file(name).with_extension("synctex.gz"))
+}
+
+#[derive(Debug, Eq, PartialEq, Parser)]
+struct SynctexViewCommand {
+    #[command(flatten)]
+    document: DocumentArgs,
+
+    /// The source location to look up, as `<file>:<line>`
+    #[arg()]
+    location: String,
+}
+
+impl SynctexViewCommand {
+    fn execute(self, status: &mut dyn StatusBackend) -> Result<i32> {
+        let (file, line) = match self
+            .location
+            .rsplit_once(':')
+            .and_then(|(f, l)| Some((f, l.parse::<u32>().ok()?)))
+        {
+            Some(t) => t,
+            None => {
+                return Err(errmsg!(
+                    "expected a location of the form `<file>:<line>`, got `{}`",
+                    self.location
+                ))
+            }
+        };
+
+        let data = self.document.load()?;
+        let boxes = data.forward(file, line);
+
+        if boxes.is_empty() {
+            tt_error!(status, "found no output for `{}`", self.location);
+            return Ok(1);
+        }
+
+        for b in boxes {
+            println!(
+                "Page:{} x:{:.2} y:{:.2} W:{:.2} H:{:.2}",
+                b.page, b.x, b.y, b.width, b.height
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Parser)]
+struct SynctexEditCommand {
+    #[command(flatten)]
+    document: DocumentArgs,
+
+    /// The output position to look up, as `<page>:<x>:<y>`, with coordinates
+    /// in points from the top left of the page
+    #[arg()]
+    position: String,
+}
+
+impl SynctexEditCommand {
+    fn execute(self, status: &mut dyn StatusBackend) -> Result<i32> {
+        let parsed = match self.position.split(':').collect::<Vec<_>>()[..] {
+            [page, x, y] => match (page.parse::<u32>(), x.parse::<f64>(), y.parse::<f64>()) {
+                (Ok(page), Ok(x), Ok(y)) => Some((page, x, y)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some((page, x, y)) = parsed else {
+            return Err(errmsg!(
+                "expected a position of the form `<page>:<x>:<y>`, got `{}`",
+                self.position
+            ));
+        };
+
+        let data = self.document.load()?;
+
+        match data.inverse(page, x, y) {
+            Some(loc) => {
+                println!("{}:{}", loc.file.display(), loc.line);
+                Ok(0)
+            }
+
+            None => {
+                tt_error!(status, "found no source for `{}`", self.position);
+                Ok(1)
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@ use clap::Parser;
 use std::time::Duration;
 use std::{env, path::PathBuf, sync::Arc};
 use tectonic::{config::PersistentConfig, errors::Result, tt_error};
+use tectonic_docmodel::workspace::Workspace;
 use tectonic_status_base::StatusBackend;
 use tokio::runtime;
 use watchexec::command::Program;
@@ -38,6 +39,10 @@ pub struct WatchCommand {
     /// Tectonic commands to execute on build [default: build]
     #[arg(long = "exec", short = 'x')]
     execute: Vec<String>,
+
+    /// Build only the workspace document with this name
+    #[arg(long = "doc", name = "doc_name")]
+    doc: Option<String>,
 }
 
 impl WatchCommand {
@@ -84,6 +89,11 @@ impl WatchCommand {
             }
             args.push("build".to_string());
 
+            if let Some(doc) = &self.doc {
+                args.push("--doc".to_string());
+                args.push(doc.clone());
+            }
+
             let cmd = Command {
                 program: Program::Exec {
                     prog: exe_name.into(),
@@ -95,19 +105,37 @@ impl WatchCommand {
             cmds.push((Id::default(), Arc::new(cmd)));
         }
 
-        let current_dir = env::current_dir()?;
+        // Watch the whole workspace, so that changes to files shared between
+        // its documents are noticed, but ignore the documents' build
+        // directories.
+        let (watch_dir, mut ignores) = match Workspace::open_from_environment() {
+            Ok(ws) => {
+                let ignores = ws
+                    .documents()
+                    .iter()
+                    .filter_map(|doc| doc.build_dir().strip_prefix(ws.root_dir()).ok())
+                    .map(|rel| {
+                        let mut glob = rel
+                            .components()
+                            .map(|c| c.as_os_str().to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join("/");
+                        glob.push_str("/**");
+                        (glob, None)
+                    })
+                    .collect::<Vec<_>>();
+                (ws.root_dir().to_owned(), ignores)
+            }
 
-        let filter = GlobsetFilterer::new(
-            &current_dir,
-            [],
-            // Ignore build directory, and things like vim swap files
-            [("build/**".to_string(), None), ("*.swp".to_string(), None)],
-            [],
-            [],
-            [],
-        )
-        .await
-        .unwrap();
+            Err(_) => (env::current_dir()?, vec![("build/**".to_string(), None)]),
+        };
+
+        // Also ignore things like vim swap files
+        ignores.push(("*.swp".to_string(), None));
+
+        let filter = GlobsetFilterer::new(&watch_dir, [], ignores, [], [], [])
+            .await
+            .unwrap();
 
         async fn end_task(end: Ticket, job: Job) {
             end.await;
@@ -190,7 +218,7 @@ impl WatchCommand {
             Ok(exec_handler) => {
                 exec_handler
                     .config
-                    .pathset([current_dir])
+                    .pathset([watch_dir])
                     .filterer(Arc::new(filter));
                 exec_handler.main().await.unwrap().unwrap();
                 Ok(0)
@@ -5,23 +5,29 @@
 //! Cargo, as compared to the classic "rustc-like" CLI.
 
 use clap::{Parser, Subcommand};
-use std::{env, ffi::OsString, fs, path::Path, path::PathBuf, process};
+use std::{env, ffi::OsString, fs, io, path::Path, path::PathBuf, process};
 use tectonic::{
     config::PersistentConfig,
     errors::{Result, SyncError},
-    status::{termcolor::TermcolorStatusBackend, ChatterLevel, StatusBackend},
+    status::{
+        json::JsonStatusBackend, termcolor::TermcolorStatusBackend, ChatterLevel, StatusBackend,
+    },
     tt_note,
 };
 use tectonic_errors::prelude::anyhow;
 use tectonic_status_base::plain::PlainStatusBackend;
 use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
 use self::commands::{
     build::BuildCommand,
     bundle::BundleCommand,
     dump::DumpCommand,
+    lsp::LspCommand,
     new::{InitCommand, NewCommand},
+    serve::ServeCommand,
     show::ShowCommand,
+    synctex::SynctexCommand,
     watch::WatchCommand,
 };
 
@@ -43,6 +49,10 @@ struct V2CliOptions {
     #[arg(long = "color", default_value = "auto")]
     cli_color: crate::CliColor,
 
+    /// The format of status messages
+    #[arg(long = "message-format", default_value = "human")]
+    message_format: crate::MessageFormat,
+
     /// The command to run
     #[command(subcommand)]
     command: Commands,
@@ -79,13 +89,6 @@ pub fn v2_main(effective_args: &[OsString]) {
 
     let args = V2CliOptions::parse_from(effective_args);
 
-    tracing_subscriber::fmt()
-        .with_max_level(LevelFilter::INFO)
-        .with_target(false)
-        .without_time()
-        .with_ansi(args.cli_color.should_enable())
-        .init();
-
     // Command-specific customizations before we do our centralized setup.
     // This is a semi-hack so that we can set up certain commands to ensure
     // that status info is always printed to stderr.
@@ -96,13 +99,32 @@ pub fn v2_main(effective_args: &[OsString]) {
         Commands::Bundle(o) => o.customize(&mut customizations),
         Commands::Compile(_) => {} // avoid namespacing/etc issues
         Commands::Dump(o) => o.customize(&mut customizations),
+        Commands::Lsp(o) => o.customize(&mut customizations),
         Commands::New(o) => o.customize(&mut customizations),
         Commands::Init(o) => o.customize(&mut customizations),
+        Commands::Serve(o) => o.customize(&mut customizations),
         Commands::Show(o) => o.customize(&mut customizations),
+        Commands::Synctex(o) => o.customize(&mut customizations),
         Commands::Watch(o) => o.customize(&mut customizations),
         Commands::External(_) => {}
     }
 
+    // Logging follows the status output, so that it stays out of the way of
+    // commands that print machine-readable output.
+    let log_writer = if customizations.always_stderr {
+        BoxMakeWriter::new(io::stderr)
+    } else {
+        BoxMakeWriter::new(io::stdout)
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::INFO)
+        .with_target(false)
+        .without_time()
+        .with_ansi(args.cli_color.should_enable())
+        .with_writer(log_writer)
+        .init();
+
     // Set up colorized output.
     let chatter_level = if customizations.minimal_chatter {
         ChatterLevel::Minimal
@@ -110,7 +132,11 @@ pub fn v2_main(effective_args: &[OsString]) {
         args.chatter_level
     };
 
-    let mut status = if args.cli_color.should_enable() {
+    let mut status = if args.message_format == crate::MessageFormat::Json {
+        let mut sb = JsonStatusBackend::new(chatter_level);
+        sb.always_stderr(customizations.always_stderr);
+        Box::new(sb) as Box<dyn StatusBackend>
+    } else if args.cli_color.should_enable() {
         let mut sb = TermcolorStatusBackend::new(chatter_level);
         sb.always_stderr(customizations.always_stderr);
         Box::new(sb) as Box<dyn StatusBackend>
@@ -134,9 +160,12 @@ pub fn v2_main(effective_args: &[OsString]) {
         Commands::Bundle(o) => o.execute(config, &mut *status),
         Commands::Compile(o) => o.execute(config, &mut *status),
         Commands::Dump(o) => o.execute(config, &mut *status),
+        Commands::Lsp(o) => o.execute(config, &mut *status),
         Commands::New(o) => o.execute(config, &mut *status),
         Commands::Init(o) => o.execute(config, &mut *status),
+        Commands::Serve(o) => o.execute(config, &mut *status),
         Commands::Show(o) => o.execute(config, &mut *status),
+        Commands::Synctex(o) => o.execute(config, &mut *status),
         Commands::Watch(o) => o.execute(config, &mut *status),
         Commands::External(all_args) => do_external(all_args),
     };
@@ -174,6 +203,10 @@ enum Commands {
     /// Run a partial compilation and output an intermediate file
     Dump(DumpCommand),
 
+    #[command(name = "lsp")]
+    /// Run a Language Server Protocol server for the current document
+    Lsp(LspCommand),
+
     #[command(name = "new")]
     /// Create a new document project
     New(NewCommand),
@@ -182,10 +215,18 @@ enum Commands {
     /// Initializes a new document in the current directory
     Init(InitCommand),
 
+    #[command(name = "serve")]
+    /// Run a compile server listening on a Unix-domain socket
+    Serve(ServeCommand),
+
     #[command(name = "show")]
     /// Display various useful pieces of information
     Show(ShowCommand),
 
+    #[command(name = "synctex")]
+    /// Look up source and output positions using SyncTeX data
+    Synctex(SynctexCommand),
+
     #[command(name = "watch")]
     /// Watch input files and execute commands on change
     Watch(WatchCommand),
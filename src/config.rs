@@ -15,7 +15,7 @@ use std::{
     path::PathBuf,
     sync::atomic::{AtomicBool, Ordering},
 };
-use tectonic_bundles::{detect_bundle, Bundle};
+use tectonic_bundles::{detect_bundle, layered::LayeredBundle, Bundle};
 use tectonic_io_base::app_dirs;
 
 use crate::errors::{ErrorKind, Result};
@@ -133,29 +133,66 @@ impl PersistentConfig {
     }
 
     /// Get the default bundle URL for this configuration
+    ///
+    /// If several default bundles are configured, this is the base bundle,
+    /// the last one, which the others are layered on top of. Use
+    /// [`Self::default_bundle_locs`] to get all of them.
     pub fn default_bundle_loc(&self) -> &str {
-        &self.default_bundles[0].url
+        &self
+            .default_bundles
+            .last()
+            .expect("at least one default_bundle item must be specified")
+            .url
+    }
+
+    /// Get the URLs of all of the default bundles, in priority order
+    ///
+    /// If several default bundles are configured, the earlier ones are
+    /// layered on top of the last one.
+    pub fn default_bundle_locs(&self) -> Vec<&str> {
+        self.default_bundles
+            .iter()
+            .map(|b| b.url.as_str())
+            .collect()
     }
 
     /// Attempt to open the default bundle
+    ///
+    /// If several default bundles are configured, they are layered in the
+    /// order given, so that files in the first one take priority.
     pub fn default_bundle(&self, only_cached: bool) -> Result<Box<dyn Bundle>> {
         if CONFIG_TEST_MODE_ACTIVATED.load(Ordering::SeqCst) {
             let bundle = crate::test_util::TestBundle::default();
             return Ok(Box::new(bundle));
         }
 
-        if self.default_bundles.len() != 1 {
+        if self.default_bundles.is_empty() {
             return Err(ErrorKind::Msg(
-                "exactly one default_bundle item must be specified (for now)".to_owned(),
+                "at least one default_bundle item must be specified".to_owned(),
             )
             .into());
         }
 
-        Ok(
-            detect_bundle(self.default_bundles[0].url.to_owned(), only_cached, None)
-                .unwrap()
-                .unwrap(),
-        )
+        let mut layers = Vec::new();
+
+        for info in &self.default_bundles {
+            match detect_bundle(info.url.to_owned(), only_cached, None)? {
+                Some(b) => layers.push(b),
+                None => {
+                    return Err(ErrorKind::Msg(format!(
+                        "`{}` doesn't specify a valid bundle",
+                        info.url
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        if layers.len() == 1 {
+            return Ok(layers.remove(0));
+        }
+
+        Ok(Box::new(LayeredBundle::new(layers)))
     }
 
     /// Get the cache directory to use for format files
@@ -7,24 +7,52 @@
 //! `tectonic_docmodel` crate with the actual document-processing capabilities
 //! provided by the processing engines.
 
-use std::{fmt::Write as FmtWrite, fs, io, path::PathBuf};
+use std::{
+    fmt::Write as FmtWrite,
+    fs, io,
+    path::{Path, PathBuf},
+};
 use tectonic_bridge_core::SecuritySettings;
-use tectonic_bundles::{detect_bundle, Bundle};
+use tectonic_bundles::{detect_bundle, layered::LayeredBundle, Bundle};
 use tectonic_docmodel::{
-    document::{BuildTargetType, Document, InputFile},
+    document::{BuildPass, BuildTargetType, Document, InputFile, PdfConformance, PdfEncryption},
+    lockfile::Lockfile,
     workspace::{Workspace, WorkspaceCreator},
 };
 use tectonic_geturl::{DefaultBackend, GetUrlBackend};
+use tectonic_io_base::{
+    digest::{self, DigestData},
+    OpenResult,
+};
+use url::Url;
 
 use crate::{
     config, ctry,
     driver::{OutputFormat, PassSetting, ProcessingSessionBuilder},
+    engines::xdvipdfmx,
+    errmsg,
     errors::{ErrorKind, Result},
     status::StatusBackend,
-    test_util, tt_note,
+    test_util, tt_note, tt_warning,
     unstable_opts::UnstableOptions,
 };
 
+/// How document setup treats a document’s `Tectonic.lock` file, if it has one.
+///
+/// See [`tectonic_docmodel::lockfile`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LockPolicy {
+    /// Fail if the bundle doesn’t match the lockfile.
+    #[default]
+    Enforce,
+
+    /// Warn if the bundle doesn’t match the lockfile, but carry on.
+    Warn,
+
+    /// Don’t check the lockfile at all.
+    Ignore,
+}
+
 /// Options for setting up [`Document`] instances with the driver
 #[derive(Clone, Debug, Default)]
 pub struct DocumentSetupOptions {
@@ -37,6 +65,19 @@ pub struct DocumentSetupOptions {
 
     /// Ensure a deterministic build environment.
     deterministic_mode: bool,
+
+    /// Keep a persistent build cache in the document's build directory.
+    build_cache: bool,
+
+    /// How to treat the document's lockfile.
+    lock_policy: LockPolicy,
+
+    /// PDF encryption settings that take precedence over the profile's.
+    pdf_encryption: Option<PdfEncryption>,
+
+    /// PDF passwords that take precedence over the profile's, if it turns
+    /// encryption on.
+    pdf_passwords: Option<PdfEncryption>,
 }
 
 impl DocumentSetupOptions {
@@ -46,6 +87,10 @@ impl DocumentSetupOptions {
         DocumentSetupOptions {
             only_cached: false,
             deterministic_mode: false,
+            build_cache: false,
+            lock_policy: LockPolicy::Enforce,
+            pdf_encryption: None,
+            pdf_passwords: None,
             security,
         }
     }
@@ -65,6 +110,70 @@ impl DocumentSetupOptions {
         self.deterministic_mode = s;
         self
     }
+
+    /// Specify whether to keep a persistent build cache for each output,
+    /// allowing unchanged outputs to be skipped and changed ones to be rebuilt
+    /// incrementally.
+    ///
+    /// The cache is stored in a subdirectory `.cache` of the document’s build
+    /// directory. See [`ProcessingSessionBuilder::build_cache_dir`] for
+    /// details.
+    pub fn build_cache(&mut self, s: bool) -> &mut Self {
+        self.build_cache = s;
+        self
+    }
+
+    /// Specify how to treat the document’s `Tectonic.lock` file when a
+    /// session is set up. By default, setup fails if the document’s bundle
+    /// doesn’t match it.
+    pub fn lock_policy(&mut self, p: LockPolicy) -> &mut Self {
+        self.lock_policy = p;
+        self
+    }
+
+    /// Specify PDF encryption settings that override those of the output
+    /// profile. The fields that are set take precedence, and if the profile
+    /// doesn’t ask for encryption, PDF output will be encrypted anyway.
+    pub fn pdf_encryption(&mut self, e: PdfEncryption) -> &mut Self {
+        self.pdf_encryption = Some(e);
+        self
+    }
+
+    /// Specify PDF encryption settings, usually just the passwords, that
+    /// override those of the output profile if it asks for encryption. Unlike
+    /// [`Self::pdf_encryption`], these don’t turn encryption on by
+    /// themselves.
+    pub fn pdf_passwords(&mut self, e: PdfEncryption) -> &mut Self {
+        self.pdf_passwords = Some(e);
+        self
+    }
+}
+
+/// Turn document-model PDF encryption settings into the engine’s, filling in
+/// the defaults for anything that isn’t set.
+pub fn engine_pdf_encryption(settings: &PdfEncryption) -> Result<xdvipdfmx::PdfEncryption> {
+    let mut enc = xdvipdfmx::PdfEncryption::default();
+
+    if let Some(ref pw) = settings.owner_password {
+        enc.owner_password = pw.clone();
+    }
+
+    if let Some(ref pw) = settings.user_password {
+        enc.user_password = pw.clone();
+    }
+
+    if let Some(bits) = settings.key_length {
+        enc.key_bits = bits;
+    }
+
+    if let Some(ref names) = settings.permissions {
+        match xdvipdfmx::PdfPermissions::from_names(names.iter().map(|n| n.as_str())) {
+            Ok(p) => enc.permissions = p,
+            Err(e) => return Err(errmsg!("{}", e)),
+        }
+    }
+
+    Ok(enc)
 }
 
 /// Extension methods for [`Document`].
@@ -75,10 +184,20 @@ pub trait DocumentExt {
     /// bundle backend.
     fn bundle(&self, setup_options: &DocumentSetupOptions) -> Result<Box<dyn Bundle>>;
 
+    /// Check the document’s bundle against its `Tectonic.lock` file, if it has
+    /// one.
+    ///
+    /// If the bundle digest doesn’t match, but the lockfile records the
+    /// digests of the bundle files that the document uses and none of them
+    /// have changed, this only warns. Otherwise, a mismatch is an error.
+    fn check_lockfile(&self, bundle: &mut dyn Bundle, status: &mut dyn StatusBackend)
+        -> Result<()>;
+
     /// Set up a [`ProcessingSessionBuilder`] for one of the outputs.
     ///
     /// The *output_profile* argument gives the name of the document’s output profile to
-    /// use.
+    /// use. The bundle is checked against the document’s lockfile as directed
+    /// by the setup options; see [`Self::check_lockfile`].
     fn setup_session(
         &self,
         output_profile: &str,
@@ -90,17 +209,100 @@ pub trait DocumentExt {
 impl DocumentExt for Document {
     fn bundle(&self, setup_options: &DocumentSetupOptions) -> Result<Box<dyn Bundle>> {
         // Load test bundle
-        if config::is_config_test_mode_activated() {
-            let bundle = test_util::TestBundle::default();
-            return Ok(Box::new(bundle));
+        let base: Box<dyn Bundle> = if config::is_config_test_mode_activated() {
+            Box::new(test_util::TestBundle::default())
+        } else {
+            open_bundle(self.bundle_loc.clone(), setup_options.only_cached)?
+        };
+
+        if self.bundle_overlays.is_empty() {
+            return Ok(base);
+        }
+
+        let mut layers = Vec::new();
+
+        for loc in &self.bundle_overlays {
+            // Local paths are relative to the document, not the working
+            // directory.
+            let loc = if Url::parse(loc).is_err() && Path::new(loc).is_relative() {
+                self.src_dir().join(loc).to_string_lossy().into_owned()
+            } else {
+                loc.clone()
+            };
+
+            layers.push(open_bundle(loc, setup_options.only_cached)?);
+        }
+
+        layers.push(base);
+        Ok(Box::new(LayeredBundle::new(layers)))
+    }
+
+    fn check_lockfile(
+        &self,
+        bundle: &mut dyn Bundle,
+        status: &mut dyn StatusBackend,
+    ) -> Result<()> {
+        let lock_path = self.lockfile_path();
+
+        let Some(lock) = Lockfile::load(&lock_path)? else {
+            return Ok(());
+        };
+
+        let digest = bundle.get_digest()?.to_string();
+
+        if digest == lock.bundle_digest {
+            return Ok(());
         }
 
-        let d = detect_bundle(self.bundle_loc.clone(), setup_options.only_cached, None)?;
+        if !lock.files.is_empty() {
+            let mut changed = 0;
+
+            for (name, locked) in &lock.files {
+                let actual = match bundle.input_open_name(name, status) {
+                    OpenResult::Ok(mut ih) => {
+                        let mut dc = digest::create();
+                        io::copy(&mut ih, &mut dc)?;
+                        Some(DigestData::from(dc).to_string())
+                    }
+                    OpenResult::NotAvailable => None,
+                    OpenResult::Err(e) => return Err(e.into()),
+                };
+
+                if actual.as_ref() != Some(locked) {
+                    changed += 1;
+                }
+            }
+
+            if changed == 0 {
+                tt_warning!(
+                    status,
+                    "the bundle of document `{}` doesn’t match `{}`, but none of the {} \
+                     bundle files that it uses have changed",
+                    self.name,
+                    lock_path.display(),
+                    lock.files.len()
+                );
+                return Ok(());
+            }
 
-        match d {
-            Some(b) => Ok(b),
-            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "Could not get bundle").into()),
+            tt_warning!(
+                status,
+                "{} of the {} bundle files used by document `{}` have changed",
+                changed,
+                lock.files.len(),
+                self.name
+            );
         }
+
+        Err(ErrorKind::Msg(format!(
+            "the bundle of document `{}` has changed since `{}` was written (locked digest {}, \
+             current digest {}); run `tectonic -X bundle update` to accept the new bundle",
+            self.name,
+            lock_path.display(),
+            lock.bundle_digest,
+            digest
+        ))
+        .into())
     }
 
     fn setup_session(
@@ -136,26 +338,78 @@ impl DocumentExt for Document {
         let mut sess_builder =
             ProcessingSessionBuilder::new_with_security(setup_options.security.clone());
 
-        // Interpret all extra paths as relative to our working dir
+        // Interpret all extra paths as relative to our working dir. The
+        // profile's paths come after the document's.
         let extra_paths: Vec<PathBuf> = self
             .extra_paths
             .iter()
+            .chain(&profile.extra_paths)
             .map(|x| self.src_dir().join(x))
             .collect();
 
+        let deterministic_mode = setup_options.deterministic_mode || profile.deterministic_mode;
+
+        let pdf_conformance = match profile.pdf_conformance {
+            PdfConformance::None => xdvipdfmx::PdfConformance::None,
+            PdfConformance::PdfA2b => xdvipdfmx::PdfConformance::PdfA2b,
+            PdfConformance::PdfX4 => xdvipdfmx::PdfConformance::PdfX4,
+        };
+
+        let pass = match profile.pass {
+            BuildPass::Default => PassSetting::Default,
+            BuildPass::Tex => PassSetting::Tex,
+            BuildPass::BibtexFirst => PassSetting::BibtexFirst,
+        };
+
         sess_builder
             .output_format(output_format)
             .format_name(&profile.tex_format)
-            .build_date_from_env(setup_options.deterministic_mode)
+            .build_date_from_env(deterministic_mode)
             .unstables(UnstableOptions {
-                deterministic_mode: setup_options.deterministic_mode,
+                deterministic_mode,
                 extra_search_paths: extra_paths,
+                paper_size: profile.paper_size.clone(),
+                min_crossrefs: profile.min_crossrefs,
                 ..Default::default()
             })
-            .pass(PassSetting::Default)
+            .pass(pass)
             .primary_input_buffer(input_buffer.as_bytes())
             .tex_input_name(output_profile)
-            .synctex(profile.synctex);
+            .synctex(profile.synctex)
+            .pdf_conformance(pdf_conformance)
+            .keep_intermediates(profile.keep_intermediates);
+
+        if profile.synctex_relative_paths {
+            sess_builder.synctex_path_base(self.src_dir());
+        }
+
+        if let Some(ref p) = profile.output_intent_profile {
+            sess_builder.output_intent_profile(p);
+        }
+
+        if let Some(ref c) = profile.output_condition {
+            sess_builder.output_condition(c);
+        }
+
+        let profile_encryption = match (&profile.encryption, &setup_options.pdf_passwords) {
+            (Some(p), Some(o)) => Some(p.overridden_by(o)),
+            (p, _) => p.clone(),
+        };
+
+        let encryption = match (&profile_encryption, &setup_options.pdf_encryption) {
+            (None, None) => None,
+            (Some(p), None) => Some(p.clone()),
+            (None, Some(o)) => Some(o.clone()),
+            (Some(p), Some(o)) => Some(p.overridden_by(o)),
+        };
+
+        if let Some(ref e) = encryption {
+            sess_builder.pdf_encryption(engine_pdf_encryption(e)?);
+        }
+
+        if let Some(reruns) = profile.reruns {
+            sess_builder.reruns(reruns);
+        }
 
         if profile.shell_escape {
             // For now, this is the only option we allow.
@@ -169,7 +423,21 @@ impl DocumentExt for Document {
         if setup_options.only_cached {
             tt_note!(status, "using only cached resource files");
         }
-        sess_builder.bundle(self.bundle(setup_options)?);
+        let mut bundle = self.bundle(setup_options)?;
+
+        match setup_options.lock_policy {
+            LockPolicy::Enforce => self.check_lockfile(bundle.as_mut(), status)?,
+
+            LockPolicy::Warn => {
+                if let Err(e) = self.check_lockfile(bundle.as_mut(), status) {
+                    tt_warning!(status, "{}", e);
+                }
+            }
+
+            LockPolicy::Ignore => {}
+        }
+
+        sess_builder.bundle(bundle);
 
         let mut tex_dir = self.src_dir().to_owned();
         tex_dir.push("src");
@@ -183,10 +451,29 @@ impl DocumentExt for Document {
         );
         sess_builder.output_dir(&output_dir);
 
+        if setup_options.build_cache {
+            let mut cache_dir = self.build_dir().to_owned();
+            cache_dir.push(".cache");
+            cache_dir.push(output_profile);
+            sess_builder.build_cache_dir(&cache_dir);
+        }
+
         Ok(sess_builder)
     }
 }
 
+/// Open the bundle at a location, which is either a URL or a local path.
+fn open_bundle(loc: String, only_cached: bool) -> Result<Box<dyn Bundle>> {
+    match detect_bundle(loc.clone(), only_cached, None)? {
+        Some(b) => Ok(b),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("could not get bundle `{loc}`"),
+        )
+        .into()),
+    }
+}
+
 /// Extension methods for [`WorkspaceCreator`].
 pub trait WorkspaceCreatorExt {
     /// Create the new workspace with a good default for the bundle location.
@@ -207,14 +494,32 @@ impl WorkspaceCreatorExt for WorkspaceCreator {
         config: &config::PersistentConfig,
         bundle: Option<String>,
     ) -> Result<Workspace> {
+        let mut wc = self;
+
         let bundle_loc = if config::is_test_bundle_wanted(bundle.clone()) {
             "test-bundle://".to_owned()
         } else {
-            let loc = bundle.unwrap_or(config.default_bundle_loc().to_owned());
+            let loc = match bundle {
+                Some(loc) => loc,
+                None => {
+                    // Overlays from the configuration only make sense on top
+                    // of the configured base bundle, which comes last.
+                    let mut locs = config.default_bundle_locs();
+                    let base = locs
+                        .pop()
+                        .ok_or_else(|| {
+                            errmsg!("at least one default_bundle item must be specified")
+                        })?
+                        .to_owned();
+                    wc.bundle_overlays(locs.into_iter().map(|l| l.to_owned()).collect());
+                    base
+                }
+            };
+
             let mut gub = DefaultBackend::default();
             gub.resolve_url(&loc)?
         };
 
-        Ok(self.create(bundle_loc, Vec::new())?)
+        Ok(wc.create(bundle_loc, Vec::new())?)
     }
 }
@@ -16,7 +16,8 @@
 use byte_unit::{Byte, UnitType};
 use quick_xml::{events::Event, NsReader};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
     fs::File,
     io::{Cursor, Read, Write},
     path::{Path, PathBuf},
@@ -24,13 +25,13 @@ use std::{
     rc::Rc,
     result::Result as StdResult,
     str::FromStr,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
-use tectonic_bridge_core::{CoreBridgeLauncher, DriverHooks, SecuritySettings, SystemRequestError};
-use tectonic_bundles::Bundle;
+use tectonic_bridge_core::{DriverHooks, SecuritySettings, SystemRequestError};
+use tectonic_bundles::{dir::DirBundle, Bundle};
 use tectonic_engine_spx2html::AssetSpecification;
 use tectonic_io_base::{
-    digest::DigestData,
+    digest::{self, Digest, DigestComputer, DigestData},
     filesystem::{FilesystemIo, FilesystemPrimaryInputIo},
     stdstreams::{BufferedPrimaryIo, GenuineStdoutIo},
     InputHandle, IoProvider, OpenResult, OutputHandle,
@@ -38,24 +39,30 @@ use tectonic_io_base::{
 use which::which;
 
 use crate::{
-    ctry, errmsg,
+    ctry,
+    engines::xdvipdfmx::{PdfConformance, PdfEncryption},
+    errmsg,
     errors::{ChainErrCompatExt, ErrorKind, Result},
     io::{
-        format_cache::FormatCache,
+        build_cache::{BuildCache, CachedSession},
+        format_cache::{FormatCache, FormatMemory},
         memory::{MemoryFileCollection, MemoryIo},
         InputOrigin,
     },
     status::StatusBackend,
     tt_error, tt_note, tt_warning,
     unstable_opts::UnstableOptions,
-    BibtexEngine, Spx2HtmlEngine, TexEngine, TexOutcome, XdvipdfmxEngine,
+    worker::{EngineJob, EngineLaunch, EngineWorker},
+    Spx2HtmlEngine, TexOutcome,
 };
 
 /// Different patterns with which files may have been accessed by the
 /// underlying engines. Once a file is marked as ReadThenWritten or
 /// WrittenThenRead, its pattern does not evolve further.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum AccessPattern {
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "snake_case"))]
+pub enum AccessPattern {
     /// This file is only ever read.
     Read,
 
@@ -80,10 +87,16 @@ enum AccessPattern {
 /// digest of the file when it was last read; and the cryptographic digest of
 /// the file as it was last written.
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct FileSummary {
-    access_pattern: AccessPattern,
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct FileSummary {
+    /// How the engines accessed this file over the course of the session.
+    pub access_pattern: AccessPattern,
 
     /// If this file was read, where did it come from?
+    #[cfg_attr(
+        feature = "serialization",
+        serde(serialize_with = "report_serde::input_origin")
+    )]
     pub input_origin: InputOrigin,
 
     /// If this file was read, this is the digest of its contents at the time it was *first* read.
@@ -93,13 +106,28 @@ struct FileSummary {
     /// There's some chance that this will be `None` even if the file was read. Tectonic makes an
     /// effort to compute the digest as the data is being read from the file, but this can fail if
     /// tex decides to seek in the file as it is being written.
+    #[cfg_attr(
+        feature = "serialization",
+        serde(serialize_with = "report_serde::digest")
+    )]
     pub read_digest: Option<DigestData>,
 
     /// If this file was written, this is the digest of its contents at the time it was last
     /// written.
+    #[cfg_attr(
+        feature = "serialization",
+        serde(serialize_with = "report_serde::digest")
+    )]
     pub write_digest: Option<DigestData>,
 
-    got_written_to_disk: bool,
+    /// Whether the driver actually wrote this file out to disk.
+    pub got_written_to_disk: bool,
+
+    /// If this file was read from the filesystem, its absolute path.
+    pub abspath: Option<PathBuf>,
+
+    /// Whether this file was provided by the backing bundle.
+    pub from_bundle: bool,
 }
 
 impl FileSummary {
@@ -110,6 +138,8 @@ impl FileSummary {
             read_digest: None,
             write_digest: None,
             got_written_to_disk: false,
+            abspath: None,
+            from_bundle: false,
         }
     }
 }
@@ -230,7 +260,7 @@ struct BridgeState {
     filesystem: FilesystemIo,
 
     /// Extra paths we search through for files.
-    extra_search_paths: Vec<FilesystemIo>,
+    extra_search_paths: Vec<DirBundle>,
 
     /// Additional filesystem backing used if "shell escape" functionality is
     /// activated. If None, we take that to mean that shell-escape is
@@ -253,6 +283,11 @@ struct BridgeState {
 
     /// The I/O events that occurred while processing.
     events: HashMap<String, FileSummary>,
+
+    /// Whether the most recent I/O request was handled by the bundle. This is
+    /// updated by `bridgestate_ioprovider_cascade!` so that dependency
+    /// tracking can tell bundle files apart from everything else.
+    last_open_from_bundle: bool,
 }
 
 impl BridgeState {
@@ -420,6 +455,29 @@ impl BridgeState {
         Ok(())
     }
 
+    /// Try to open an input from the filesystem layers of the I/O stack only,
+    /// in the same order as the full cascade. This is used to check the build
+    /// cache without consulting the bundle or recording I/O events.
+    fn input_open_local(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        match self.filesystem.input_open_name_with_abspath(name, status) {
+            OpenResult::NotAvailable => {}
+            r => return r,
+        }
+
+        for dir in self.extra_search_paths.iter_mut() {
+            match dir.input_open_name_with_abspath(name, status) {
+                OpenResult::NotAvailable => {}
+                r => return r,
+            }
+        }
+
+        OpenResult::NotAvailable
+    }
+
     // Get the names of all intermediate files which are generated from
     // previous passes.
     fn get_intermediate_file_names(&self) -> Vec<String> {
@@ -440,6 +498,8 @@ macro_rules! bridgestate_ioprovider_try {
 
 macro_rules! bridgestate_ioprovider_cascade {
     ($self:ident, $($inner:tt)+) => {
+        $self.last_open_from_bundle = false;
+
         if let Some(ref mut p) = $self.genuine_stdout {
             bridgestate_ioprovider_try!(p, $($inner)+);
         }
@@ -474,7 +534,10 @@ macro_rules! bridgestate_ioprovider_cascade {
             }
         }
 
+        $self.last_open_from_bundle = true;
         bridgestate_ioprovider_try!($self.bundle.as_ioprovider_mut(), $($inner)+);
+        $self.last_open_from_bundle = false;
+
         bridgestate_ioprovider_try!($self.format_cache, $($inner)+);
 
         return OpenResult::NotAvailable;
@@ -551,17 +614,17 @@ impl IoProvider for BridgeState {
         })();
 
         match r {
-            OpenResult::Ok((ref ih, ref _path)) => {
+            OpenResult::Ok((ref ih, ref path)) => {
                 if let Some(summ) = self.events.get_mut(name) {
                     summ.access_pattern = match summ.access_pattern {
                         AccessPattern::Written => AccessPattern::WrittenThenRead,
                         c => c, // identity mapping makes sense for remaining options
                     };
                 } else {
-                    self.events.insert(
-                        name.to_owned(),
-                        FileSummary::new(AccessPattern::Read, ih.origin()),
-                    );
+                    let mut fs = FileSummary::new(AccessPattern::Read, ih.origin());
+                    fs.abspath.clone_from(path);
+                    fs.from_bundle = self.last_open_from_bundle;
+                    self.events.insert(name.to_owned(), fs);
                 }
             }
 
@@ -804,8 +867,13 @@ pub struct ProcessingSessionBuilder {
     filesystem_root: Option<PathBuf>,
     format_name: Option<String>,
     format_cache_path: Option<PathBuf>,
+    format_memory: Option<FormatMemory>,
     output_format: OutputFormat,
     makefile_output_path: Option<PathBuf>,
+    depfile_output_path: Option<PathBuf>,
+    dependency_manifest_path: Option<PathBuf>,
+    report_output_path: Option<PathBuf>,
+    build_cache_dir: Option<PathBuf>,
     hidden_input_paths: HashSet<PathBuf>,
     pass: PassSetting,
     reruns: Option<usize>,
@@ -814,6 +882,11 @@ pub struct ProcessingSessionBuilder {
     keep_intermediates: bool,
     keep_logs: bool,
     synctex: bool,
+    synctex_path_base: Option<PathBuf>,
+    pdf_conformance: PdfConformance,
+    output_intent_profile: Option<String>,
+    output_condition: Option<String>,
+    pdf_encryption: Option<PdfEncryption>,
     build_date: Option<SystemTime>,
     unstables: UnstableOptions,
     shell_escape_mode: ShellEscapeMode,
@@ -821,6 +894,7 @@ pub struct ProcessingSessionBuilder {
     html_precomputed_assets: Option<AssetSpecification>,
     html_do_not_emit_files: bool,
     html_do_not_emit_assets: bool,
+    engine_worker: Option<EngineWorker>,
 }
 
 impl ProcessingSessionBuilder {
@@ -911,6 +985,15 @@ impl ProcessingSessionBuilder {
         self
     }
 
+    /// Keep the contents of format files in the given [`FormatMemory`].
+    ///
+    /// A process that runs many sessions can share one `FormatMemory` among
+    /// all of them, so that each format file is only read from disk once.
+    pub fn format_memory(&mut self, memory: FormatMemory) -> &mut Self {
+        self.format_memory = Some(memory);
+        self
+    }
+
     /// The type of output to create.
     pub fn output_format(&mut self, f: OutputFormat) -> &mut Self {
         self.output_format = f;
@@ -923,6 +1006,58 @@ impl ProcessingSessionBuilder {
         self
     }
 
+    /// If set, a Ninja/GCC-style depfile will be written out at the given
+    /// path.
+    ///
+    /// The depfile lists every file written to disk as a target, depending on
+    /// every input read from the filesystem. Files provided by the bundle are
+    /// not listed; see [`Self::dependency_manifest_path`] for a more complete
+    /// accounting.
+    pub fn depfile_output_path<P: AsRef<Path>>(&mut self, p: P) -> &mut Self {
+        self.depfile_output_path = Some(p.as_ref().to_owned());
+        self
+    }
+
+    /// If set, a JSON manifest of the session's dependencies will be written
+    /// out at the given path.
+    ///
+    /// This requires the `serialization` Cargo feature; without it, a warning
+    /// is issued and no manifest is written. The same information is available
+    /// programmatically through [`ProcessingSession::dependency_manifest`].
+    pub fn dependency_manifest_path<P: AsRef<Path>>(&mut self, p: P) -> &mut Self {
+        self.dependency_manifest_path = Some(p.as_ref().to_owned());
+        self
+    }
+
+    /// If set, a JSON report describing the files accessed and the passes run
+    /// will be written out at the given path.
+    ///
+    /// The report is written even if processing fails. This requires the
+    /// `serialization` Cargo feature; without it, a warning is issued and no
+    /// report is written. The same information is available programmatically
+    /// through [`ProcessingSession::report`].
+    pub fn report_output_path<P: AsRef<Path>>(&mut self, p: P) -> &mut Self {
+        self.report_output_path = Some(p.as_ref().to_owned());
+        self
+    }
+
+    /// If set, a persistent build cache will be kept in the given directory.
+    ///
+    /// After a successful run, the session records the digests of its inputs,
+    /// the outputs that it wrote, and the intermediate files (such as `.aux`,
+    /// `.toc`, and `.bbl`) that carry state between TeX passes. The next
+    /// session with the same configuration skips processing entirely if none
+    /// of its inputs or outputs have changed, and otherwise starts from the
+    /// saved intermediate files, which usually saves the priming TeX pass.
+    ///
+    /// The cache is only used with the default pass settings, and is never
+    /// used if shell-escape is enabled, for HTML output, or if the outputs
+    /// aren't being saved to disk.
+    pub fn build_cache_dir<P: AsRef<Path>>(&mut self, p: P) -> &mut Self {
+        self.build_cache_dir = Some(p.as_ref().to_owned());
+        self
+    }
+
     /// Which kind of pass should the `ProcessingSession` run? Defaults to `PassSetting::Default`
     /// (duh).
     pub fn pass(&mut self, p: PassSetting) -> &mut Self {
@@ -978,6 +1113,45 @@ impl ProcessingSessionBuilder {
         self
     }
 
+    /// Record input paths in SyncTeX data relative to the directory *p*,
+    /// rather than as absolute paths.
+    ///
+    /// This keeps SyncTeX working in deterministic mode, which otherwise
+    /// hides absolute paths from the engine, and lets the data be used after
+    /// the sources are moved. The directory should be given as an absolute
+    /// path.
+    pub fn synctex_path_base<P: AsRef<Path>>(&mut self, p: P) -> &mut Self {
+        self.synctex_path_base = Some(p.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the standard that PDF output should conform to, such as PDF/A-2b.
+    pub fn pdf_conformance(&mut self, c: PdfConformance) -> &mut Self {
+        self.pdf_conformance = c;
+        self
+    }
+
+    /// Sets the ICC profile embedded as the output intent of conforming PDF
+    /// output. See `XdvipdfmxEngine::output_intent_profile`.
+    pub fn output_intent_profile(&mut self, profile: &str) -> &mut Self {
+        self.output_intent_profile = Some(profile.to_owned());
+        self
+    }
+
+    /// Sets the identifier of the output condition of conforming PDF output.
+    /// See `XdvipdfmxEngine::output_condition`.
+    pub fn output_condition(&mut self, condition: &str) -> &mut Self {
+        self.output_condition = Some(condition.to_owned());
+        self
+    }
+
+    /// Encrypts PDF output with the given passwords and permissions. See
+    /// `XdvipdfmxEngine::encryption`.
+    pub fn pdf_encryption(&mut self, encryption: PdfEncryption) -> &mut Self {
+        self.pdf_encryption = Some(encryption);
+        self
+    }
+
     /// Sets the date and time of the processing session.
     /// See `TexEngine::build_date` for mor information.
     pub fn build_date(&mut self, date: SystemTime) -> &mut Self {
@@ -1107,6 +1281,18 @@ impl ProcessingSessionBuilder {
         self
     }
 
+    /// Run each engine pass in a child process started from *worker*, rather
+    /// than in this process.
+    ///
+    /// Only one engine can run in a process at a time, so sessions in
+    /// different threads normally wait for each other. Sessions that use
+    /// workers don't, at the cost of starting a process for every engine pass.
+    /// See [`crate::worker`] for the requirements on the worker program.
+    pub fn engine_worker(&mut self, worker: EngineWorker) -> &mut Self {
+        self.engine_worker = Some(worker);
+        self
+    }
+
     /// Creates a `ProcessingSession`.
     pub fn create(self, status: &mut dyn StatusBackend) -> Result<ProcessingSession> {
         // First, work on the "bridge state", which gathers the subset of our
@@ -1160,7 +1346,12 @@ impl ProcessingSessionBuilder {
         let format_cache_path = self
             .format_cache_path
             .unwrap_or_else(|| filesystem_root.clone());
-        let format_cache = FormatCache::new(bundle.get_digest()?, format_cache_path);
+        let bundle_digest = bundle.get_digest()?;
+        let mut format_cache = FormatCache::new(bundle_digest, format_cache_path);
+
+        if let Some(memory) = self.format_memory {
+            format_cache = format_cache.with_memory(memory);
+        }
 
         let genuine_stdout = if self.print_stdout {
             Some(GenuineStdoutIo::new())
@@ -1175,7 +1366,7 @@ impl ProcessingSessionBuilder {
             self.unstables
                 .extra_search_paths
                 .iter()
-                .map(|p| FilesystemIo::new(p, false, false, hidden_input_paths.clone()))
+                .map(|p| DirBundle::new_for_search_path(p, hidden_input_paths.clone()))
                 .collect()
         } else {
             if !self.unstables.extra_search_paths.is_empty() {
@@ -1199,6 +1390,7 @@ impl ProcessingSessionBuilder {
             genuine_stdout,
             format_primary: None,
             events: HashMap::new(),
+            last_open_from_bundle: false,
         };
 
         // Now we can do the rest.
@@ -1253,11 +1445,22 @@ impl ProcessingSessionBuilder {
             tex_pdf_path: pdf_path.display().to_string(),
             output_format: self.output_format,
             makefile_output_path: self.makefile_output_path,
+            depfile_output_path: self.depfile_output_path,
+            dependency_manifest_path: self.dependency_manifest_path,
+            report_output_path: self.report_output_path,
+            bundle_digest,
+            build_cache: self.build_cache_dir.map(BuildCache::new),
+            restored_build_state: false,
             output_path,
             tex_rerun_specification: self.reruns,
             keep_intermediates: self.keep_intermediates,
             keep_logs: self.keep_logs,
             synctex_enabled: self.synctex,
+            synctex_path_base: self.synctex_path_base,
+            pdf_conformance: self.pdf_conformance,
+            output_intent_profile: self.output_intent_profile,
+            output_condition: self.output_condition,
+            pdf_encryption: self.pdf_encryption,
             build_date: self.build_date.unwrap_or(SystemTime::UNIX_EPOCH),
             unstables: self.unstables,
             shell_escape_mode,
@@ -1265,15 +1468,247 @@ impl ProcessingSessionBuilder {
             html_precomputed_assets: self.html_precomputed_assets,
             html_emit_files: !self.html_do_not_emit_files,
             html_emit_assets: !self.html_do_not_emit_assets,
+            engine_worker: self.engine_worker,
+            passes: Vec::new(),
         })
     }
 }
 
-#[derive(Debug, Clone)]
-enum RerunReason {
+/// The reasons that [`ProcessingSession`] might decide to rerun the TeX
+/// engine.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "snake_case"))]
+pub enum RerunReason {
+    /// The `biber` tool was run, so its outputs need to be incorporated.
     Biber,
+
+    /// BibTeX was run, so its outputs need to be incorporated.
     Bibtex,
+
+    /// The named file was read and then rewritten with different contents.
     FileChange(String),
+
+    /// The session was configured to run a fixed number of passes.
+    Requested,
+}
+
+impl fmt::Display for RerunReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RerunReason::Biber => write!(f, "biber was run"),
+            RerunReason::Bibtex => write!(f, "bibtex was run"),
+            RerunReason::FileChange(s) => write!(f, "\"{s}\" changed"),
+            RerunReason::Requested => write!(f, "I was told to"),
+        }
+    }
+}
+
+/// The different kinds of passes that a [`ProcessingSession`] may run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "snake_case"))]
+pub enum PassKind {
+    /// The TeX engine, run in "initex" mode to generate a format file.
+    Format,
+
+    /// The TeX engine.
+    Tex,
+
+    /// The BibTeX engine, processing the named `.aux` file.
+    Bibtex(String),
+
+    /// An external tool, such as `biber`, identified by its executable.
+    ExternalTool(String),
+
+    /// The `xdvipdfmx` engine.
+    Xdvipdfmx,
+
+    /// The SPX-to-HTML engine.
+    Spx2html,
+}
+
+/// A record of one pass run during a [`ProcessingSession`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct PassSummary {
+    /// What kind of pass this was.
+    pub kind: PassKind,
+
+    /// If this was a rerun of the TeX engine, why it was rerun.
+    pub rerun_reason: Option<RerunReason>,
+
+    /// How long the pass took, in wall-clock time.
+    #[cfg_attr(
+        feature = "serialization",
+        serde(rename = "wall_time_secs", serialize_with = "report_serde::duration")
+    )]
+    pub wall_time: Duration,
+
+    /// The outcome reported by the engine, for those engines that report one.
+    #[cfg_attr(
+        feature = "serialization",
+        serde(serialize_with = "report_serde::outcome")
+    )]
+    pub outcome: Option<TexOutcome>,
+
+    /// Whether the pass completed without a fatal error.
+    pub succeeded: bool,
+}
+
+/// A machine-readable account of what happened during a
+/// [`ProcessingSession`], as returned by [`ProcessingSession::report`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct SessionReport {
+    /// Every file accessed by the engines, keyed by the name under which it
+    /// was accessed. Standard output is not included.
+    pub files: BTreeMap<String, FileSummary>,
+
+    /// Every pass that was run, in order.
+    pub passes: Vec<PassSummary>,
+}
+
+/// A file that was provided by the backing bundle during processing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct BundleInput {
+    /// The name under which the file was requested from the bundle.
+    pub name: String,
+
+    /// The SHA256 digest of the file contents, if they were read.
+    #[cfg_attr(
+        feature = "serialization",
+        serde(serialize_with = "report_serde::digest")
+    )]
+    pub digest: Option<DigestData>,
+}
+
+/// The dependencies of a [`ProcessingSession`], as returned by
+/// [`ProcessingSession::dependency_manifest`].
+///
+/// Unlike a depfile, this distinguishes between inputs that came from the
+/// filesystem, which a build system should track by path, and inputs that
+/// came from the bundle, which are pinned down by the bundle digest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct DependencyManifest {
+    /// The files that were written to disk.
+    pub outputs: Vec<PathBuf>,
+
+    /// The inputs that were read from the filesystem. The primary input, if
+    /// it is a file, comes first.
+    pub filesystem_inputs: Vec<PathBuf>,
+
+    /// The inputs that were provided by the bundle.
+    pub bundle_inputs: Vec<BundleInput>,
+
+    /// The digest of the bundle that was used.
+    #[cfg_attr(
+        feature = "serialization",
+        serde(serialize_with = "report_serde::required_digest")
+    )]
+    pub bundle_digest: DigestData,
+
+    /// Filesystem inputs that were also overwritten on disk by this session.
+    /// These are not listed in `filesystem_inputs` since they can't be
+    /// expressed as dependencies without creating a cycle.
+    pub circular: Vec<PathBuf>,
+}
+
+/// Serialization helpers for types that don't implement `Serialize`
+/// themselves.
+#[cfg(feature = "serialization")]
+mod report_serde {
+    use serde::Serializer;
+    use std::time::Duration;
+    use tectonic_io_base::{digest::DigestData, InputOrigin};
+
+    use crate::TexOutcome;
+
+    pub fn input_origin<S: Serializer>(
+        origin: &InputOrigin,
+        ser: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        ser.serialize_str(match origin {
+            InputOrigin::Filesystem => "filesystem",
+            InputOrigin::NotInput => "not_input",
+            InputOrigin::Other => "other",
+        })
+    }
+
+    pub fn digest<S: Serializer>(
+        digest: &Option<DigestData>,
+        ser: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match digest {
+            Some(d) => ser.serialize_some(&d.to_string()),
+            None => ser.serialize_none(),
+        }
+    }
+
+    pub fn required_digest<S: Serializer>(
+        digest: &DigestData,
+        ser: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        ser.serialize_str(&digest.to_string())
+    }
+
+    pub fn duration<S: Serializer>(d: &Duration, ser: S) -> std::result::Result<S::Ok, S::Error> {
+        ser.serialize_f64(d.as_secs_f64())
+    }
+
+    pub fn outcome<S: Serializer>(
+        outcome: &Option<TexOutcome>,
+        ser: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match outcome {
+            Some(TexOutcome::Spotless) => ser.serialize_some("spotless"),
+            Some(TexOutcome::Warnings) => ser.serialize_some("warnings"),
+            Some(TexOutcome::Errors) => ser.serialize_some("errors"),
+            None => ser.serialize_none(),
+        }
+    }
+}
+
+/// The outcome of consulting the build cache before processing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BuildCacheStatus {
+    /// The cache has nothing usable for this session.
+    Missing,
+
+    /// Intermediate files from a previous session have been loaded, but the
+    /// inputs or outputs have changed, so processing is still needed.
+    Restored,
+
+    /// Nothing has changed since the previous session.
+    UpToDate,
+}
+
+/// Compute the digest of the remaining contents of an input.
+fn digest_input(ih: &mut InputHandle) -> Result<DigestData> {
+    let mut buf = Vec::new();
+    ih.read_to_end(&mut buf)?;
+    let mut dc = digest::create();
+    dc.update(&buf);
+    Ok(DigestData::from(dc))
+}
+
+/// Escape a path for use in a Ninja/GCC-style depfile.
+fn escape_depfile_path(p: &Path) -> Result<String> {
+    let s = ctry!(p.to_str(); "depfile path `{}` must be Unicode-able", p.display());
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            ' ' => escaped.push_str("\\ "),
+            '#' => escaped.push_str("\\#"),
+            '$' => escaped.push_str("$$"),
+            c => escaped.push(c),
+        }
+    }
+
+    Ok(escaped)
 }
 
 /// The ProcessingSession struct runs the whole show when we're actually
@@ -1309,6 +1744,29 @@ pub struct ProcessingSession {
     /// engine doesn't know about this path at all.
     makefile_output_path: Option<PathBuf>,
 
+    /// If we're writing out a Ninja/GCC-style depfile, this is where it goes.
+    depfile_output_path: Option<PathBuf>,
+
+    /// If we're writing out a JSON dependency manifest, this is where it goes.
+    dependency_manifest_path: Option<PathBuf>,
+
+    /// The digest of the backing bundle, recorded in the dependency manifest.
+    bundle_digest: DigestData,
+
+    /// If we're writing out a JSON report of the session, this is where it
+    /// goes.
+    report_output_path: Option<PathBuf>,
+
+    /// The passes that have been run so far, for reporting.
+    passes: Vec<PassSummary>,
+
+    /// The persistent build cache, if one is being used.
+    build_cache: Option<BuildCache>,
+
+    /// Whether intermediate files were loaded from the build cache before
+    /// processing started.
+    restored_build_state: bool,
+
     /// This is the path that the processed file will be saved at. It defaults
     /// to the path of `primary_input_path` or `.` if STDIN is used. If set to
     /// None, the output files will not be saved to disk — in which case, the
@@ -1322,6 +1780,11 @@ pub struct ProcessingSession {
     keep_intermediates: bool,
     keep_logs: bool,
     synctex_enabled: bool,
+    synctex_path_base: Option<PathBuf>,
+    pdf_conformance: PdfConformance,
+    output_intent_profile: Option<String>,
+    output_condition: Option<String>,
+    pdf_encryption: Option<PdfEncryption>,
 
     /// See `TexEngine::with_date` and `XdvipdfmxEngine::with_date`.
     build_date: SystemTime,
@@ -1336,6 +1799,10 @@ pub struct ProcessingSession {
     html_precomputed_assets: Option<AssetSpecification>,
     html_emit_files: bool,
     html_emit_assets: bool,
+
+    /// If set, the engines are run in child processes started from this
+    /// worker.
+    engine_worker: Option<EngineWorker>,
 }
 
 const DEFAULT_MAX_TEX_PASSES: usize = 6;
@@ -1343,6 +1810,55 @@ const ALWAYS_INTERMEDIATE_EXTENSIONS: &[&str] = &[
     ".snm", ".toc", // generated by Beamer
 ];
 
+/// Feeds the settings that make up a build cache key into a digest. Integers
+/// are little-endian, and strings are preceded by their length, so that
+/// different settings can't run together into the same key.
+struct CacheKeyWriter(DigestComputer);
+
+impl CacheKeyWriter {
+    fn u8(&mut self, v: u8) {
+        self.0.update([v]);
+    }
+
+    fn bool(&mut self, v: bool) {
+        self.u8(v.into());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.0.update(v.to_le_bytes());
+    }
+
+    fn bytes(&mut self, v: &[u8]) {
+        self.u64(v.len() as u64);
+        self.0.update(v);
+    }
+
+    fn str(&mut self, v: &str) {
+        self.bytes(v.as_bytes());
+    }
+
+    #[cfg(unix)]
+    fn path(&mut self, v: &Path) {
+        use std::os::unix::ffi::OsStrExt;
+        self.bytes(v.as_os_str().as_bytes());
+    }
+
+    #[cfg(not(unix))]
+    fn path(&mut self, v: &Path) {
+        self.str(&v.to_string_lossy());
+    }
+
+    fn option<T>(&mut self, v: Option<T>, f: impl FnOnce(&mut Self, T)) {
+        match v {
+            Some(x) => {
+                self.bool(true);
+                f(self, x);
+            }
+            None => self.bool(false),
+        }
+    }
+}
+
 impl ProcessingSession {
     /// Assess whether we need to rerun an engine. This is the case if there
     /// was a file that the engine read and then rewrote, and the rewritten
@@ -1455,14 +1971,256 @@ impl ProcessingSession {
             }
         }
 
+        // Write the report, if requested. If processing failed, that's the
+        // error that the caller cares about, so don't let a problem here mask
+        // it.
+
+        if let Some(report_path) = self.report_output_path.clone() {
+            if let Err(e) = self.write_report(&report_path, status) {
+                if result.is_ok() {
+                    return Err(e);
+                }
+
+                tt_warning!(status, "{}", e);
+            }
+        }
+
         // Propagate the actual result.
         result
     }
 
+    /// Get a report of the files accessed and passes run by this session.
+    ///
+    /// This is most useful after [`Self::run`] has been called, whether or
+    /// not it succeeded.
+    pub fn report(&self) -> SessionReport {
+        let stdout_key = self.bs.mem.stdout_key();
+
+        SessionReport {
+            files: self
+                .bs
+                .events
+                .iter()
+                .filter(|(name, _)| name.as_str() != stdout_key)
+                .map(|(name, summ)| (name.clone(), summ.clone()))
+                .collect(),
+            passes: self.passes.clone(),
+        }
+    }
+
+    #[cfg(feature = "serialization")]
+    fn write_report(&self, path: &Path, _status: &mut dyn StatusBackend) -> Result<()> {
+        let f = ctry!(
+            File::create(path);
+            "couldn't create build report file `{}`", path.display()
+        );
+        ctry!(
+            serde_json::to_writer_pretty(f, &self.report());
+            "couldn't write build report file `{}`", path.display()
+        );
+        Ok(())
+    }
+
+    #[cfg(not(feature = "serialization"))]
+    fn write_report(&self, _path: &Path, status: &mut dyn StatusBackend) -> Result<()> {
+        tt_warning!(
+            status,
+            "not writing a build report: Tectonic was built without the \"serialization\" feature"
+        );
+        Ok(())
+    }
+
+    /// Get the dependencies of this session.
+    ///
+    /// This is only meaningful after [`Self::run`] has completed
+    /// successfully. If the session was told not to write its outputs to
+    /// disk, the list of outputs will be empty.
+    pub fn dependency_manifest(&self) -> DependencyManifest {
+        let mut outputs = Vec::new();
+        let mut filesystem_inputs = Vec::new();
+        let mut bundle_inputs = Vec::new();
+        let mut circular = Vec::new();
+
+        let root = self.output_path.as_deref();
+
+        for (name, info) in &self.bs.events {
+            if info.got_written_to_disk {
+                if let Some(root) = root {
+                    outputs.push(root.join(name));
+                }
+            }
+
+            if info.from_bundle {
+                bundle_inputs.push(BundleInput {
+                    name: name.clone(),
+                    digest: info.read_digest,
+                });
+                continue;
+            }
+
+            if info.input_origin != InputOrigin::Filesystem {
+                continue;
+            }
+
+            let path = match (info.abspath.as_ref(), root) {
+                (Some(p), _) => p.clone(),
+                (None, Some(root)) => root.join(name),
+                (None, None) => PathBuf::from(name),
+            };
+
+            if info.got_written_to_disk {
+                circular.push(path);
+            } else {
+                filesystem_inputs.push(path);
+            }
+        }
+
+        outputs.sort();
+        filesystem_inputs.sort();
+        bundle_inputs.sort_by(|a, b| a.name.cmp(&b.name));
+        circular.sort();
+
+        if let Some(ref pip) = self.primary_input_path {
+            filesystem_inputs.retain(|p| p != pip);
+            filesystem_inputs.insert(0, pip.clone());
+        }
+
+        DependencyManifest {
+            outputs,
+            filesystem_inputs,
+            bundle_inputs,
+            bundle_digest: self.bundle_digest,
+            circular,
+        }
+    }
+
+    /// Write a Ninja/GCC-style depfile describing this session's filesystem
+    /// dependencies.
+    fn write_depfile(&self, path: &Path, status: &mut dyn StatusBackend) -> Result<()> {
+        let manifest = self.dependency_manifest();
+
+        if manifest.outputs.is_empty() {
+            tt_warning!(
+                status,
+                "requested to generate a depfile, but no files written to disk!"
+            );
+            return Ok(());
+        }
+
+        for p in &manifest.circular {
+            tt_warning!(
+                status,
+                "omitting circular depfile dependency for {}",
+                p.display()
+            );
+        }
+
+        let mut text = String::new();
+
+        for (i, p) in manifest.outputs.iter().enumerate() {
+            if i > 0 {
+                text.push(' ');
+            }
+
+            text.push_str(&escape_depfile_path(p)?);
+        }
+
+        text.push(':');
+
+        for p in &manifest.filesystem_inputs {
+            text.push_str(" \\\n  ");
+            text.push_str(&escape_depfile_path(p)?);
+        }
+
+        text.push('\n');
+
+        ctry!(
+            std::fs::write(path, text);
+            "couldn't write depfile `{}`", path.display()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serialization")]
+    fn write_dependency_manifest(
+        &self,
+        path: &Path,
+        _status: &mut dyn StatusBackend,
+    ) -> Result<()> {
+        let f = ctry!(
+            File::create(path);
+            "couldn't create dependency manifest file `{}`", path.display()
+        );
+        ctry!(
+            serde_json::to_writer_pretty(f, &self.dependency_manifest());
+            "couldn't write dependency manifest file `{}`", path.display()
+        );
+        Ok(())
+    }
+
+    #[cfg(not(feature = "serialization"))]
+    fn write_dependency_manifest(
+        &self,
+        _path: &Path,
+        status: &mut dyn StatusBackend,
+    ) -> Result<()> {
+        tt_warning!(
+            status,
+            "not writing a dependency manifest: Tectonic was built without the \"serialization\" feature"
+        );
+        Ok(())
+    }
+
+    /// Record a pass that was started at *start* for the session report.
+    fn record_pass(
+        &mut self,
+        kind: PassKind,
+        rerun_reason: Option<RerunReason>,
+        start: Instant,
+        outcome: Option<TexOutcome>,
+        succeeded: bool,
+    ) {
+        self.passes.push(PassSummary {
+            kind,
+            rerun_reason,
+            wall_time: start.elapsed(),
+            outcome,
+            succeeded,
+        });
+    }
+
     /// The bulk of the `run` implementation. We need to wrap it to manage the
     /// lifecycle of resources like the shell-escape temporary directory, if
     /// needed.
     fn run_inner(&mut self, status: &mut dyn StatusBackend) -> Result<()> {
+        // Can we skip some or all of the work?
+
+        let cache_key = self.build_cache_key(status)?;
+
+        if let Some(key) = cache_key {
+            match self.consult_build_cache(key, status) {
+                // If we're writing Makefile rules, we need to go through the
+                // motions to find out what the outputs are.
+                BuildCacheStatus::UpToDate if self.makefile_output_path.is_none() => {
+                    status.note_highlighted(
+                        "Skipping ",
+                        "processing",
+                        ": nothing has changed since the last build",
+                    );
+                    return self.write_dependency_files(status);
+                }
+
+                BuildCacheStatus::UpToDate | BuildCacheStatus::Restored => {
+                    // Start the I/O tracking from scratch, since we're really
+                    // going to do the work.
+                    self.bs.events.clear();
+                    self.restored_build_state = true;
+                }
+
+                BuildCacheStatus::Missing => {}
+            }
+        }
+
         // Do we need to generate the format file?
 
         let generate_format = if self.output_format == OutputFormat::Format {
@@ -1499,6 +2257,16 @@ impl ProcessingSession {
         };
 
         if let Err(e) = result {
+            // If we started from saved intermediate files, they might be
+            // the problem, so don't use them next time.
+            if self.restored_build_state {
+                if let Some(ref cache) = self.build_cache {
+                    if let Err(e) = cache.clear() {
+                        tt_warning!(status, "couldn't clear the build cache"; e);
+                    }
+                }
+            }
+
             self.write_files(None, status, true)?;
             return Err(e);
         };
@@ -1569,11 +2337,334 @@ impl ProcessingSession {
             ctry!(writeln!(mf_dest, ""); "couldn't write to Makefile-rules file");
         }
 
+        // Other dependency outputs, maybe.
+
+        self.write_dependency_files(status)?;
+
+        // Save our state for next time, maybe.
+
+        if let Some(key) = cache_key {
+            if let Err(e) = self.save_build_cache(key, status) {
+                tt_warning!(status, "couldn't update the build cache: {}", e);
+            }
+        }
+
         // All done.
 
         Ok(())
     }
 
+    /// Write out the depfile and dependency manifest, if they were requested.
+    fn write_dependency_files(&self, status: &mut dyn StatusBackend) -> Result<()> {
+        if let Some(ref p) = self.depfile_output_path {
+            self.write_depfile(p, status)?;
+        }
+
+        if let Some(ref p) = self.dependency_manifest_path {
+            self.write_dependency_manifest(p, status)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compute the key identifying this session in the build cache. This
+    /// covers everything that could affect the outputs other than the files
+    /// that the engines read, which are tracked separately. Returns None if
+    /// the build cache shouldn't be used for this session.
+    fn build_cache_key(&mut self, status: &mut dyn StatusBackend) -> Result<Option<DigestData>> {
+        if self.build_cache.is_none()
+            || self.output_path.is_none()
+            || self.shell_escape_mode != ShellEscapeMode::Disabled
+            || self.html_precomputed_assets.is_some()
+        {
+            return Ok(None);
+        }
+
+        match self.pass {
+            PassSetting::Default | PassSetting::BibtexFirst => {}
+            PassSetting::Tex => return Ok(None),
+        }
+
+        // The spx2html engine writes its outputs directly to disk, so we
+        // wouldn't be able to verify them.
+        match self.output_format {
+            OutputFormat::Aux | OutputFormat::Xdv | OutputFormat::Pdf => {}
+            OutputFormat::Html | OutputFormat::Format => return Ok(None),
+        }
+
+        // Only the settings that can affect the outputs go into the key, each
+        // with a fixed encoding, so that the key doesn't change along with
+        // unrelated settings or `Debug` output. Shell escape and HTML output
+        // aren't listed, since they disable the build cache altogether.
+        let mut key = CacheKeyWriter(digest::create());
+        key.str(env!("CARGO_PKG_VERSION"));
+        key.u64(crate::FORMAT_SERIAL.into());
+        key.str(&self.bundle_digest.to_string());
+        key.str(&self.format_name);
+        key.str(&self.primary_input_tex_path);
+        key.u8(match self.output_format {
+            OutputFormat::Aux => 0,
+            OutputFormat::Html => 1,
+            OutputFormat::Xdv => 2,
+            OutputFormat::Pdf => 3,
+            OutputFormat::Format => 4,
+        });
+        key.u8(match self.pass {
+            PassSetting::Default => 0,
+            PassSetting::Tex => 1,
+            PassSetting::BibtexFirst => 2,
+        });
+        key.option(self.tex_rerun_specification, |k, n| k.u64(n as u64));
+        key.option(self.output_path.as_deref(), CacheKeyWriter::path);
+        key.bool(self.keep_intermediates);
+        key.bool(self.keep_logs);
+        key.bool(self.synctex_enabled);
+        key.option(self.synctex_path_base.as_deref(), CacheKeyWriter::path);
+
+        key.bool(self.unstables.continue_on_errors);
+        key.option(self.unstables.paper_size.as_deref(), CacheKeyWriter::str);
+        key.option(self.unstables.min_crossrefs, |k, n| k.u64(n.into()));
+        key.u64(self.unstables.extra_search_paths.len() as u64);
+
+        for p in &self.unstables.extra_search_paths {
+            key.path(p);
+        }
+
+        key.bool(self.unstables.deterministic_mode);
+        key.bool(self.security.allow_extra_search_paths());
+
+        key.u8(match self.pdf_conformance {
+            PdfConformance::None => 0,
+            PdfConformance::PdfA2b => 1,
+            PdfConformance::PdfX4 => 2,
+        });
+        key.option(self.output_intent_profile.as_deref(), CacheKeyWriter::str);
+        key.option(self.output_condition.as_deref(), CacheKeyWriter::str);
+
+        // Only a digest of the passwords goes into the key.
+        key.option(self.pdf_encryption.as_ref(), |k, enc| {
+            let p = &enc.permissions;
+            let mut pw = digest::create();
+            pw.update(enc.owner_password.as_bytes());
+            pw.update([0]);
+            pw.update(enc.user_password.as_bytes());
+            k.str(&DigestData::from(pw).to_string());
+            k.u64(enc.key_bits.into());
+
+            for flag in [
+                p.print,
+                p.modify,
+                p.copy,
+                p.annotate,
+                p.fill_forms,
+                p.accessibility,
+                p.assemble,
+                p.print_high_quality,
+            ] {
+                k.bool(flag);
+            }
+        });
+
+        // Unless we're in deterministic mode, the build date is just the
+        // current time, and we don't want that to invalidate the cache.
+        if self.unstables.deterministic_mode {
+            let d = self
+                .build_date
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            key.u64(d.as_secs());
+            key.u64(d.subsec_nanos().into());
+        }
+
+        let mut dc = key.0;
+
+        let mut ih = match self.bs.primary_input.input_open_primary(status) {
+            OpenResult::Ok(ih) => ih,
+            OpenResult::NotAvailable => return Ok(None),
+            OpenResult::Err(e) => return Err(e.into()),
+        };
+
+        let mut buf = Vec::new();
+        ih.read_to_end(&mut buf)?;
+        dc.update(&buf);
+
+        Ok(Some(DigestData::from(dc)))
+    }
+
+    /// Check the build cache, loading any saved intermediate files into the
+    /// memory layer if they're applicable to this session. Problems with the
+    /// cache are never fatal.
+    fn consult_build_cache(
+        &mut self,
+        key: DigestData,
+        status: &mut dyn StatusBackend,
+    ) -> BuildCacheStatus {
+        match self.try_consult_build_cache(key, status) {
+            Ok(s) => s,
+            Err(e) => {
+                tt_warning!(status, "couldn't use the build cache: {}", e);
+                self.bs.mem.files.borrow_mut().clear();
+                self.bs.events.clear();
+                BuildCacheStatus::Missing
+            }
+        }
+    }
+
+    fn try_consult_build_cache(
+        &mut self,
+        key: DigestData,
+        status: &mut dyn StatusBackend,
+    ) -> Result<BuildCacheStatus> {
+        let cache = self.build_cache.clone().unwrap();
+
+        let cached = match ctry!(cache.load(); "couldn't read the build cache") {
+            Some(c) if c.key == Some(key) => c,
+            _ => return Ok(BuildCacheStatus::Missing),
+        };
+
+        for (name, digest) in &cached.state {
+            let data = ctry!(cache.load_state(digest); "couldn't read saved state for `{}`", name);
+            self.bs.mem.create_entry(name, data);
+        }
+
+        // Now see if everything else is the same as before. As we go, we
+        // fill in the I/O events table as if we had done the processing, so
+        // that the dependency information can be reproduced.
+
+        let mut events = HashMap::new();
+
+        for (name, digest) in &cached.inputs {
+            let (mut ih, abspath) = match self.bs.input_open_local(name, status) {
+                OpenResult::Ok(r) => r,
+                _ => return Ok(BuildCacheStatus::Restored),
+            };
+
+            if digest_input(&mut ih)? != *digest {
+                return Ok(BuildCacheStatus::Restored);
+            }
+
+            let mut summ = FileSummary::new(AccessPattern::Read, InputOrigin::Filesystem);
+            summ.read_digest = Some(*digest);
+            summ.abspath = abspath;
+            events.insert(name.clone(), summ);
+        }
+
+        for name in cached.absent.iter().chain(&cached.bundle) {
+            if let OpenResult::Ok(_) = self.bs.input_open_local(name, status) {
+                return Ok(BuildCacheStatus::Restored);
+            }
+        }
+
+        for name in &cached.bundle {
+            let mut summ = FileSummary::new(AccessPattern::Read, InputOrigin::Other);
+            summ.from_bundle = true;
+            events.insert(name.clone(), summ);
+        }
+
+        // The check in build_cache_key ensures that this is never None.
+        let root = self.output_path.as_ref().unwrap();
+
+        for (name, digest) in &cached.outputs {
+            let mut dc = digest::create();
+
+            match std::fs::read(root.join(name)) {
+                Ok(data) => dc.update(&data),
+                Err(_) => return Ok(BuildCacheStatus::Restored),
+            }
+
+            if DigestData::from(dc) != *digest {
+                return Ok(BuildCacheStatus::Restored);
+            }
+
+            let mut summ = FileSummary::new(AccessPattern::Written, InputOrigin::NotInput);
+            summ.write_digest = Some(*digest);
+            summ.got_written_to_disk = true;
+            events.insert(name.clone(), summ);
+        }
+
+        self.bs.events = events;
+        Ok(BuildCacheStatus::UpToDate)
+    }
+
+    /// Save the state of this session to the build cache.
+    fn save_build_cache(&mut self, key: DigestData, status: &mut dyn StatusBackend) -> Result<()> {
+        let mut cached = CachedSession {
+            key: Some(key),
+            ..Default::default()
+        };
+
+        let mut state_data = Vec::new();
+        let stdout_key = self.bs.mem.stdout_key().to_owned();
+        let mut names: Vec<_> = self.bs.events.keys().cloned().collect();
+        names.sort();
+
+        if names.iter().any(|n| n.contains('\n')) {
+            return Err(errmsg!(
+                "can't cache the names of files with newlines in them"
+            ));
+        }
+
+        for name in names {
+            if name == stdout_key {
+                continue;
+            }
+
+            let info = &self.bs.events[&name];
+
+            if info.got_written_to_disk {
+                let files = self.bs.mem.files.borrow();
+                let normalized = crate::io::normalize_tex_path(&name);
+
+                if let Some(file) = files.get(&*normalized) {
+                    let mut dc = digest::create();
+                    dc.update(&file.data);
+                    cached.outputs.push((name.clone(), DigestData::from(dc)));
+                }
+            }
+
+            match info.access_pattern {
+                AccessPattern::Read => {}
+
+                AccessPattern::ReadThenWritten => {
+                    let files = self.bs.mem.files.borrow();
+                    let normalized = crate::io::normalize_tex_path(&name);
+
+                    if let Some(file) = files.get(&*normalized) {
+                        let mut dc = digest::create();
+                        dc.update(&file.data);
+                        cached.state.push((name.clone(), DigestData::from(dc)));
+                        state_data.push(file.data.clone());
+                    }
+
+                    continue;
+                }
+
+                AccessPattern::Written | AccessPattern::WrittenThenRead => continue,
+            }
+
+            if info.from_bundle {
+                cached.bundle.push(name);
+            } else if info.input_origin == InputOrigin::Filesystem {
+                let mut ih = match self.bs.input_open_local(&name, status) {
+                    OpenResult::Ok((ih, _)) => ih,
+                    OpenResult::NotAvailable => {
+                        return Err(errmsg!("input `{}` disappeared during processing", name))
+                    }
+                    OpenResult::Err(e) => return Err(e.into()),
+                };
+                let digest = digest_input(&mut ih)?;
+                cached.inputs.push((name, digest));
+            } else if info.input_origin == InputOrigin::NotInput {
+                cached.absent.push(name);
+            }
+        }
+
+        let state_refs: Vec<&[u8]> = state_data.iter().map(|d| &d[..]).collect();
+        let cache = self.build_cache.as_ref().unwrap();
+        ctry!(cache.save(&cached, &state_refs); "couldn't write the build cache");
+        Ok(())
+    }
+
     fn write_files(
         &mut self,
         mut mf_dest_maybe: Option<&mut File>,
@@ -1597,7 +2688,13 @@ impl ProcessingSession {
             }
 
             let sname = name;
-            let summ = self.bs.events.get_mut(name).unwrap();
+
+            // Intermediate files loaded from the build cache but never touched
+            // by the engines are stale, so there's no point in writing them.
+            let summ = match self.bs.events.get_mut(name) {
+                Some(s) => s,
+                None => continue,
+            };
 
             if !only_logs && (self.output_format == OutputFormat::Aux) {
                 // In this mode we're only writing the .aux file. I initially
@@ -1685,11 +2782,28 @@ impl ProcessingSession {
             let maybe_biber = self.check_biber_requirement(status)?;
 
             if let Some(biber) = maybe_biber {
-                self.bs.external_tool_pass(&biber, status)?;
+                let start = Instant::now();
+                let result = self.bs.external_tool_pass(&biber, status);
+                self.record_pass(
+                    PassKind::ExternalTool(biber.argv[0].clone()),
+                    None,
+                    start,
+                    None,
+                    result.is_ok(),
+                );
+                result?;
                 Some(RerunReason::Biber)
             } else if self.is_bibtex_needed() {
                 self.bibtex_pass(status)?;
-                Some(RerunReason::Bibtex)
+
+                // If we started from a saved `.bbl` file, TeX has already
+                // read it, so we only need to rerun if BibTeX changed it (or
+                // anything else changed).
+                if self.restored_build_state {
+                    self.is_rerun_needed(status)
+                } else {
+                    Some(RerunReason::Bibtex)
+                }
             } else {
                 self.is_rerun_needed(status)
             }
@@ -1703,13 +2817,11 @@ impl ProcessingSession {
         };
 
         for i in 0..pass_count {
-            let rerun_explanation = if reruns_fixed {
-                "I was told to".to_owned()
+            let rerun_reason = if reruns_fixed {
+                RerunReason::Requested
             } else {
                 match rerun_result {
-                    Some(RerunReason::Biber) => "biber was run".to_owned(),
-                    Some(RerunReason::Bibtex) => "bibtex was run".to_owned(),
-                    Some(RerunReason::FileChange(ref s)) => format!("\"{s}\" changed"),
+                    Some(ref r) => r.clone(),
                     None => break,
                 }
             };
@@ -1724,7 +2836,7 @@ impl ProcessingSession {
                 summ.read_digest = None;
             }
 
-            warnings = self.tex_pass(Some(&rerun_explanation), status)?;
+            warnings = self.tex_pass(Some(rerun_reason), status)?;
 
             if !reruns_fixed {
                 rerun_result = self.is_rerun_needed(status);
@@ -1785,22 +2897,38 @@ impl ProcessingSession {
             ))
             .into()
         });
-        let stem = r?;
+        let stem = r?.to_owned();
 
+        let start = Instant::now();
         let result = {
             self.bs
                 .enter_format_mode(&format!("tectonic-format-{stem}.tex"));
-            let mut launcher =
-                CoreBridgeLauncher::new_with_security(&mut self.bs, status, self.security.clone());
-            let r = TexEngine::default()
-                .halt_on_error_mode(true)
-                .initex_mode(true)
-                .shell_escape(self.shell_escape_mode != ShellEscapeMode::Disabled)
-                .process(&mut launcher, "UNUSED.fmt", "texput");
+            let launch = EngineLaunch::new(
+                EngineJob::Tex {
+                    format: "UNUSED.fmt".to_owned(),
+                    input: "texput".to_owned(),
+                    halt_on_error: true,
+                    initex: true,
+                    synctex: false,
+                    semantic_pagination: false,
+                    shell_escape: self.shell_escape_mode != ShellEscapeMode::Disabled,
+                    build_date: SystemTime::UNIX_EPOCH,
+                },
+                self.security.clone(),
+            );
+            let r = launch.run(&mut self.bs, status, self.engine_worker.as_ref());
             self.bs.leave_format_mode();
             r
         };
 
+        self.record_pass(
+            PassKind::Format,
+            None,
+            start,
+            result.as_ref().ok().copied(),
+            result.is_ok(),
+        );
+
         match result {
             Ok(TexOutcome::Spotless) => {}
             Ok(TexOutcome::Warnings) => {
@@ -1831,7 +2959,7 @@ impl ProcessingSession {
             }
 
             // Note that we intentionally pass 'stem', not 'name'.
-            ctry!(self.bs.format_cache.write_format(stem, &file.data, status); "cannot write format file {}", sname);
+            ctry!(self.bs.format_cache.write_format(&stem, &file.data, status); "cannot write format file {}", sname);
         }
 
         // All done. Clear the memory layer since this was a special preparatory step.
@@ -1843,45 +2971,55 @@ impl ProcessingSession {
     /// Run one pass of the TeX engine.
     fn tex_pass(
         &mut self,
-        rerun_explanation: Option<&str>,
+        rerun_reason: Option<RerunReason>,
         status: &mut dyn StatusBackend,
     ) -> Result<Option<&'static str>> {
+        let start = Instant::now();
         let result = {
-            if let Some(s) = rerun_explanation {
-                status.note_highlighted("Rerunning ", "TeX", &format!(" because {s} ..."));
+            if let Some(ref r) = rerun_reason {
+                status.note_highlighted("Rerunning ", "TeX", &format!(" because {r} ..."));
             } else {
                 status.note_highlighted("Running ", "TeX", " ...");
             }
 
-            let mut launcher =
-                CoreBridgeLauncher::new_with_security(&mut self.bs, status, self.security.clone());
+            let mut launch = EngineLaunch::new(
+                EngineJob::Tex {
+                    format: self.format_name.clone(),
+                    input: self.primary_input_tex_path.clone(),
+                    halt_on_error: !self.unstables.continue_on_errors,
+                    initex: self.output_format == OutputFormat::Format,
+                    synctex: self.synctex_enabled,
+                    semantic_pagination: self.output_format == OutputFormat::Html,
+                    shell_escape: self.shell_escape_mode != ShellEscapeMode::Disabled,
+                    build_date: self.build_date,
+                },
+                self.security.clone(),
+            );
 
             // In deterministic mode, we stub a few aspects of the environment.
             // They default to a "realistic" view, but we override them with static values:
             if self.unstables.deterministic_mode {
-                launcher.with_expose_absolute_paths(false);
-                launcher.with_mtime_override(Some(
+                launch.expose_absolute_paths = false;
+                launch.mtime_override = Some(
                     self.build_date
                         .duration_since(SystemTime::UNIX_EPOCH)
                         .map(|x| x.as_secs() as i64)
                         .expect("invalid build date in deterministic mode"),
-                ));
-            }
-
-            TexEngine::default()
-                .halt_on_error_mode(!self.unstables.continue_on_errors)
-                .initex_mode(self.output_format == OutputFormat::Format)
-                .synctex(self.synctex_enabled)
-                .semantic_pagination(self.output_format == OutputFormat::Html)
-                .shell_escape(self.shell_escape_mode != ShellEscapeMode::Disabled)
-                .build_date(self.build_date)
-                .process(
-                    &mut launcher,
-                    &self.format_name,
-                    &self.primary_input_tex_path,
-                )
+                );
+            }
+
+            launch.relative_path_base = self.synctex_path_base.clone();
+            launch.run(&mut self.bs, status, self.engine_worker.as_ref())
         };
 
+        self.record_pass(
+            PassKind::Tex,
+            rerun_reason,
+            start,
+            result.as_ref().ok().copied(),
+            result.is_ok(),
+        );
+
         let warnings = match result {
             Ok(TexOutcome::Spotless) => None,
             Ok(TexOutcome::Warnings) =>
@@ -1911,14 +3049,27 @@ impl ProcessingSession {
         status: &mut dyn StatusBackend,
         aux_file: &String,
     ) -> Result<i32> {
+        let start = Instant::now();
         let result = {
             status.note_highlighted("Running ", "BibTeX", &format!(" on {aux_file} ..."));
-            let mut launcher =
-                CoreBridgeLauncher::new_with_security(&mut self.bs, status, self.security.clone());
-            let mut engine = BibtexEngine::new();
-            engine.process(&mut launcher, aux_file, &self.unstables)
+            let launch = EngineLaunch::new(
+                EngineJob::Bibtex {
+                    aux: aux_file.clone(),
+                    min_crossrefs: self.unstables.min_crossrefs,
+                },
+                self.security.clone(),
+            );
+            launch.run(&mut self.bs, status, self.engine_worker.as_ref())
         };
 
+        self.record_pass(
+            PassKind::Bibtex(aux_file.clone()),
+            None,
+            start,
+            result.as_ref().ok().copied(),
+            result.is_ok(),
+        );
+
         match result {
             Ok(TexOutcome::Spotless) => {}
             Ok(TexOutcome::Warnings) => {
@@ -1935,7 +3086,7 @@ impl ProcessingSession {
                 );
             }
             Err(e) => {
-                return Err(e.chain_err(|| ErrorKind::EngineError("BibTeX")));
+                return Err(e).chain_err(|| ErrorKind::EngineError("BibTeX"));
             }
         }
 
@@ -1945,9 +3096,10 @@ impl ProcessingSession {
     fn bibtex_pass(&mut self, status: &mut dyn StatusBackend) -> Result<i32> {
         let mut aux_files = vec![self.tex_aux_path.clone()];
 
-        // find other .aux files generated by tex_pass
+        // find other .aux files generated by tex_pass, ignoring any stale ones
+        // loaded from the build cache
         for f in self.bs.get_intermediate_file_names() {
-            if f.ends_with(".aux") && f != self.tex_aux_path {
+            if f.ends_with(".aux") && f != self.tex_aux_path && self.bs.events.contains_key(&f) {
                 aux_files.push(f);
             }
         }
@@ -1960,28 +3112,36 @@ impl ProcessingSession {
     }
 
     fn xdvipdfmx_pass(&mut self, status: &mut dyn StatusBackend) -> Result<i32> {
-        {
+        let start = Instant::now();
+        let result = {
             status.note_highlighted("Running ", "xdvipdfmx", " ...");
 
-            let mut launcher =
-                CoreBridgeLauncher::new_with_security(&mut self.bs, status, self.security.clone());
-            let mut engine = XdvipdfmxEngine::default();
-
-            engine.build_date(self.build_date);
-
-            if let Some(ref ps) = self.unstables.paper_size {
-                engine.paper_spec(ps.clone());
-            }
+            let launch = EngineLaunch::new(
+                EngineJob::Xdvipdfmx {
+                    xdv: self.tex_xdv_path.clone(),
+                    pdf: self.tex_pdf_path.clone(),
+                    build_date: self.build_date,
+                    paper_spec: self.unstables.paper_size.clone(),
+                    conformance: self.pdf_conformance,
+                    output_intent_profile: self.output_intent_profile.clone(),
+                    output_condition: self.output_condition.clone(),
+                    encryption: self.pdf_encryption.clone(),
+                },
+                self.security.clone(),
+            );
+            launch.run(&mut self.bs, status, self.engine_worker.as_ref())
+        };
 
-            engine.process(&mut launcher, &self.tex_xdv_path, &self.tex_pdf_path)?;
-        }
+        self.record_pass(PassKind::Xdvipdfmx, None, start, None, result.is_ok());
+        result?;
 
         self.bs.mem.files.borrow_mut().remove(&self.tex_xdv_path);
         Ok(0)
     }
 
     fn spx2html_pass(&mut self, status: &mut dyn StatusBackend) -> Result<i32> {
-        {
+        let start = Instant::now();
+        let result = {
             let mut engine = Spx2HtmlEngine::default();
 
             match (self.html_emit_files, self.output_path.as_ref()) {
@@ -2001,8 +3161,11 @@ impl ProcessingSession {
             }
 
             status.note_highlighted("Running ", "spx2html", " ...");
-            engine.process_to_filesystem(&mut self.bs, status, &self.tex_xdv_path)?;
-        }
+            engine.process_to_filesystem(&mut self.bs, status, &self.tex_xdv_path)
+        };
+
+        self.record_pass(PassKind::Spx2html, None, start, None, result.is_ok());
+        result?;
 
         self.bs.mem.files.borrow_mut().remove(&self.tex_xdv_path);
         Ok(0)
@@ -4,4 +4,7 @@
 
 //! Engine for invoking `xdvipdfmx`.
 
-pub use tectonic_engine_xdvipdfmx::XdvipdfmxEngine;
+pub use tectonic_engine_xdvipdfmx::{
+    ConformanceError, ConformanceViolation, PdfConformance, PdfEncryption, PdfPermissions,
+    ViolationKind, XdvipdfmxEngine,
+};
@@ -0,0 +1,253 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! Code for persisting the state of a processing session between runs.
+//!
+//! A build cache lives in a directory of its own, usually inside a document's
+//! build directory. It records a digest of the session configuration, the
+//! digests of the inputs that the session read, the outputs that it wrote, and
+//! the contents of the intermediate files (like `.aux`, `.toc`, and `.bbl`)
+//! that carry state from one TeX pass to the next. The driver uses this
+//! information to skip processing entirely if nothing has changed, or to seed
+//! the next session with the saved intermediate files otherwise.
+//!
+//! The manifest is a simple line-oriented text file, so that it can be used
+//! whether or not the `serialization` feature is enabled.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+use tectonic_errors::{anyhow::bail, Result};
+
+use crate::digest::DigestData;
+
+/// The first line of every manifest. Bump the version number whenever the
+/// format or meaning of the cache changes.
+const MANIFEST_HEADER: &str = "tectonic-build-cache 1";
+
+/// The name of the manifest file within the cache directory.
+const MANIFEST_NAME: &str = "manifest";
+
+/// The name of the subdirectory holding saved intermediate files.
+const STATE_DIR_NAME: &str = "state";
+
+/// The information recorded about a completed processing session.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CachedSession {
+    /// A digest of the session configuration. A cache entry is only
+    /// applicable to a session with the same key.
+    pub key: Option<DigestData>,
+
+    /// Inputs read from the filesystem, with the digests of their contents.
+    pub inputs: Vec<(String, DigestData)>,
+
+    /// Names that were looked up but not found.
+    pub absent: Vec<String>,
+
+    /// Names that were provided by the bundle. Their contents are pinned by
+    /// the bundle digest, which is part of the key, but the session becomes
+    /// stale if a local file starts shadowing one of them.
+    pub bundle: Vec<String>,
+
+    /// Files written to the output directory, with the digests of their
+    /// contents.
+    pub outputs: Vec<(String, DigestData)>,
+
+    /// Intermediate files that carry state between passes, with the digests
+    /// of their contents. The contents themselves are stored separately.
+    pub state: Vec<(String, DigestData)>,
+}
+
+/// A build cache rooted in a particular directory.
+#[derive(Clone, Debug)]
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    /// Create a new `BuildCache` rooted at *dir*. The directory is not
+    /// created until something is saved.
+    pub fn new(dir: PathBuf) -> BuildCache {
+        BuildCache { dir }
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join(MANIFEST_NAME)
+    }
+
+    fn state_path(&self, digest: &DigestData) -> PathBuf {
+        self.dir.join(STATE_DIR_NAME).join(digest.to_string())
+    }
+
+    /// Load the most recently saved session.
+    ///
+    /// Returns `Ok(None)` if nothing has been saved, or if the saved manifest
+    /// was written by an incompatible version of this code.
+    pub fn load(&self) -> Result<Option<CachedSession>> {
+        let text = match fs::read_to_string(self.manifest_path()) {
+            Ok(t) => t,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = text.lines();
+
+        if lines.next() != Some(MANIFEST_HEADER) {
+            return Ok(None);
+        }
+
+        let mut session = CachedSession::default();
+
+        for line in lines {
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+            match kind {
+                "key" => session.key = Some(rest.parse()?),
+                "input" => session.inputs.push(parse_digest_and_name(rest)?),
+                "absent" => session.absent.push(rest.to_owned()),
+                "bundle" => session.bundle.push(rest.to_owned()),
+                "output" => session.outputs.push(parse_digest_and_name(rest)?),
+                "state" => session.state.push(parse_digest_and_name(rest)?),
+                _ => bail!("unrecognized build cache manifest line `{}`", line),
+            }
+        }
+
+        Ok(Some(session))
+    }
+
+    /// Load the contents of a saved intermediate file.
+    pub fn load_state(&self, digest: &DigestData) -> Result<Vec<u8>> {
+        Ok(fs::read(self.state_path(digest))?)
+    }
+
+    /// Save a session, along with the contents of its intermediate files.
+    ///
+    /// The *state_data* must provide contents for every item in
+    /// `session.state`, in the same order. Saved intermediate files that are
+    /// no longer referenced are deleted.
+    pub fn save(&self, session: &CachedSession, state_data: &[&[u8]]) -> Result<()> {
+        let state_dir = self.dir.join(STATE_DIR_NAME);
+        fs::create_dir_all(&state_dir)?;
+
+        for ((_, digest), data) in session.state.iter().zip(state_data) {
+            let p = self.state_path(digest);
+
+            if !p.exists() {
+                let mut temp_dest = tempfile::Builder::new()
+                    .prefix("state_")
+                    .rand_bytes(6)
+                    .tempfile_in(&state_dir)?;
+                temp_dest.write_all(data)?;
+                temp_dest.persist(p)?;
+            }
+        }
+
+        let mut text = String::new();
+        text.push_str(MANIFEST_HEADER);
+        text.push('\n');
+
+        if let Some(ref key) = session.key {
+            text.push_str(&format!("key {key}\n"));
+        }
+
+        for (name, digest) in &session.inputs {
+            text.push_str(&format!("input {digest} {name}\n"));
+        }
+
+        for name in &session.absent {
+            text.push_str(&format!("absent {name}\n"));
+        }
+
+        for name in &session.bundle {
+            text.push_str(&format!("bundle {name}\n"));
+        }
+
+        for (name, digest) in &session.outputs {
+            text.push_str(&format!("output {digest} {name}\n"));
+        }
+
+        for (name, digest) in &session.state {
+            text.push_str(&format!("state {digest} {name}\n"));
+        }
+
+        let mut temp_dest = tempfile::Builder::new()
+            .prefix("manifest_")
+            .rand_bytes(6)
+            .tempfile_in(&self.dir)?;
+        temp_dest.write_all(text.as_bytes())?;
+        temp_dest.persist(self.manifest_path())?;
+
+        // Clean up state files that the new manifest doesn't reference. This
+        // is best-effort, since a stray file doesn't hurt anything.
+
+        if let Ok(entries) = fs::read_dir(&state_dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let referenced = session
+                    .state
+                    .iter()
+                    .any(|(_, d)| name.to_str() == Some(&d.to_string()));
+
+                if !referenced {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Discard the saved session, if any.
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_file(self.manifest_path()) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn parse_digest_and_name(text: &str) -> Result<(String, DigestData)> {
+    let (digest, name) = match text.split_once(' ') {
+        Some(t) => t,
+        None => bail!("malformed build cache manifest entry `{}`", text),
+    };
+
+    Ok((name.to_owned(), digest.parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::{self, Digest};
+
+    #[test]
+    fn roundtrip() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::new(tempdir.path().join("cache"));
+        assert_eq!(cache.load().unwrap(), None);
+
+        let aux = b"\\relax\n".as_slice();
+        let mut dc = digest::create();
+        dc.update(aux);
+        let aux_digest = DigestData::from(dc);
+
+        let session = CachedSession {
+            key: Some(DigestData::of_nothing()),
+            inputs: vec![("my file.tex".to_owned(), DigestData::zeros())],
+            absent: vec!["missing.sty".to_owned()],
+            bundle: vec!["article.cls".to_owned()],
+            outputs: vec![("my file.pdf".to_owned(), DigestData::zeros())],
+            state: vec![("my file.aux".to_owned(), aux_digest)],
+        };
+
+        cache.save(&session, &[aux]).unwrap();
+        assert_eq!(cache.load().unwrap(), Some(session.clone()));
+        assert_eq!(cache.load_state(&aux_digest).unwrap(), aux.to_vec());
+
+        cache.clear().unwrap();
+        assert_eq!(cache.load().unwrap(), None);
+    }
+}
@@ -4,8 +4,12 @@
 //! Code for locally caching compiled format files.
 
 use std::{
-    io::{BufReader, Write},
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    io::{BufReader, Cursor, Write},
     path::PathBuf,
+    rc::Rc,
 };
 use tectonic_errors::{anyhow::bail, Result};
 
@@ -21,6 +25,19 @@ use crate::{digest::DigestData, status::StatusBackend};
 pub struct FormatCache {
     bundle_digest: DigestData,
     formats_base: PathBuf,
+    memory: Option<FormatMemory>,
+}
+
+/// An in-memory copy of the format files used by a series of processing
+/// sessions.
+///
+/// Long-running processes can share one of these between their sessions so
+/// that each format file only needs to be read from disk once. Clones of a
+/// `FormatMemory` refer to the same underlying storage, and every reader
+/// opened from it shares the stored buffer rather than copying it.
+#[derive(Clone, Debug, Default)]
+pub struct FormatMemory {
+    files: Rc<RefCell<HashMap<PathBuf, Rc<[u8]>>>>,
 }
 
 impl FormatCache {
@@ -33,9 +50,17 @@ impl FormatCache {
         FormatCache {
             bundle_digest,
             formats_base,
+            memory: None,
         }
     }
 
+    /// Keep the contents of the format files in *memory*, and consult it
+    /// before going to disk.
+    pub fn with_memory(mut self, memory: FormatMemory) -> FormatCache {
+        self.memory = Some(memory);
+        self
+    }
+
     /// Get an on-disk path name for a given format file. This function simply
     /// produces a path that may or may not exist.
     #[allow(clippy::manual_split_once)] // requires Rust 1.52 (note that we don't actually define our MSRV)
@@ -72,6 +97,28 @@ impl IoProvider for FormatCache {
             Err(e) => return OpenResult::Err(e),
         };
 
+        if let Some(memory) = &self.memory {
+            let mut files = memory.files.borrow_mut();
+
+            if !files.contains_key(&path) {
+                match fs::read(&path) {
+                    Ok(data) => {
+                        files.insert(path.clone(), data.into());
+                    }
+                    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        return OpenResult::NotAvailable
+                    }
+                    Err(e) => return OpenResult::Err(e.into()),
+                }
+            }
+
+            return OpenResult::Ok(InputHandle::new_read_only(
+                name,
+                Cursor::new(Rc::clone(&files[&path])),
+                InputOrigin::Other,
+            ));
+        }
+
         let f = match super::try_open_file(path) {
             OpenResult::Ok(f) => f,
             OpenResult::NotAvailable => return OpenResult::NotAvailable,
@@ -97,7 +144,12 @@ impl IoProvider for FormatCache {
             .rand_bytes(6)
             .tempfile_in(&self.formats_base)?;
         temp_dest.write_all(data)?;
-        temp_dest.persist(final_path)?;
+        temp_dest.persist(&final_path)?;
+
+        if let Some(memory) = &self.memory {
+            memory.files.borrow_mut().insert(final_path, Rc::from(data));
+        }
+
         Ok(())
     }
 }
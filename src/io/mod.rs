@@ -5,6 +5,7 @@
 
 use tectonic_status_base::StatusBackend;
 
+pub mod build_cache;
 pub mod format_cache;
 pub mod memory;
 
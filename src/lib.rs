@@ -86,7 +86,9 @@ pub mod engines;
 pub mod errors;
 pub mod io;
 pub mod status;
+pub mod synctex;
 pub mod unstable_opts;
+pub mod worker;
 
 // Note: this module is intentionally *not* gated by #[cfg(test)] -- see its
 // docstring for details.
@@ -139,8 +141,9 @@ pub use tectonic_status_base::{tt_error, tt_note, tt_warning};
 /// they are not thread-safe. This crate uses a global mutex to serialize
 /// invocations of the engines. This means that if you call this function from
 /// multiple threads simultaneously, the bulk of the work will be done in
-/// serial. The aim is to lift this limitation one day, but it will require
-/// extensive work on the underlying C/C++ code.
+/// serial. To process documents in parallel, set up sessions with the
+/// [`driver`] module that run their engines in separate processes, as
+/// described in the [`worker`] module.
 pub fn latex_to_pdf<T: AsRef<str>>(latex: T) -> Result<Vec<u8>> {
     let mut status = status::NoopStatusBackend::default();
 
@@ -0,0 +1,184 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! Status backend that emits machine-readable JSON messages.
+//!
+//! Every message is written as a single JSON object on its own line, so that
+//! editors and other tools can consume Tectonic's output as it is generated.
+//! Each object has a `type` field identifying its structure:
+//!
+//! - `message`: a note, warning, or error, with `kind`, `message`, and
+//!   optionally `causes` (the error chain) and `segments` (for highlighted
+//!   notes).
+//! - `diagnostic`: a message from the TeX engine, with `kind`, `message`, and
+//!   optionally `file`, `line`, and `context`.
+//! - `error_log`: the contents of the engine log files that are dumped after a
+//!   failed run, as `log`.
+
+use serde::Serialize;
+use std::{
+    fmt::Arguments,
+    io::{self, Write},
+};
+use tectonic_errors::Error;
+
+use super::{ChatterLevel, Diagnostic, MessageKind, StatusBackend};
+
+/// A status backend that writes one JSON object per message.
+///
+/// All messages are written to standard output, unless [`Self::always_stderr`]
+/// is activated, in which case they are all written to standard error.
+pub struct JsonStatusBackend {
+    chatter: ChatterLevel,
+    always_stderr: bool,
+}
+
+/// The three pieces of a highlighted note.
+#[derive(Serialize)]
+struct Segments<'a> {
+    before: &'a str,
+    highlighted: &'a str,
+    after: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event<'a> {
+    Message {
+        kind: &'static str,
+        message: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        causes: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        segments: Option<Segments<'a>>,
+    },
+
+    Diagnostic {
+        kind: &'static str,
+        message: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<u32>,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        context: &'a [String],
+    },
+
+    ErrorLog {
+        log: String,
+    },
+}
+
+fn kind_name(kind: MessageKind) -> &'static str {
+    match kind {
+        MessageKind::Note => "note",
+        MessageKind::Warning => "warning",
+        MessageKind::Error => "error",
+    }
+}
+
+impl JsonStatusBackend {
+    /// Create a new backend with the specified chatter level.
+    pub fn new(chatter: ChatterLevel) -> Self {
+        JsonStatusBackend {
+            chatter,
+            always_stderr: false,
+        }
+    }
+
+    /// Set whether messages should be sent to stderr rather than stdout.
+    pub fn always_stderr(&mut self, setting: bool) -> &mut Self {
+        self.always_stderr = setting;
+        self
+    }
+
+    fn emit(&mut self, kind: MessageKind, event: &Event) {
+        if self.chatter.suppress_message(kind) {
+            return;
+        }
+
+        let mut text = serde_json::to_string(event).expect("failed to serialize JSON message");
+        text.push('\n');
+
+        if self.always_stderr {
+            io::stderr()
+                .write_all(text.as_bytes())
+                .expect("write to stderr failed");
+        } else {
+            io::stdout()
+                .write_all(text.as_bytes())
+                .expect("write to stdout failed");
+        }
+    }
+}
+
+impl StatusBackend for JsonStatusBackend {
+    fn report(&mut self, kind: MessageKind, args: Arguments, err: Option<&Error>) {
+        let causes = err
+            .map(|e| e.chain().map(|item| item.to_string()).collect())
+            .unwrap_or_default();
+
+        self.emit(
+            kind,
+            &Event::Message {
+                kind: kind_name(kind),
+                message: args.to_string(),
+                causes,
+                segments: None,
+            },
+        );
+    }
+
+    fn report_error(&mut self, err: &Error) {
+        let mut chain = err.chain().map(|item| item.to_string());
+        let message = chain.next().unwrap_or_default();
+
+        self.emit(
+            MessageKind::Error,
+            &Event::Message {
+                kind: kind_name(MessageKind::Error),
+                message,
+                causes: chain.collect(),
+                segments: None,
+            },
+        );
+    }
+
+    fn report_diagnostic(&mut self, diag: &Diagnostic) {
+        self.emit(
+            diag.kind,
+            &Event::Diagnostic {
+                kind: kind_name(diag.kind),
+                message: &diag.message,
+                file: diag.file.as_deref(),
+                line: diag.line,
+                context: &diag.context,
+            },
+        );
+    }
+
+    fn note_highlighted(&mut self, before: &str, highlighted: &str, after: &str) {
+        self.emit(
+            MessageKind::Note,
+            &Event::Message {
+                kind: kind_name(MessageKind::Note),
+                message: format!("{before}{highlighted}{after}"),
+                causes: Vec::new(),
+                segments: Some(Segments {
+                    before,
+                    highlighted,
+                    after,
+                }),
+            },
+        );
+    }
+
+    fn dump_error_logs(&mut self, output: &[u8]) {
+        self.emit(
+            MessageKind::Error,
+            &Event::ErrorLog {
+                log: String::from_utf8_lossy(output).into_owned(),
+            },
+        );
+    }
+}
@@ -0,0 +1,650 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! Reading SyncTeX data, to map between source lines and output positions.
+//!
+//! When SyncTeX is enabled, the TeX engine writes a `.synctex.gz` file next to
+//! its output. It records, page by page, the boxes and other elements that TeX
+//! laid out, each tagged with the input file and line that produced it. This
+//! module parses that file and uses it for the two kinds of lookups that
+//! editors and viewers need:
+//!
+//! - *forward search*, from a file and line to a region of a page, with
+//!   [`SyncTex::forward`];
+//! - *inverse search*, from a point on a page back to a file and line, with
+//!   [`SyncTex::inverse`].
+//!
+//! Positions on the page are measured in PostScript points (big points) from
+//! the top left corner of the page, as PDF viewers usually report them.
+
+use flate2::read::GzDecoder;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use tectonic_errors::{
+    anyhow::{bail, Context},
+    Result,
+};
+
+/// The number of TeX scaled points in a big point, at unit magnification.
+const SP_PER_BP: f64 = 65536.0 * 72.27 / 72.0;
+
+/// A region of an output page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageBox {
+    /// The page number, starting at 1.
+    pub page: u32,
+
+    /// The distance of the left edge from the left of the page, in points.
+    pub x: f64,
+
+    /// The distance of the top edge from the top of the page, in points.
+    pub y: f64,
+
+    /// The width of the region, in points.
+    pub width: f64,
+
+    /// The height of the region, in points.
+    pub height: f64,
+}
+
+/// A place in the source of a document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceLocation {
+    /// The path of the input file, as recorded by the engine and resolved
+    /// against the base directory if it is relative.
+    pub file: PathBuf,
+
+    /// The line number, starting at 1.
+    pub line: u32,
+}
+
+/// The kinds of records in SyncTeX data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum NodeKind {
+    VBox,
+    HBox,
+    VoidVBox,
+    VoidHBox,
+    Rule,
+    Kern,
+    Glue,
+    Math,
+    Current,
+}
+
+impl NodeKind {
+    /// Whether this kind of record describes a box with a size, as opposed
+    /// to a single point.
+    fn is_box(self) -> bool {
+        matches!(
+            self,
+            NodeKind::VBox
+                | NodeKind::HBox
+                | NodeKind::VoidVBox
+                | NodeKind::VoidHBox
+                | NodeKind::Rule
+        )
+    }
+}
+
+/// One record, in the engine's units.
+#[derive(Clone, Debug)]
+struct Node {
+    kind: NodeKind,
+    tag: u32,
+    line: u32,
+    h: i64,
+    v: i64,
+    width: i64,
+    height: i64,
+    depth: i64,
+
+    /// The index of the enclosing box on the same page, if any.
+    parent: Option<usize>,
+}
+
+impl Node {
+    /// The vertical extent of this record: its own for boxes, and that of
+    /// the enclosing box for points.
+    fn vertical_extent(&self, page: &Page) -> (i64, i64) {
+        if self.kind.is_box() {
+            (self.v - self.height, self.v + self.depth)
+        } else if let Some(p) = self.parent {
+            let parent = &page.nodes[p];
+            (parent.v - parent.height, parent.v + parent.depth)
+        } else {
+            (self.v, self.v)
+        }
+    }
+
+    fn contains(&self, h: i64, v: i64) -> bool {
+        self.kind.is_box()
+            && h >= self.h
+            && h <= self.h + self.width
+            && v >= self.v - self.height
+            && v <= self.v + self.depth
+    }
+
+    /// The distance from a point to this record's extent, in engine units.
+    fn distance(&self, page: &Page, h: i64, v: i64) -> f64 {
+        let (top, bottom) = self.vertical_extent(page);
+        let dh = if h < self.h {
+            self.h - h
+        } else if h > self.h + self.width {
+            h - self.h - self.width
+        } else {
+            0
+        };
+        let dv = if v < top {
+            top - v
+        } else if v > bottom {
+            v - bottom
+        } else {
+            0
+        };
+
+        ((dh * dh + dv * dv) as f64).sqrt()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Page {
+    number: u32,
+    nodes: Vec<Node>,
+}
+
+impl Page {
+    /// Pick the record closest to a point from some of this page's records.
+    fn nearest<'a>(
+        &'a self,
+        nodes: impl Iterator<Item = &'a Node>,
+        h: i64,
+        v: i64,
+    ) -> Option<&'a Node> {
+        nodes.min_by(|a, b| a.distance(self, h, v).total_cmp(&b.distance(self, h, v)))
+    }
+}
+
+/// Parsed SyncTeX data for one output document.
+#[derive(Clone, Debug)]
+pub struct SyncTex {
+    inputs: BTreeMap<u32, String>,
+    pages: Vec<Page>,
+    unit: f64,
+    magnification: f64,
+    base_dir: Option<PathBuf>,
+}
+
+impl SyncTex {
+    /// Read a SyncTeX file, which is decompressed if its name ends in `.gz`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("failed to open SyncTeX file `{}`", path.display()))?;
+
+        let reader: Box<dyn Read> = if path.extension().is_some_and(|e| e == "gz") {
+            Box::new(GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+
+        Self::parse(BufReader::new(reader))
+            .with_context(|| format!("failed to read SyncTeX file `{}`", path.display()))
+    }
+
+    /// Parse SyncTeX data from an uncompressed stream.
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self> {
+        let mut data = SyncTex {
+            inputs: BTreeMap::new(),
+            pages: Vec::new(),
+            unit: 1.0,
+            magnification: 1000.0,
+            base_dir: None,
+        };
+
+        let mut page: Option<Page> = None;
+        let mut open_boxes: Vec<usize> = Vec::new();
+        let mut seen_version = false;
+
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_num = n + 1;
+
+            if !seen_version {
+                if !line.starts_with("SyncTeX Version:") {
+                    bail!("not SyncTeX data (line {line_num})");
+                }
+
+                seen_version = true;
+                continue;
+            }
+
+            if let Some(rest) = line.strip_prefix("Input:") {
+                let Some((tag, name)) = rest.split_once(':') else {
+                    bail!("malformed input record at line {line_num}");
+                };
+                let tag = parse_num(tag, line_num)?;
+                data.inputs.insert(tag as u32, name.to_owned());
+                continue;
+            }
+
+            if let Some(rest) = line.strip_prefix("Unit:") {
+                data.unit = parse_num(rest, line_num)? as f64;
+                continue;
+            }
+
+            if let Some(rest) = line.strip_prefix("Magnification:") {
+                data.magnification = parse_num(rest, line_num)? as f64;
+                continue;
+            }
+
+            if line.starts_with("Postamble:") {
+                break;
+            }
+
+            let Some(first) = line.chars().next() else {
+                continue;
+            };
+            let rest = &line[first.len_utf8()..];
+
+            match first {
+                '{' => {
+                    page = Some(Page {
+                        number: parse_num(rest, line_num)? as u32,
+                        nodes: Vec::new(),
+                    });
+                    open_boxes.clear();
+                }
+
+                '}' => {
+                    if let Some(p) = page.take() {
+                        data.pages.push(p);
+                    }
+                }
+
+                ']' | ')' => {
+                    open_boxes.pop();
+                }
+
+                '[' | '(' | 'v' | 'h' | 'r' | 'k' | 'g' | '$' | 'x' => {
+                    let Some(ref mut page) = page else {
+                        // Records outside of a page belong to forms, which
+                        // we don't track.
+                        continue;
+                    };
+
+                    let kind = match first {
+                        '[' => NodeKind::VBox,
+                        '(' => NodeKind::HBox,
+                        'v' => NodeKind::VoidVBox,
+                        'h' => NodeKind::VoidHBox,
+                        'r' => NodeKind::Rule,
+                        'k' => NodeKind::Kern,
+                        'g' => NodeKind::Glue,
+                        '$' => NodeKind::Math,
+                        _ => NodeKind::Current,
+                    };
+
+                    let mut node = parse_node(kind, rest, line_num)?;
+                    node.parent = open_boxes.last().copied();
+                    page.nodes.push(node);
+
+                    if matches!(kind, NodeKind::VBox | NodeKind::HBox) {
+                        open_boxes.push(page.nodes.len() - 1);
+                    }
+                }
+
+                // Everything else -- the header, byte offsets, and form
+                // records -- isn't needed for lookups.
+                _ => {}
+            }
+        }
+
+        if !seen_version {
+            bail!("not SyncTeX data (empty file)");
+        }
+
+        Ok(data)
+    }
+
+    /// Set the directory against which relative input paths are resolved.
+    ///
+    /// Relative paths are recorded when a document is built with SyncTeX paths
+    /// relative to its source directory, so that is what this should usually
+    /// be set to.
+    pub fn base_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.base_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Get the paths of the input files that the data refers to.
+    pub fn input_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.inputs.values().map(|n| self.resolve(n))
+    }
+
+    /// Find the regions of the output that were produced by a line of a
+    /// source file.
+    ///
+    /// If nothing was produced by that exact line, the next line that produced
+    /// something is used instead, or failing that, the closest line before it.
+    /// There is one region for each page on which the line appears.
+    pub fn forward<P: AsRef<Path>>(&self, file: P, line: u32) -> Vec<PageBox> {
+        let tags = self.tags_for_file(file.as_ref());
+
+        let lines: Vec<u32> = self
+            .pages
+            .iter()
+            .flat_map(|p| p.nodes.iter())
+            .filter(|n| n.line > 0 && tags.contains(&n.tag))
+            .map(|n| n.line)
+            .collect();
+
+        let target = lines
+            .iter()
+            .copied()
+            .filter(|l| *l >= line)
+            .min()
+            .or_else(|| lines.iter().copied().max());
+
+        let Some(target) = target else {
+            return Vec::new();
+        };
+
+        let mut results: Vec<PageBox> = Vec::new();
+
+        for page in &self.pages {
+            let mut extent: Option<(i64, i64, i64, i64)> = None;
+
+            for node in page
+                .nodes
+                .iter()
+                .filter(|n| n.line == target && tags.contains(&n.tag))
+            {
+                let (top, bottom) = node.vertical_extent(page);
+                let left = node.h;
+                let right = node.h + node.width;
+
+                extent = Some(match extent {
+                    None => (left, top, right, bottom),
+                    Some((l, t, r, b)) => (l.min(left), t.min(top), r.max(right), b.max(bottom)),
+                });
+            }
+
+            if let Some((left, top, right, bottom)) = extent {
+                results.push(PageBox {
+                    page: page.number,
+                    x: self.sp_to_points(left),
+                    y: self.sp_to_points(top),
+                    width: self.sp_to_points(right - left),
+                    height: self.sp_to_points(bottom - top),
+                });
+            }
+        }
+
+        results
+    }
+
+    /// Find the source line that produced the output at a point on a page.
+    ///
+    /// The innermost box containing the point is found, and within it the
+    /// element nearest to the left of the point. If no box contains the point,
+    /// the nearest element on the page is used. Returns `None` if the page
+    /// doesn't exist or has no elements from known input files.
+    pub fn inverse(&self, page: u32, x: f64, y: f64) -> Option<SourceLocation> {
+        let page = self.pages.iter().find(|p| p.number == page)?;
+        let h = self.points_to_sp(x);
+        let v = self.points_to_sp(y);
+        let known = |n: &Node| n.line > 0 && self.inputs.contains_key(&n.tag);
+
+        // The innermost box is the smallest one, preferring horizontal boxes,
+        // which correspond to lines of text.
+        let container = page
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.contains(h, v))
+            .min_by_key(|(_, n)| (n.kind != NodeKind::HBox, n.width * (n.height + n.depth)))
+            .map(|(i, _)| i);
+
+        let node = container.and_then(|c| {
+            let children = page
+                .nodes
+                .iter()
+                .filter(|n| n.parent == Some(c) && known(n));
+
+            // Within a line, each element marks where the material from its
+            // source line ends, so the one to the left of the point is wanted.
+            let best = if page.nodes[c].kind == NodeKind::HBox {
+                children
+                    .clone()
+                    .filter(|n| n.h <= h)
+                    .max_by_key(|n| n.h)
+                    .or_else(|| children.min_by_key(|n| n.h))
+            } else {
+                page.nearest(children, h, v)
+            };
+
+            best.or_else(|| Some(&page.nodes[c]).filter(|n| known(n)))
+        });
+
+        let node = node.or_else(|| page.nearest(page.nodes.iter().filter(|n| known(n)), h, v))?;
+
+        Some(SourceLocation {
+            file: self.resolve(&self.inputs[&node.tag]),
+            line: node.line,
+        })
+    }
+
+    fn resolve(&self, name: &str) -> PathBuf {
+        let path = Path::new(name);
+
+        match self.base_dir {
+            Some(ref base) if path.is_relative() => base.join(path),
+            _ => path.to_owned(),
+        }
+    }
+
+    /// Get the tags of the inputs that match *file*. A relative *file* also
+    /// matches inputs whose paths end with it, so that a bare file name is
+    /// enough if it is unambiguous.
+    fn tags_for_file(&self, file: &Path) -> Vec<u32> {
+        let canonical = file.canonicalize().ok();
+
+        self.inputs
+            .iter()
+            .filter(|(_, name)| {
+                let resolved = self.resolve(name);
+
+                resolved == file
+                    || (file.is_relative() && resolved.ends_with(file))
+                    || (canonical.is_some() && resolved.canonicalize().ok() == canonical)
+            })
+            .map(|(tag, _)| *tag)
+            .collect()
+    }
+
+    fn scale(&self) -> f64 {
+        self.unit * self.magnification / 1000.0 / SP_PER_BP
+    }
+
+    fn sp_to_points(&self, value: i64) -> f64 {
+        value as f64 * self.scale()
+    }
+
+    fn points_to_sp(&self, value: f64) -> i64 {
+        (value / self.scale()).round() as i64
+    }
+}
+
+fn parse_num(text: &str, line_num: usize) -> Result<i64> {
+    match text.trim().parse() {
+        Ok(n) => Ok(n),
+        Err(_) => bail!("malformed number `{text}` at line {line_num}"),
+    }
+}
+
+/// Parse the body of a record, `tag,line[,column]:h,v[:W[,H,D]]`.
+fn parse_node(kind: NodeKind, text: &str, line_num: usize) -> Result<Node> {
+    let mut parts = text.split(':');
+
+    let (Some(link), Some(pos)) = (parts.next(), parts.next()) else {
+        bail!("malformed record at line {line_num}");
+    };
+
+    let mut link = link.split(',');
+    let tag = parse_num(link.next().unwrap_or_default(), line_num)?;
+    let line = parse_num(link.next().unwrap_or("0"), line_num)?;
+
+    let Some((h, v)) = pos.split_once(',') else {
+        bail!("malformed position at line {line_num}");
+    };
+
+    let mut node = Node {
+        kind,
+        tag: tag as u32,
+        line: line.max(0) as u32,
+        h: parse_num(h, line_num)?,
+        v: parse_num(v, line_num)?,
+        width: 0,
+        height: 0,
+        depth: 0,
+        parent: None,
+    };
+
+    if let Some(size) = parts.next() {
+        let mut size = size.split(',');
+
+        if let Some(w) = size.next() {
+            node.width = parse_num(w, line_num)?;
+        }
+
+        if let (Some(ht), Some(dp)) = (size.next(), size.next()) {
+            node.height = parse_num(ht, line_num)?;
+            node.depth = parse_num(dp, line_num)?;
+        }
+    }
+
+    // Kerns are recorded at the position where they end.
+    if kind == NodeKind::Kern {
+        node.h -= node.width;
+    }
+
+    // Boxes can have negative widths when they are set right to left.
+    if node.width < 0 {
+        node.h += node.width;
+        node.width = -node.width;
+    }
+
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One page with two lines of text from `src/index.tex`, the first of
+    // which has an interword glue and a kern. One inch is 4736286sp, and
+    // 10pt is 655360sp.
+    const DATA: &str = "SyncTeX Version:1
+Input:1:texput
+Input:2:src/index.tex
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+!103
+{1
+[1,3:4736286,44736286:30000000,40000000,0
+(2,5:4736286,5392000:20000000,655360,0
+g2,4:6000000,5392000
+k2,5:9000000,5392000:100000
+)
+(2,7:4736286,6400000:20000000,655360,0
+x2,7:4736286,6400000
+)
+]
+}1
+!200
+Postamble:
+Count:7
+!20
+Post scriptum:
+";
+
+    fn data() -> SyncTex {
+        let mut st = SyncTex::parse(DATA.as_bytes()).unwrap();
+        st.base_dir("/doc");
+        st
+    }
+
+    #[test]
+    fn inputs() {
+        let files: Vec<_> = data().input_files().collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("/doc/texput"),
+                PathBuf::from("/doc/src/index.tex")
+            ]
+        );
+    }
+
+    #[test]
+    fn forward() {
+        let st = data();
+
+        let boxes = st.forward("index.tex", 4);
+        assert_eq!(boxes.len(), 1);
+        let b = boxes[0];
+        assert_eq!(b.page, 1);
+
+        // The glue on line 4 is in the first line box, 10pt tall, whose
+        // baseline is at 5392000sp.
+        let scale = 72.0 / 72.27 / 65536.0;
+        assert!((b.x - 6000000.0 * scale).abs() < 1e-6);
+        assert!((b.y - (5392000.0 - 655360.0) * scale).abs() < 1e-6);
+        assert!((b.height - 10.0 * 72.0 / 72.27).abs() < 1e-6);
+
+        // Nothing comes from line 6, so line 7 is used.
+        let boxes = st.forward("/doc/src/index.tex", 6);
+        assert_eq!(boxes.len(), 1);
+        assert!((boxes[0].y - (6400000.0 - 655360.0) * scale).abs() < 1e-6);
+
+        assert!(st.forward("other.tex", 1).is_empty());
+    }
+
+    #[test]
+    fn inverse() {
+        let st = data();
+        let scale = 72.0 / 72.27 / 65536.0;
+
+        // To the right of the glue in the first line, but left of the kern.
+        let loc = st.inverse(1, 7000000.0 * scale, 5300000.0 * scale).unwrap();
+        assert_eq!(loc.file, PathBuf::from("/doc/src/index.tex"));
+        assert_eq!(loc.line, 4);
+
+        // In the second line.
+        let loc = st.inverse(1, 5000000.0 * scale, 6300000.0 * scale).unwrap();
+        assert_eq!(loc.line, 7);
+
+        // To the right of both lines: the nearest one wins.
+        let loc = st
+            .inverse(1, 30000000.0 * scale, 6400000.0 * scale)
+            .unwrap();
+        assert_eq!(loc.line, 7);
+
+        assert_eq!(st.inverse(2, 0.0, 0.0), None);
+    }
+
+    #[test]
+    fn not_synctex() {
+        assert!(SyncTex::parse("hello\n".as_bytes()).is_err());
+        assert!(SyncTex::parse("".as_bytes()).is_err());
+    }
+}
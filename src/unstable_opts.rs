@@ -20,7 +20,8 @@ const HELPMSG: &str = r#"Available unstable options:
                                     crossrefs" [default: 2]
     -Z paper-size=<spec>        Change the initial paper size [default: letter]
     -Z search-path=<path>       Also look in <path> for files (unless --untrusted has been specified),
-                                    like TEXINPUTS. Can be specified multiple times.
+                                    like TEXINPUTS. A path ending in // is searched recursively.
+                                    Can be specified multiple times.
     -Z shell-escape             Enable \write18 (unless --untrusted has been specified)
     -Z shell-escape-cwd=<path>  Working directory to use for \write18. Use $(pwd) for same behaviour as
                                     most other engines (e.g. for relative paths in \inputminted).
@@ -137,6 +138,8 @@ pub struct UnstableOptions {
     /// date, but this is already covered by [`crate::driver::ProcessingSessionBuilder::build_date_from_env`],
     /// which accepts a `deterministic` flag. Additionally, deterministic mode
     /// spoofs file modification times and hides absolute paths from the engine.
+    /// SyncTeX data then only work if paths are recorded relative to a base
+    /// directory; see [`crate::driver::ProcessingSessionBuilder::synctex_path_base`].
     ///
     /// There's a few ways to break determinism (shell escape, reading from
     /// `/dev/urandom`), but anything else (especially behaviour in TeXLive
@@ -0,0 +1,1653 @@
+// Copyright 2026 the Tectonic Project
+// Licensed under the MIT License.
+
+//! Running the engines in child worker processes.
+//!
+//! The C/C++ engines keep their state in global variables, so only one of
+//! them can run in a process at a time: every engine invocation holds a
+//! global lock while it runs (see
+//! [`CoreBridgeLauncher::with_global_lock`]). A program that wants to process
+//! several documents at once can instead have each
+//! [`ProcessingSession`](crate::driver::ProcessingSession) run its engines in
+//! child processes, by giving it an [`EngineWorker`] with
+//! [`ProcessingSessionBuilder::engine_worker`](crate::driver::ProcessingSessionBuilder::engine_worker).
+//!
+//! For each engine invocation, the session starts the worker program and
+//! sends it a description of the job over its standard input. The worker runs
+//! the engine and sends every I/O request, I/O event and status message back
+//! over its standard output, where the session answers them using its own I/O
+//! stack and status backend. All files are still opened and written by the
+//! session, so the results are the same as those of an in-process run. The
+//! session does not take the global engine lock, so sessions in different
+//! threads can run at the same time.
+//!
+//! The worker program is usually the current executable, which must call
+//! [`run_worker_if_requested`] at the very start of its `main` function,
+//! before it does anything that might write to standard output. The
+//! `tectonic` executable does this, so it can also be used as a worker.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    io::{self, BufReader, BufWriter, Cursor, Read, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::{self, ChildStdin, ChildStdout, Command, Stdio},
+    rc::Rc,
+    result::Result as StdResult,
+    time::{Duration, SystemTime},
+};
+use tectonic_bridge_core::{
+    CoreBridgeLauncher, DriverHooks, SecuritySettings, SecurityStance, SystemRequestError,
+};
+use tectonic_errors::{
+    anyhow::{anyhow, bail, Context},
+    Error, Result,
+};
+use tectonic_io_base::{
+    digest::DigestData, InputFeatures, InputHandle, InputOrigin, IoProvider, OpenResult,
+    OutputHandle,
+};
+use tectonic_status_base::{tt_warning, Diagnostic, MessageKind, StatusBackend};
+
+use crate::{
+    engines::xdvipdfmx::{
+        ConformanceError, ConformanceViolation, PdfConformance, PdfEncryption, PdfPermissions,
+        ViolationKind, XdvipdfmxEngine,
+    },
+    errors::{self, SyncError},
+    unstable_opts::UnstableOptions,
+    BibtexEngine, TexEngine, TexOutcome,
+};
+
+/// The environment variable that tells a program that it was started as an
+/// engine worker.
+const WORKER_ENV_VAR: &str = "TECTONIC_ENGINE_WORKER";
+
+/// How much output data a worker buffers before sending it to the session.
+const OUTPUT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How much input data a worker asks the session for at a time.
+const INPUT_CHUNK_SIZE: usize = 64 * 1024;
+
+// Messages from the worker to the session. The session answers the requests
+// that open, read or seek files, or run commands; it doesn't answer the
+// others.
+
+const MSG_OUTPUT_OPEN_NAME: u8 = 1;
+const MSG_OUTPUT_OPEN_STDOUT: u8 = 2;
+const MSG_INPUT_OPEN_NAME: u8 = 3;
+const MSG_INPUT_OPEN_PRIMARY: u8 = 4;
+const MSG_INPUT_OPEN_FORMAT: u8 = 5;
+const MSG_SHELL_ESCAPE: u8 = 6;
+const MSG_OUTPUT_WRITE: u8 = 7;
+const MSG_OUTPUT_CLOSE: u8 = 8;
+const MSG_EVENT_OUTPUT_CLOSED: u8 = 9;
+const MSG_EVENT_INPUT_CLOSED: u8 = 10;
+const MSG_REPORT: u8 = 11;
+const MSG_DIAGNOSTIC: u8 = 12;
+const MSG_DUMP_ERROR_LOGS: u8 = 13;
+const MSG_DONE: u8 = 14;
+const MSG_INPUT_READ: u8 = 15;
+const MSG_INPUT_SEEK: u8 = 16;
+const MSG_INPUT_SIZE: u8 = 17;
+const MSG_INPUT_CLOSE: u8 = 18;
+
+// The status codes that start the session's answers to requests.
+
+const REPLY_OK: u8 = 0;
+const REPLY_NOT_AVAILABLE: u8 = 1;
+const REPLY_ERR: u8 = 2;
+
+// The kinds of the root causes of errors. Callers can downcast errors of
+// these kinds, so they're rebuilt on the other side of the connection rather
+// than being reduced to their messages.
+
+const ERROR_OTHER: u8 = 0;
+const ERROR_CONFORMANCE: u8 = 1;
+
+/// A program that can be started to run engines outside of this process.
+///
+/// The program must call [`run_worker_if_requested`] at the start of its
+/// `main` function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EngineWorker {
+    program: PathBuf,
+    args: Vec<OsString>,
+}
+
+impl EngineWorker {
+    /// Use the program at *program* as the worker.
+    pub fn new<P: AsRef<Path>>(program: P) -> Self {
+        EngineWorker {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Use the currently running executable as the worker.
+    pub fn current_exe() -> Result<Self> {
+        let exe = env::current_exe().context("failed to locate the current executable")?;
+        Ok(Self::new(exe))
+    }
+
+    /// Pass an extra command-line argument to the worker program.
+    ///
+    /// [`run_worker_if_requested`] ignores its arguments, so these are only
+    /// useful to programs that need to do some setup before calling it.
+    pub fn arg<S: Into<OsString>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Run an engine in a new worker process, serving its requests with
+    /// *hooks* and *status* until it finishes.
+    fn run(
+        &self,
+        launch: &EngineLaunch,
+        hooks: &mut dyn DriverHooks,
+        status: &mut dyn StatusBackend,
+    ) -> Result<TexOutcome> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env(WORKER_ENV_VAR, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "failed to start the engine worker `{}`",
+                    self.program.display()
+                )
+            })?;
+
+        let mut server = WorkerServer {
+            reader: WireReader(BufReader::new(child.stdout.take().unwrap())),
+            writer: WireWriter(BufWriter::new(child.stdin.take().unwrap())),
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+            next_id: 0,
+        };
+
+        let result = server.serve(launch, hooks, status);
+
+        // Close our ends of the pipes before waiting, so that a worker that
+        // is still running can't block on them.
+        drop(server);
+        let exit = child
+            .wait()
+            .context("failed to wait for the engine worker to exit")?;
+
+        match result {
+            Err(e) if !exit.success() => {
+                Err(e.context(format!("the engine worker failed ({exit})")))
+            }
+            r => r,
+        }
+    }
+}
+
+/// Act as an engine worker, if this process was started as one.
+///
+/// If a processing session started this process to run an engine, this
+/// function runs it, talking to the session over standard input and output,
+/// and then exits the process. Otherwise, it returns immediately.
+pub fn run_worker_if_requested() {
+    if env::var_os(WORKER_ENV_VAR).is_none() {
+        return;
+    }
+
+    let code = match worker_main() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: the Tectonic engine worker failed: {e:#}");
+            1
+        }
+    };
+
+    process::exit(code);
+}
+
+/// The work of an engine invocation.
+#[derive(Clone, Debug)]
+pub(crate) enum EngineJob {
+    /// Run the TeX engine. See [`TexEngine`] for the settings.
+    Tex {
+        format: String,
+        input: String,
+        halt_on_error: bool,
+        initex: bool,
+        synctex: bool,
+        semantic_pagination: bool,
+        shell_escape: bool,
+        build_date: SystemTime,
+    },
+
+    /// Run BibTeX on an `.aux` file.
+    Bibtex {
+        aux: String,
+        min_crossrefs: Option<u32>,
+    },
+
+    /// Run `xdvipdfmx`. See [`XdvipdfmxEngine`] for the settings.
+    Xdvipdfmx {
+        xdv: String,
+        pdf: String,
+        build_date: SystemTime,
+        paper_spec: Option<String>,
+        conformance: PdfConformance,
+        output_intent_profile: Option<String>,
+        output_condition: Option<String>,
+        encryption: Option<PdfEncryption>,
+    },
+}
+
+/// An engine invocation, with the environment that it runs in.
+///
+/// The fields other than `job` correspond to the settings of
+/// [`CoreBridgeLauncher`].
+#[derive(Clone, Debug)]
+pub(crate) struct EngineLaunch {
+    pub job: EngineJob,
+    pub security: SecuritySettings,
+    pub expose_absolute_paths: bool,
+    pub relative_path_base: Option<PathBuf>,
+    pub mtime_override: Option<i64>,
+}
+
+impl EngineLaunch {
+    /// Set up an invocation with the launcher's default environment.
+    pub fn new(job: EngineJob, security: SecuritySettings) -> Self {
+        EngineLaunch {
+            job,
+            security,
+            expose_absolute_paths: true,
+            relative_path_base: None,
+            mtime_override: None,
+        }
+    }
+
+    /// Run the engine, in a process started from *worker* if it is given,
+    /// and in this process otherwise.
+    ///
+    /// The `xdvipdfmx` engine doesn't have outcomes of its own, and reports
+    /// [`TexOutcome::Spotless`] if it succeeds.
+    pub fn run(
+        &self,
+        hooks: &mut dyn DriverHooks,
+        status: &mut dyn StatusBackend,
+        worker: Option<&EngineWorker>,
+    ) -> Result<TexOutcome> {
+        match worker {
+            Some(w) => w.run(self, hooks, status),
+            None => self.run_here(hooks, status),
+        }
+    }
+
+    fn run_here(
+        &self,
+        hooks: &mut dyn DriverHooks,
+        status: &mut dyn StatusBackend,
+    ) -> Result<TexOutcome> {
+        let mut launcher =
+            CoreBridgeLauncher::new_with_security(hooks, status, self.security.clone());
+        launcher.with_expose_absolute_paths(self.expose_absolute_paths);
+        launcher.with_mtime_override(self.mtime_override);
+
+        if let Some(ref base) = self.relative_path_base {
+            launcher.with_relative_paths(base.clone());
+        }
+
+        match self.job {
+            EngineJob::Tex {
+                ref format,
+                ref input,
+                halt_on_error,
+                initex,
+                synctex,
+                semantic_pagination,
+                shell_escape,
+                build_date,
+            } => TexEngine::default()
+                .halt_on_error_mode(halt_on_error)
+                .initex_mode(initex)
+                .synctex(synctex)
+                .semantic_pagination(semantic_pagination)
+                .shell_escape(shell_escape)
+                .build_date(build_date)
+                .process(&mut launcher, format, input),
+
+            EngineJob::Bibtex {
+                ref aux,
+                min_crossrefs,
+            } => {
+                let unstables = UnstableOptions {
+                    min_crossrefs,
+                    ..UnstableOptions::default()
+                };
+
+                // The wrapper's errors are of the old style, but its engine's
+                // are of the new one, so unwrap them when we can.
+                match BibtexEngine::new().process(&mut launcher, aux, &unstables) {
+                    Ok(outcome) => Ok(outcome),
+                    Err(errors::Error(errors::ErrorKind::NewStyle(e), _)) => Err(e),
+                    Err(e) => Err(SyncError::new(e).into()),
+                }
+            }
+
+            EngineJob::Xdvipdfmx {
+                ref xdv,
+                ref pdf,
+                build_date,
+                ref paper_spec,
+                conformance,
+                ref output_intent_profile,
+                ref output_condition,
+                ref encryption,
+            } => {
+                let mut engine = XdvipdfmxEngine::default();
+                engine.build_date(build_date).conformance(conformance);
+
+                if let Some(ps) = paper_spec {
+                    engine.paper_spec(ps.clone());
+                }
+
+                if let Some(p) = output_intent_profile {
+                    engine.output_intent_profile(p.clone());
+                }
+
+                if let Some(c) = output_condition {
+                    engine.output_condition(c.clone());
+                }
+
+                if let Some(e) = encryption {
+                    engine.encryption(e.clone());
+                }
+
+                engine.process(&mut launcher, xdv, pdf)?;
+                Ok(TexOutcome::Spotless)
+            }
+        }
+    }
+
+    fn write_to<W: Write>(&self, w: &mut WireWriter<W>) -> io::Result<()> {
+        w.bool(self.security.allow_shell_escape())?;
+        w.bool(self.expose_absolute_paths)?;
+        w.option(self.relative_path_base.as_deref(), WireWriter::path)?;
+        w.option(self.mtime_override, WireWriter::i64)?;
+
+        match self.job {
+            EngineJob::Tex {
+                ref format,
+                ref input,
+                halt_on_error,
+                initex,
+                synctex,
+                semantic_pagination,
+                shell_escape,
+                build_date,
+            } => {
+                w.u8(0)?;
+                w.str(format)?;
+                w.str(input)?;
+                w.bool(halt_on_error)?;
+                w.bool(initex)?;
+                w.bool(synctex)?;
+                w.bool(semantic_pagination)?;
+                w.bool(shell_escape)?;
+                w.time(build_date)
+            }
+
+            EngineJob::Bibtex {
+                ref aux,
+                min_crossrefs,
+            } => {
+                w.u8(1)?;
+                w.str(aux)?;
+                w.option(min_crossrefs.map(u64::from), WireWriter::u64)
+            }
+
+            EngineJob::Xdvipdfmx {
+                ref xdv,
+                ref pdf,
+                build_date,
+                ref paper_spec,
+                conformance,
+                ref output_intent_profile,
+                ref output_condition,
+                ref encryption,
+            } => {
+                w.u8(2)?;
+                w.str(xdv)?;
+                w.str(pdf)?;
+                w.time(build_date)?;
+                w.option(paper_spec.as_deref(), WireWriter::str)?;
+                w.conformance(conformance)?;
+                w.option(output_intent_profile.as_deref(), WireWriter::str)?;
+                w.option(output_condition.as_deref(), WireWriter::str)?;
+                w.option(encryption.as_ref(), |w, e| {
+                    let p = &e.permissions;
+                    w.str(&e.owner_password)?;
+                    w.str(&e.user_password)?;
+                    w.u64(e.key_bits.into())?;
+
+                    for flag in [
+                        p.print,
+                        p.modify,
+                        p.copy,
+                        p.annotate,
+                        p.fill_forms,
+                        p.accessibility,
+                        p.assemble,
+                        p.print_high_quality,
+                    ] {
+                        w.bool(flag)?;
+                    }
+
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    fn read_from<R: Read>(r: &mut WireReader<R>) -> io::Result<Self> {
+        let security = SecuritySettings::new(if r.bool()? {
+            SecurityStance::MaybeAllowInsecures
+        } else {
+            SecurityStance::DisableInsecures
+        });
+        let expose_absolute_paths = r.bool()?;
+        let relative_path_base = r.option(WireReader::path)?;
+        let mtime_override = r.option(WireReader::i64)?;
+
+        let job = match r.u8()? {
+            0 => EngineJob::Tex {
+                format: r.string()?,
+                input: r.string()?,
+                halt_on_error: r.bool()?,
+                initex: r.bool()?,
+                synctex: r.bool()?,
+                semantic_pagination: r.bool()?,
+                shell_escape: r.bool()?,
+                build_date: r.time()?,
+            },
+
+            1 => EngineJob::Bibtex {
+                aux: r.string()?,
+                min_crossrefs: r
+                    .option(WireReader::u64)?
+                    .map(|x| x.try_into().map_err(invalid_data))
+                    .transpose()?,
+            },
+
+            2 => EngineJob::Xdvipdfmx {
+                xdv: r.string()?,
+                pdf: r.string()?,
+                build_date: r.time()?,
+                paper_spec: r.option(WireReader::string)?,
+                conformance: r.conformance()?,
+                output_intent_profile: r.option(WireReader::string)?,
+                output_condition: r.option(WireReader::string)?,
+                encryption: r.option(|r| {
+                    Ok(PdfEncryption {
+                        owner_password: r.string()?,
+                        user_password: r.string()?,
+                        key_bits: r.u64()?.try_into().map_err(invalid_data)?,
+                        permissions: PdfPermissions {
+                            print: r.bool()?,
+                            modify: r.bool()?,
+                            copy: r.bool()?,
+                            annotate: r.bool()?,
+                            fill_forms: r.bool()?,
+                            accessibility: r.bool()?,
+                            assemble: r.bool()?,
+                            print_high_quality: r.bool()?,
+                        },
+                    })
+                })?,
+            },
+
+            x => return Err(invalid_data(format!("unknown engine job {x}"))),
+        };
+
+        Ok(EngineLaunch {
+            job,
+            security,
+            expose_absolute_paths,
+            relative_path_base,
+            mtime_override,
+        })
+    }
+}
+
+// The session's side of the connection.
+
+/// The session's end of the connection to a worker, which answers the
+/// worker's requests.
+struct WorkerServer {
+    reader: WireReader<BufReader<ChildStdout>>,
+    writer: WireWriter<BufWriter<ChildStdin>>,
+    inputs: HashMap<u64, InputHandle>,
+    outputs: HashMap<u64, OutputHandle>,
+    next_id: u64,
+}
+
+impl WorkerServer {
+    /// Send the job to the worker and serve its requests until it finishes.
+    fn serve(
+        &mut self,
+        launch: &EngineLaunch,
+        hooks: &mut dyn DriverHooks,
+        status: &mut dyn StatusBackend,
+    ) -> Result<TexOutcome> {
+        launch.write_to(&mut self.writer)?;
+        self.writer.flush()?;
+
+        loop {
+            let msg = match self.reader.u8() {
+                Ok(m) => m,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    bail!("the engine worker exited before finishing its job")
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            match msg {
+                MSG_OUTPUT_OPEN_NAME => {
+                    let name = self.reader.string()?;
+                    let r = hooks.io().output_open_name(&name);
+                    self.reply_output(r)?;
+                }
+
+                MSG_OUTPUT_OPEN_STDOUT => {
+                    let r = hooks.io().output_open_stdout();
+                    self.reply_output(r)?;
+                }
+
+                MSG_INPUT_OPEN_NAME => {
+                    let name = self.reader.string()?;
+                    let r = hooks.io().input_open_name_with_abspath(&name, status);
+                    self.reply_input(r)?;
+                }
+
+                MSG_INPUT_OPEN_PRIMARY => {
+                    let r = hooks.io().input_open_primary_with_abspath(status);
+                    self.reply_input(r)?;
+                }
+
+                MSG_INPUT_OPEN_FORMAT => {
+                    let name = self.reader.string()?;
+                    let r = match hooks.io().input_open_format(&name, status) {
+                        OpenResult::Ok(ih) => OpenResult::Ok((ih, None)),
+                        OpenResult::NotAvailable => OpenResult::NotAvailable,
+                        OpenResult::Err(e) => OpenResult::Err(e),
+                    };
+                    self.reply_input(r)?;
+                }
+
+                MSG_SHELL_ESCAPE => {
+                    let command = self.reader.string()?;
+                    let code = match hooks.sysrq_shell_escape(&command, status) {
+                        Ok(()) => 0,
+                        Err(SystemRequestError::NotImplemented) => 1,
+                        Err(SystemRequestError::NotAllowed) => 2,
+                        Err(SystemRequestError::Failed) => 3,
+                    };
+                    self.writer.u8(code)?;
+                    self.writer.flush()?;
+                }
+
+                MSG_INPUT_READ => {
+                    let id = self.reader.u64()?;
+                    let len = self.reader.u64()?;
+                    let r = self.input(id).and_then(|ih| {
+                        let mut data = Vec::new();
+                        ih.by_ref().take(len).read_to_end(&mut data)?;
+                        Ok(data)
+                    });
+                    self.reply(r, |w, data| w.bytes(&data))?;
+                }
+
+                MSG_INPUT_SEEK => {
+                    let id = self.reader.u64()?;
+                    let pos = self.reader.seek_from()?;
+                    let r = self.input(id).and_then(|ih| ih.try_seek(pos));
+                    self.reply(r, WireWriter::u64)?;
+                }
+
+                MSG_INPUT_SIZE => {
+                    let id = self.reader.u64()?;
+                    let r = self.input(id).and_then(|ih| ih.get_size());
+                    self.reply(r, |w, size| w.u64(size as u64))?;
+                }
+
+                MSG_INPUT_CLOSE => {
+                    let id = self.reader.u64()?;
+                    self.inputs.remove(&id);
+                }
+
+                MSG_OUTPUT_WRITE => {
+                    let id = self.reader.u64()?;
+                    let data = self.reader.bytes()?;
+
+                    if let Some(oh) = self.outputs.get_mut(&id) {
+                        if let Err(e) = oh.write_all(&data) {
+                            tt_warning!(status, "write to {} failed", oh.name(); e.into());
+                        }
+                    }
+                }
+
+                MSG_OUTPUT_CLOSE => {
+                    let id = self.reader.u64()?;
+
+                    if let Some(mut oh) = self.outputs.remove(&id) {
+                        if let Err(e) = oh.flush() {
+                            tt_warning!(status, "error when closing output {}", oh.name(); e.into());
+                        }
+                    }
+                }
+
+                MSG_EVENT_OUTPUT_CLOSED => {
+                    let name = self.reader.string()?;
+                    let digest = self.reader.string()?.parse()?;
+                    hooks.event_output_closed(name, digest);
+                }
+
+                MSG_EVENT_INPUT_CLOSED => {
+                    let name = self.reader.string()?;
+                    let digest = self
+                        .reader
+                        .option(WireReader::string)?
+                        .map(|d| d.parse::<DigestData>())
+                        .transpose()?;
+                    hooks.event_input_closed(name, digest, status);
+                }
+
+                MSG_REPORT => {
+                    let kind = self.reader.message_kind()?;
+                    let message = self.reader.string()?;
+                    let err = self.reader.option(WireReader::error)?;
+                    status.report(kind, format_args!("{message}"), err.as_ref());
+                }
+
+                MSG_DIAGNOSTIC => {
+                    let diag = Diagnostic {
+                        kind: self.reader.message_kind()?,
+                        file: self.reader.option(WireReader::string)?,
+                        line: self
+                            .reader
+                            .option(WireReader::u64)?
+                            .map(|l| l.try_into().unwrap_or(u32::MAX)),
+                        context: self.reader.strings()?,
+                        message: self.reader.string()?,
+                    };
+                    status.report_diagnostic(&diag);
+                }
+
+                MSG_DUMP_ERROR_LOGS => {
+                    let data = self.reader.bytes()?;
+                    status.dump_error_logs(&data);
+                }
+
+                MSG_DONE => {
+                    return match self.reader.u8()? {
+                        REPLY_OK => Ok(match self.reader.u8()? {
+                            0 => TexOutcome::Spotless,
+                            1 => TexOutcome::Warnings,
+                            _ => TexOutcome::Errors,
+                        }),
+                        _ => Err(self.reader.error()?),
+                    };
+                }
+
+                m => bail!("unexpected message {m} from the engine worker"),
+            }
+        }
+    }
+
+    /// Look up an input that the worker has open.
+    fn input(&mut self, id: u64) -> Result<&mut InputHandle> {
+        self.inputs
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("the engine worker used an unknown input {id}"))
+    }
+
+    /// Answer a request on an open file with *r*, writing a successful
+    /// result with *f*.
+    fn reply<T, F>(&mut self, r: Result<T>, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut WireWriter<BufWriter<ChildStdin>>, T) -> io::Result<()>,
+    {
+        let w = &mut self.writer;
+
+        match r {
+            Ok(v) => {
+                w.u8(REPLY_OK)?;
+                f(w, v)?;
+            }
+
+            Err(e) => {
+                w.u8(REPLY_ERR)?;
+                w.error(&e)?;
+            }
+        }
+
+        Ok(w.flush()?)
+    }
+
+    fn reply_output(&mut self, r: OpenResult<OutputHandle>) -> Result<()> {
+        let w = &mut self.writer;
+
+        match r {
+            OpenResult::Ok(oh) => {
+                let id = self.next_id;
+                self.next_id += 1;
+                w.u8(REPLY_OK)?;
+                w.str(oh.name())?;
+                w.u64(id)?;
+                self.outputs.insert(id, oh);
+            }
+
+            OpenResult::NotAvailable => w.u8(REPLY_NOT_AVAILABLE)?,
+
+            OpenResult::Err(e) => {
+                w.u8(REPLY_ERR)?;
+                w.error(&e)?;
+            }
+        }
+
+        Ok(w.flush()?)
+    }
+
+    /// Answer an input request. The session keeps the file open, and the
+    /// worker reads it in chunks, so that big files such as formats never
+    /// have to be held in memory whole. The worker reports the digest of
+    /// what it read when it closes the file.
+    fn reply_input(&mut self, r: OpenResult<(InputHandle, Option<PathBuf>)>) -> Result<()> {
+        let w = &mut self.writer;
+
+        match r {
+            OpenResult::Ok((mut ih, path)) => match ih.get_unix_mtime() {
+                Ok(mtime) => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    w.u8(REPLY_OK)?;
+                    w.str(ih.name())?;
+                    w.u8(match ih.origin() {
+                        InputOrigin::Filesystem => 0,
+                        InputOrigin::NotInput => 1,
+                        InputOrigin::Other => 2,
+                    })?;
+                    w.option(mtime, WireWriter::i64)?;
+                    w.option(path.as_deref(), WireWriter::path)?;
+                    w.u64(id)?;
+                    self.inputs.insert(id, ih);
+                }
+
+                Err(e) => {
+                    w.u8(REPLY_ERR)?;
+                    w.error(&e)?;
+                }
+            },
+
+            OpenResult::NotAvailable => w.u8(REPLY_NOT_AVAILABLE)?,
+
+            OpenResult::Err(e) => {
+                w.u8(REPLY_ERR)?;
+                w.error(&e)?;
+            }
+        }
+
+        Ok(w.flush()?)
+    }
+}
+
+// The worker's side of the connection.
+
+/// The worker's end of the connection to the session.
+struct Channel {
+    reader: WireReader<io::StdinLock<'static>>,
+    writer: WireWriter<BufWriter<io::StdoutLock<'static>>>,
+}
+
+impl Channel {
+    /// Send a message that the session doesn't answer.
+    ///
+    /// Errors are ignored, since there is nobody to report them to. If the
+    /// connection is broken, the session will notice that the worker's output
+    /// ended early, and the worker will fail when it tries to send its
+    /// result.
+    fn notify<F>(chan: &RefCell<Channel>, f: F)
+    where
+        F: FnOnce(&mut WireWriter<BufWriter<io::StdoutLock<'static>>>) -> io::Result<()>,
+    {
+        if let Ok(mut chan) = chan.try_borrow_mut() {
+            let _ = f(&mut chan.writer);
+        }
+    }
+
+    /// Send a request with an optional string argument, and read the status
+    /// code that starts the answer.
+    fn request(&mut self, msg: u8, arg: Option<&str>) -> io::Result<u8> {
+        self.writer.u8(msg)?;
+
+        if let Some(a) = arg {
+            self.writer.str(a)?;
+        }
+
+        self.writer.flush()?;
+        self.reader.u8()
+    }
+}
+
+fn worker_main() -> Result<()> {
+    let chan = Rc::new(RefCell::new(Channel {
+        reader: WireReader(io::stdin().lock()),
+        writer: WireWriter(BufWriter::new(io::stdout().lock())),
+    }));
+
+    let launch = EngineLaunch::read_from(&mut chan.borrow_mut().reader)
+        .context("failed to receive the engine job")?;
+
+    let mut hooks = WorkerHooks { chan: chan.clone() };
+    let mut status = WorkerStatus { chan: chan.clone() };
+    let result = launch.run_here(&mut hooks, &mut status);
+
+    let w = &mut chan.borrow_mut().writer;
+    w.u8(MSG_DONE)?;
+
+    match result {
+        Ok(outcome) => {
+            w.u8(REPLY_OK)?;
+            w.u8(match outcome {
+                TexOutcome::Spotless => 0,
+                TexOutcome::Warnings => 1,
+                TexOutcome::Errors => 2,
+            })?;
+        }
+
+        Err(e) => {
+            w.u8(REPLY_ERR)?;
+            w.error(&e)?;
+        }
+    }
+
+    Ok(w.flush()?)
+}
+
+/// The driver hooks and I/O provider of a worker, which pass everything on to
+/// the session.
+struct WorkerHooks {
+    chan: Rc<RefCell<Channel>>,
+}
+
+impl WorkerHooks {
+    fn open_output(&mut self, msg: u8, name: Option<&str>) -> OpenResult<OutputHandle> {
+        let r = (|| {
+            let mut chan = self.chan.borrow_mut();
+
+            Ok(match chan.request(msg, name)? {
+                REPLY_OK => {
+                    let name = chan.reader.string()?;
+                    let id = chan.reader.u64()?;
+                    OpenResult::Ok(OutputHandle::new(
+                        name,
+                        WorkerOutput {
+                            chan: self.chan.clone(),
+                            id,
+                            buf: Vec::new(),
+                        },
+                    ))
+                }
+                REPLY_NOT_AVAILABLE => OpenResult::NotAvailable,
+                _ => OpenResult::Err(chan.reader.error()?),
+            })
+        })();
+
+        r.unwrap_or_else(|e: io::Error| OpenResult::Err(e.into()))
+    }
+
+    fn open_input(
+        &mut self,
+        msg: u8,
+        name: Option<&str>,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        let r = (|| {
+            let mut chan = self.chan.borrow_mut();
+
+            Ok(match chan.request(msg, name)? {
+                REPLY_OK => {
+                    let name = chan.reader.string()?;
+                    let origin = match chan.reader.u8()? {
+                        0 => InputOrigin::Filesystem,
+                        1 => InputOrigin::NotInput,
+                        _ => InputOrigin::Other,
+                    };
+                    let mtime = chan.reader.option(WireReader::i64)?;
+                    let path = chan.reader.option(WireReader::path)?;
+                    let id = chan.reader.u64()?;
+                    let input = WorkerInput {
+                        chan: self.chan.clone(),
+                        id,
+                        buf: Cursor::new(Vec::new()),
+                        mtime,
+                    };
+                    OpenResult::Ok((InputHandle::new(name, input, origin), path))
+                }
+                REPLY_NOT_AVAILABLE => OpenResult::NotAvailable,
+                _ => OpenResult::Err(chan.reader.error()?),
+            })
+        })();
+
+        r.unwrap_or_else(|e: io::Error| OpenResult::Err(e.into()))
+    }
+}
+
+impl IoProvider for WorkerHooks {
+    fn output_open_name(&mut self, name: &str) -> OpenResult<OutputHandle> {
+        self.open_output(MSG_OUTPUT_OPEN_NAME, Some(name))
+    }
+
+    fn output_open_stdout(&mut self) -> OpenResult<OutputHandle> {
+        self.open_output(MSG_OUTPUT_OPEN_STDOUT, None)
+    }
+
+    fn input_open_name(
+        &mut self,
+        name: &str,
+        status: &mut dyn StatusBackend,
+    ) -> OpenResult<InputHandle> {
+        match self.input_open_name_with_abspath(name, status) {
+            OpenResult::Ok((ih, _path)) => OpenResult::Ok(ih),
+            OpenResult::Err(e) => OpenResult::Err(e),
+            OpenResult::NotAvailable => OpenResult::NotAvailable,
+        }
+    }
+
+    fn input_open_name_with_abspath(
+        &mut self,
+        name: &str,
+        _status: &mut dyn StatusBackend,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        self.open_input(MSG_INPUT_OPEN_NAME, Some(name))
+    }
+
+    fn input_open_primary(&mut self, status: &mut dyn StatusBackend) -> OpenResult<InputHandle> {
+        match self.input_open_primary_with_abspath(status) {
+            OpenResult::Ok((ih, _path)) => OpenResult::Ok(ih),
+            OpenResult::Err(e) => OpenResult::Err(e),
+            OpenResult::NotAvailable => OpenResult::NotAvailable,
+        }
+    }
+
+    fn input_open_primary_with_abspath(
+        &mut self,
+        _status: &mut dyn StatusBackend,
+    ) -> OpenResult<(InputHandle, Option<PathBuf>)> {
+        self.open_input(MSG_INPUT_OPEN_PRIMARY, None)
+    }
+
+    fn input_open_format(
+        &mut self,
+        name: &str,
+        _status: &mut dyn StatusBackend,
+    ) -> OpenResult<InputHandle> {
+        match self.open_input(MSG_INPUT_OPEN_FORMAT, Some(name)) {
+            OpenResult::Ok((ih, _path)) => OpenResult::Ok(ih),
+            OpenResult::Err(e) => OpenResult::Err(e),
+            OpenResult::NotAvailable => OpenResult::NotAvailable,
+        }
+    }
+}
+
+impl DriverHooks for WorkerHooks {
+    fn io(&mut self) -> &mut dyn IoProvider {
+        self
+    }
+
+    fn event_output_closed(&mut self, name: String, digest: DigestData) {
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_EVENT_OUTPUT_CLOSED)?;
+            w.str(&name)?;
+            w.str(&digest.to_string())
+        });
+    }
+
+    fn event_input_closed(
+        &mut self,
+        name: String,
+        digest: Option<DigestData>,
+        _status: &mut dyn StatusBackend,
+    ) {
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_EVENT_INPUT_CLOSED)?;
+            w.str(&name)?;
+            w.option(digest.map(|d| d.to_string()).as_deref(), WireWriter::str)
+        });
+    }
+
+    fn sysrq_shell_escape(
+        &mut self,
+        command: &str,
+        _status: &mut dyn StatusBackend,
+    ) -> StdResult<(), SystemRequestError> {
+        let mut chan = self.chan.borrow_mut();
+
+        match chan.request(MSG_SHELL_ESCAPE, Some(command)) {
+            Ok(0) => Ok(()),
+            Ok(1) => Err(SystemRequestError::NotImplemented),
+            Ok(2) => Err(SystemRequestError::NotAllowed),
+            _ => Err(SystemRequestError::Failed),
+        }
+    }
+}
+
+/// The status backend of a worker, which passes everything on to the
+/// session.
+struct WorkerStatus {
+    chan: Rc<RefCell<Channel>>,
+}
+
+impl StatusBackend for WorkerStatus {
+    fn report(&mut self, kind: MessageKind, args: std::fmt::Arguments, err: Option<&Error>) {
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_REPORT)?;
+            w.message_kind(kind)?;
+            w.str(&args.to_string())?;
+            w.option(err, WireWriter::error)
+        });
+    }
+
+    fn report_diagnostic(&mut self, diag: &Diagnostic) {
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_DIAGNOSTIC)?;
+            w.message_kind(diag.kind)?;
+            w.option(diag.file.as_deref(), WireWriter::str)?;
+            w.option(diag.line.map(u64::from), WireWriter::u64)?;
+            w.strings(&diag.context)?;
+            w.str(&diag.message)
+        });
+    }
+
+    fn dump_error_logs(&mut self, output: &[u8]) {
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_DUMP_ERROR_LOGS)?;
+            w.bytes(output)
+        });
+    }
+}
+
+/// An input file of a worker, which the session keeps open and sends over in
+/// chunks.
+struct WorkerInput {
+    chan: Rc<RefCell<Channel>>,
+    id: u64,
+
+    /// The part of the current chunk that hasn't been read yet.
+    buf: Cursor<Vec<u8>>,
+    mtime: Option<i64>,
+}
+
+impl WorkerInput {
+    /// Send a request about this file, and read the successful result with
+    /// *f*.
+    fn request<T, F, G>(&mut self, msg: u8, args: G, f: F) -> Result<T>
+    where
+        G: FnOnce(&mut WireWriter<BufWriter<io::StdoutLock<'static>>>) -> io::Result<()>,
+        F: FnOnce(&mut WireReader<io::StdinLock<'static>>) -> io::Result<T>,
+    {
+        let mut chan = self.chan.borrow_mut();
+        let w = &mut chan.writer;
+        w.u8(msg)?;
+        w.u64(self.id)?;
+        args(w)?;
+        w.flush()?;
+
+        match chan.reader.u8()? {
+            REPLY_OK => Ok(f(&mut chan.reader)?),
+            _ => Err(chan.reader.error()?),
+        }
+    }
+
+    /// The number of bytes of the current chunk that haven't been read yet.
+    fn n_buffered(&self) -> u64 {
+        self.buf.get_ref().len() as u64 - self.buf.position()
+    }
+}
+
+impl Read for WorkerInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.n_buffered() == 0 {
+            let chunk = self
+                .request(
+                    MSG_INPUT_READ,
+                    |w| w.u64(INPUT_CHUNK_SIZE as u64),
+                    WireReader::bytes,
+                )
+                .map_err(io::Error::other)?;
+            self.buf = Cursor::new(chunk);
+        }
+
+        self.buf.read(buf)
+    }
+}
+
+impl InputFeatures for WorkerInput {
+    fn get_size(&mut self) -> Result<usize> {
+        let size = self.request(MSG_INPUT_SIZE, |_| Ok(()), WireReader::u64)?;
+        Ok(size.try_into()?)
+    }
+
+    fn get_unix_mtime(&mut self) -> Result<Option<i64>> {
+        Ok(self.mtime)
+    }
+
+    fn try_seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        // The session is ahead of us by whatever we've buffered.
+        let pos = match pos {
+            SeekFrom::Current(n) => SeekFrom::Current(n - self.n_buffered() as i64),
+            p => p,
+        };
+
+        self.buf = Cursor::new(Vec::new());
+        self.request(MSG_INPUT_SEEK, |w| w.seek_from(pos), WireReader::u64)
+    }
+}
+
+impl Drop for WorkerInput {
+    fn drop(&mut self) {
+        let id = self.id;
+
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_INPUT_CLOSE)?;
+            w.u64(id)
+        });
+    }
+}
+
+/// An output file of a worker, whose contents are sent to the session in
+/// chunks.
+struct WorkerOutput {
+    chan: Rc<RefCell<Channel>>,
+    id: u64,
+    buf: Vec<u8>,
+}
+
+impl WorkerOutput {
+    fn send(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let w = &mut self.chan.borrow_mut().writer;
+        w.u8(MSG_OUTPUT_WRITE)?;
+        w.u64(self.id)?;
+        w.bytes(&self.buf)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl Write for WorkerOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+
+        if self.buf.len() >= OUTPUT_CHUNK_SIZE {
+            self.send()?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
+
+impl Drop for WorkerOutput {
+    fn drop(&mut self) {
+        let _ = self.send();
+        let id = self.id;
+
+        Channel::notify(&self.chan, |w| {
+            w.u8(MSG_OUTPUT_CLOSE)?;
+            w.u64(id)
+        });
+    }
+}
+
+// The encoding of the values in the messages. Integers are little-endian,
+// and byte strings are preceded by their length.
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+struct WireWriter<W>(W);
+
+impl<W: Write> WireWriter<W> {
+    fn u8(&mut self, v: u8) -> io::Result<()> {
+        self.0.write_all(&[v])
+    }
+
+    fn bool(&mut self, v: bool) -> io::Result<()> {
+        self.u8(v.into())
+    }
+
+    fn u64(&mut self, v: u64) -> io::Result<()> {
+        self.0.write_all(&v.to_le_bytes())
+    }
+
+    fn i64(&mut self, v: i64) -> io::Result<()> {
+        self.0.write_all(&v.to_le_bytes())
+    }
+
+    fn bytes(&mut self, v: &[u8]) -> io::Result<()> {
+        self.u64(v.len() as u64)?;
+        self.0.write_all(v)
+    }
+
+    fn str(&mut self, v: &str) -> io::Result<()> {
+        self.bytes(v.as_bytes())
+    }
+
+    fn strings(&mut self, v: &[String]) -> io::Result<()> {
+        self.u64(v.len() as u64)?;
+
+        for s in v {
+            self.str(s)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn path(&mut self, v: &Path) -> io::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+        self.bytes(v.as_os_str().as_bytes())
+    }
+
+    #[cfg(not(unix))]
+    fn path(&mut self, v: &Path) -> io::Result<()> {
+        self.str(&v.to_string_lossy())
+    }
+
+    fn seek_from(&mut self, v: SeekFrom) -> io::Result<()> {
+        match v {
+            SeekFrom::Start(n) => {
+                self.u8(0)?;
+                self.u64(n)
+            }
+            SeekFrom::End(n) => {
+                self.u8(1)?;
+                self.i64(n)
+            }
+            SeekFrom::Current(n) => {
+                self.u8(2)?;
+                self.i64(n)
+            }
+        }
+    }
+
+    fn time(&mut self, v: SystemTime) -> io::Result<()> {
+        let d = v
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(invalid_data)?;
+        self.u64(d.as_secs())?;
+        self.u64(d.subsec_nanos().into())
+    }
+
+    fn message_kind(&mut self, v: MessageKind) -> io::Result<()> {
+        self.u8(match v {
+            MessageKind::Note => 0,
+            MessageKind::Warning => 1,
+            MessageKind::Error => 2,
+        })
+    }
+
+    fn conformance(&mut self, v: PdfConformance) -> io::Result<()> {
+        self.u8(match v {
+            PdfConformance::None => 0,
+            PdfConformance::PdfA2b => 1,
+            PdfConformance::PdfX4 => 2,
+        })
+    }
+
+    /// Write an error as the messages of its chain of causes, followed by the
+    /// kind of its root cause. The kinds that callers might downcast to are
+    /// sent along with their data, so that they can be rebuilt.
+    fn error(&mut self, v: &Error) -> io::Result<()> {
+        let chain: Vec<_> = v.chain().map(|e| e.to_string()).collect();
+        self.strings(&chain)?;
+
+        match v.root_cause().downcast_ref::<ConformanceError>() {
+            Some(ce) => {
+                self.u8(ERROR_CONFORMANCE)?;
+                self.conformance(ce.conformance)?;
+                self.u64(ce.violations.len() as u64)?;
+
+                for cv in &ce.violations {
+                    self.u8(match cv.kind {
+                        ViolationKind::Encryption => 0,
+                        ViolationKind::FontNotEmbedded => 1,
+                        ViolationKind::ForbiddenAction => 2,
+                        ViolationKind::ForbiddenAnnotation => 3,
+                        ViolationKind::ColorSpace => 4,
+                        ViolationKind::PdfVersion => 5,
+                        ViolationKind::Metadata => 6,
+                        ViolationKind::OutputIntent => 7,
+                        ViolationKind::Other => 8,
+                    })?;
+                    self.str(&cv.detail)?;
+                }
+
+                Ok(())
+            }
+
+            None => self.u8(ERROR_OTHER),
+        }
+    }
+
+    fn option<T, F>(&mut self, v: Option<T>, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut Self, T) -> io::Result<()>,
+    {
+        match v {
+            Some(x) => {
+                self.bool(true)?;
+                f(self, x)
+            }
+            None => self.bool(false),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct WireReader<R>(R);
+
+impl<R: Read> WireReader<R> {
+    fn u8(&mut self) -> io::Result<u8> {
+        let mut b = [0u8; 1];
+        self.0.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut b = [0u8; 8];
+        self.0.read_exact(&mut b)?;
+        Ok(u64::from_le_bytes(b))
+    }
+
+    fn i64(&mut self) -> io::Result<i64> {
+        let mut b = [0u8; 8];
+        self.0.read_exact(&mut b)?;
+        Ok(i64::from_le_bytes(b))
+    }
+
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.u64()?.try_into().map_err(invalid_data)?;
+        let mut v = vec![0u8; len];
+        self.0.read_exact(&mut v)?;
+        Ok(v)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.bytes()?).map_err(invalid_data)
+    }
+
+    fn strings(&mut self) -> io::Result<Vec<String>> {
+        let n = self.u64()?;
+        (0..n).map(|_| self.string()).collect()
+    }
+
+    #[cfg(unix)]
+    fn path(&mut self) -> io::Result<PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+        Ok(OsString::from_vec(self.bytes()?).into())
+    }
+
+    #[cfg(not(unix))]
+    fn path(&mut self) -> io::Result<PathBuf> {
+        Ok(self.string()?.into())
+    }
+
+    fn seek_from(&mut self) -> io::Result<SeekFrom> {
+        Ok(match self.u8()? {
+            0 => SeekFrom::Start(self.u64()?),
+            1 => SeekFrom::End(self.i64()?),
+            _ => SeekFrom::Current(self.i64()?),
+        })
+    }
+
+    fn time(&mut self) -> io::Result<SystemTime> {
+        let secs = self.u64()?;
+        let nanos = self.u64()?.try_into().map_err(invalid_data)?;
+        Ok(SystemTime::UNIX_EPOCH + Duration::new(secs, nanos))
+    }
+
+    fn message_kind(&mut self) -> io::Result<MessageKind> {
+        Ok(match self.u8()? {
+            0 => MessageKind::Note,
+            1 => MessageKind::Warning,
+            _ => MessageKind::Error,
+        })
+    }
+
+    fn conformance(&mut self) -> io::Result<PdfConformance> {
+        Ok(match self.u8()? {
+            0 => PdfConformance::None,
+            1 => PdfConformance::PdfA2b,
+            2 => PdfConformance::PdfX4,
+            x => return Err(invalid_data(format!("unknown PDF conformance {x}"))),
+        })
+    }
+
+    /// Read an error written by [`WireWriter::error`], rebuilding its chain
+    /// of causes from their messages, and its root cause from its kind.
+    fn error(&mut self) -> io::Result<Error> {
+        let mut chain = self.strings()?.into_iter().rev();
+        let root = chain.next().unwrap_or_default();
+
+        let mut err = match self.u8()? {
+            ERROR_CONFORMANCE => {
+                let conformance = self.conformance()?;
+                let n = self.u64()?;
+                let violations = (0..n)
+                    .map(|_| {
+                        Ok(ConformanceViolation {
+                            kind: match self.u8()? {
+                                0 => ViolationKind::Encryption,
+                                1 => ViolationKind::FontNotEmbedded,
+                                2 => ViolationKind::ForbiddenAction,
+                                3 => ViolationKind::ForbiddenAnnotation,
+                                4 => ViolationKind::ColorSpace,
+                                5 => ViolationKind::PdfVersion,
+                                6 => ViolationKind::Metadata,
+                                7 => ViolationKind::OutputIntent,
+                                _ => ViolationKind::Other,
+                            },
+                            detail: self.string()?,
+                        })
+                    })
+                    .collect::<io::Result<_>>()?;
+
+                Error::from(ConformanceError {
+                    conformance,
+                    violations,
+                })
+            }
+
+            _ => anyhow!(root),
+        };
+
+        for msg in chain {
+            err = err.context(msg);
+        }
+
+        Ok(err)
+    }
+
+    fn option<T, F>(&mut self, f: F) -> io::Result<Option<T>>
+    where
+        F: FnOnce(&mut Self) -> io::Result<T>,
+    {
+        if self.bool()? {
+            f(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(launch: &EngineLaunch) -> EngineLaunch {
+        let mut w = WireWriter(Vec::new());
+        launch.write_to(&mut w).unwrap();
+        EngineLaunch::read_from(&mut WireReader(&w.0[..])).unwrap()
+    }
+
+    #[test]
+    fn tex_job_roundtrip() {
+        let mut launch = EngineLaunch::new(
+            EngineJob::Tex {
+                format: "latex.fmt".to_owned(),
+                input: "doc.tex".to_owned(),
+                halt_on_error: false,
+                initex: false,
+                synctex: true,
+                semantic_pagination: false,
+                shell_escape: true,
+                build_date: SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 5),
+            },
+            SecuritySettings::new(SecurityStance::DisableInsecures),
+        );
+        launch.expose_absolute_paths = false;
+        launch.relative_path_base = Some(PathBuf::from("/work/doc"));
+        launch.mtime_override = Some(-3);
+
+        let back = roundtrip(&launch);
+        assert!(!back.security.allow_shell_escape());
+        assert!(!back.expose_absolute_paths);
+        assert_eq!(back.relative_path_base, launch.relative_path_base);
+        assert_eq!(back.mtime_override, Some(-3));
+
+        match back.job {
+            EngineJob::Tex {
+                format,
+                input,
+                halt_on_error,
+                synctex,
+                shell_escape,
+                build_date,
+                ..
+            } => {
+                assert_eq!(format, "latex.fmt");
+                assert_eq!(input, "doc.tex");
+                assert!(!halt_on_error);
+                assert!(synctex);
+                assert!(shell_escape);
+                assert_eq!(
+                    build_date,
+                    SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 5)
+                );
+            }
+            j => panic!("unexpected job {j:?}"),
+        }
+    }
+
+    #[test]
+    fn xdvipdfmx_job_roundtrip() {
+        let encryption = PdfEncryption {
+            owner_password: "owner".to_owned(),
+            user_password: String::new(),
+            key_bits: 256,
+            permissions: PdfPermissions {
+                copy: false,
+                ..PdfPermissions::all()
+            },
+        };
+
+        let launch = EngineLaunch::new(
+            EngineJob::Xdvipdfmx {
+                xdv: "doc.xdv".to_owned(),
+                pdf: "doc.pdf".to_owned(),
+                build_date: SystemTime::UNIX_EPOCH,
+                paper_spec: Some("a4".to_owned()),
+                conformance: PdfConformance::PdfX4,
+                output_intent_profile: None,
+                output_condition: Some("FOGRA39".to_owned()),
+                encryption: Some(encryption.clone()),
+            },
+            SecuritySettings::default(),
+        );
+
+        match roundtrip(&launch).job {
+            EngineJob::Xdvipdfmx {
+                paper_spec,
+                conformance,
+                output_intent_profile,
+                output_condition,
+                encryption: e,
+                ..
+            } => {
+                assert_eq!(paper_spec.as_deref(), Some("a4"));
+                assert_eq!(conformance, PdfConformance::PdfX4);
+                assert_eq!(output_intent_profile, None);
+                assert_eq!(output_condition.as_deref(), Some("FOGRA39"));
+                assert_eq!(e, Some(encryption));
+            }
+            j => panic!("unexpected job {j:?}"),
+        }
+    }
+
+    #[test]
+    fn error_chain_roundtrip() {
+        let err = anyhow!("file not found").context("failed to open `a.tex`");
+        let mut w = WireWriter(Vec::new());
+        w.error(&err).unwrap();
+
+        let back = WireReader(&w.0[..]).error().unwrap();
+        assert_eq!(back.to_string(), "failed to open `a.tex`");
+        assert_eq!(
+            back.chain().map(|e| e.to_string()).collect::<Vec<_>>(),
+            ["failed to open `a.tex`", "file not found"]
+        );
+    }
+
+    #[test]
+    fn conformance_error_roundtrip() {
+        let ce = ConformanceError {
+            conformance: PdfConformance::PdfA2b,
+            violations: vec![
+                ConformanceViolation {
+                    kind: ViolationKind::FontNotEmbedded,
+                    detail: "font `cmr10` is not embedded".to_owned(),
+                },
+                ConformanceViolation {
+                    kind: ViolationKind::Other,
+                    detail: "something else".to_owned(),
+                },
+            ],
+        };
+        let err = Error::from(ce.clone()).context("failed to create `doc.pdf`");
+        let mut w = WireWriter(Vec::new());
+        w.error(&err).unwrap();
+
+        // The root cause can still be downcast on the other side.
+        let back = WireReader(&w.0[..]).error().unwrap();
+        assert_eq!(back.to_string(), "failed to create `doc.pdf`");
+        assert_eq!(back.downcast_ref::<ConformanceError>(), Some(&ce));
+        assert_eq!(back.root_cause().to_string(), ce.to_string());
+    }
+}
@@ -8,15 +8,175 @@
 //! ProcessingSessionBuilder will need to learn how to tell `xdvipdfmx` to
 //! enable the reproducibility options used in the `tex-outputs` test rig.
 
+use std::{fmt::Arguments, thread};
 use tectonic::{
-    config::PersistentConfig, driver::ProcessingSessionBuilder,
-    status::termcolor::TermcolorStatusBackend, status::ChatterLevel, test_util::TestBundle,
+    config::PersistentConfig,
+    driver::ProcessingSessionBuilder,
+    status::termcolor::TermcolorStatusBackend,
+    status::{ChatterLevel, Diagnostic, MessageKind, StatusBackend},
+    test_util::TestBundle,
+    worker::EngineWorker,
 };
+use tectonic_errors::Error;
 
 mod util;
 
+/// A status backend that remembers the notes and structured diagnostics it
+/// receives.
+#[derive(Default)]
+struct DiagnosticCollector {
+    notes: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl StatusBackend for DiagnosticCollector {
+    fn report(&mut self, kind: MessageKind, args: Arguments, _err: Option<&Error>) {
+        if kind == MessageKind::Note {
+            self.notes.push(args.to_string());
+        }
+    }
+
+    fn report_diagnostic(&mut self, diag: &Diagnostic) {
+        self.diagnostics.push(diag.clone());
+    }
+
+    fn dump_error_logs(&mut self, _output: &[u8]) {}
+}
+
 // Keep these alphabetized.
 
+#[test]
+fn build_cache_key() {
+    util::set_test_root();
+
+    let tempdir = tempfile::Builder::new()
+        .prefix("tectonic_driver_test")
+        .tempdir()
+        .unwrap();
+
+    // Run a session with the build cache, after letting *customize* change
+    // its settings, and return whether it was skipped.
+    let run = |customize: &dyn Fn(&mut ProcessingSessionBuilder)| {
+        let mut status = DiagnosticCollector::default();
+        let mut pbuilder = ProcessingSessionBuilder::default();
+        pbuilder
+            .primary_input_path(util::test_path(&["tex-outputs", "the_letter_a.tex"]))
+            .tex_input_name("the_letter_a.tex")
+            .format_name("plain")
+            .format_cache_path(util::test_path(&[]))
+            .output_dir(tempdir.path())
+            .build_cache_dir(tempdir.path().join(".cache"))
+            .bundle(Box::new(TestBundle::default()));
+        customize(&mut pbuilder);
+
+        pbuilder
+            .create(&mut status)
+            .expect("couldn't create processing session")
+            .run(&mut status)
+            .expect("failed to execute processing session");
+
+        status.notes.iter().any(|n| n.starts_with("Skipping"))
+    };
+
+    assert!(!run(&|_| {}));
+    assert!(run(&|_| {}));
+
+    // Settings that don't affect the outputs don't change the key ...
+    assert!(run(&|b| {
+        b.html_assets_spec_path("assets.json")
+            .html_emit_files(false)
+            .html_emit_assets(false)
+            .dependency_manifest_path(tempdir.path().join("manifest.json"));
+    }));
+
+    // ... but ones that do, do.
+    assert!(!run(&|b| {
+        b.synctex(true);
+    }));
+}
+
+#[test]
+fn engine_workers() {
+    util::set_test_root();
+
+    // Several sessions at once, each running its engines in a `tectonic`
+    // worker process.
+    let threads: Vec<_> = (0..3)
+        .map(|_| {
+            thread::spawn(|| {
+                let mut status = DiagnosticCollector::default();
+
+                let tempdir = tempfile::Builder::new()
+                    .prefix("tectonic_driver_test")
+                    .tempdir()
+                    .unwrap();
+
+                let mut pbuilder = ProcessingSessionBuilder::default();
+                pbuilder
+                    .primary_input_path(util::test_path(&["tex-outputs", "the_letter_a.tex"]))
+                    .tex_input_name("the_letter_a.tex")
+                    .format_name("plain")
+                    .format_cache_path(util::test_path(&[]))
+                    .output_dir(tempdir.path())
+                    .bundle(Box::new(TestBundle::default()))
+                    .engine_worker(EngineWorker::new(env!("CARGO_BIN_EXE_tectonic")));
+
+                let mut session = pbuilder
+                    .create(&mut status)
+                    .expect("couldn't create processing session");
+
+                session
+                    .run(&mut status)
+                    .expect("failed to execute processing session");
+
+                assert!(tempdir.path().join("the_letter_a.pdf").is_file());
+            })
+        })
+        .collect();
+
+    for t in threads {
+        t.join().unwrap();
+    }
+}
+
+#[test]
+fn structured_diagnostics() {
+    util::set_test_root();
+
+    let mut status = DiagnosticCollector::default();
+
+    let tempdir = tempfile::Builder::new()
+        .prefix("tectonic_driver_test")
+        .tempdir()
+        .unwrap();
+
+    let mut pbuilder = ProcessingSessionBuilder::default();
+    pbuilder
+        .primary_input_buffer(b"\\relax\n\\catcode 1114112=11 \\bye\n")
+        .tex_input_name("texput.tex")
+        .format_name("plain")
+        .format_cache_path(util::test_path(&[]))
+        .output_dir(tempdir.path())
+        .bundle(Box::new(TestBundle::default()));
+
+    let mut session = pbuilder
+        .create(&mut status)
+        .expect("couldn't create processing session");
+
+    // The invalid character code is a fatal error.
+    assert!(session.run(&mut status).is_err());
+
+    let diag = status
+        .diagnostics
+        .iter()
+        .find(|d| d.kind == MessageKind::Error)
+        .expect("no error diagnostic was reported");
+    assert!(diag.message.starts_with("Bad character code"));
+    assert_eq!(diag.file.as_deref(), Some("texput.tex"));
+    assert_eq!(diag.line, Some(2));
+    assert_eq!(diag.context, ["\\catcode 1114112=", "11 \\bye"]);
+}
+
 #[test]
 fn the_letter_a() {
     util::set_test_root();
@@ -239,6 +239,42 @@ fn setup_v2() -> (tempfile::TempDir, PathBuf) {
     (tempdir, temppath)
 }
 
+/// Use `bundle create` to make a small TTB bundle from the spec `spec.toml` in
+/// *dir*, returning the path of the bundle. Its build directory is `bb`.
+#[cfg(feature = "serialization")]
+fn make_tiny_bundle(dir: &Path) -> PathBuf {
+    let input = dir.join("bundle-input").join("tex");
+    fs::create_dir_all(&input).unwrap();
+    fs::write(input.join("a.tex"), "hello\n").unwrap();
+    fs::write(input.join("b.sty"), "world\n").unwrap();
+    fs::write(
+        dir.join("spec.toml"),
+        "[bundle]\n\
+         name = 'tiny'\n\
+         expected_hash = ''\n\
+         search_order = ['/', { input = 'src' }]\n\
+         [inputs.src]\n\
+         source.dir.path = 'bundle-input'\n\
+         search_order = ['tex//']\n",
+    )
+    .unwrap();
+
+    let output = run_tectonic(
+        dir,
+        &[
+            "-X",
+            "bundle",
+            "create",
+            "--build-dir",
+            "bb",
+            "spec.toml",
+            "v1",
+        ],
+    );
+    success_or_panic(&output);
+    dir.join("bb").join("tiny").join("tiny.ttb")
+}
+
 /* Keep tests alphabetized */
 
 #[test]
@@ -479,6 +515,36 @@ fn keep_logs_on_error() {
     assert!(log.contains(r"job aborted, no legal \end found"));
 }
 
+#[cfg(feature = "serialization")]
+#[test]
+fn message_format_json() {
+    let fmt_arg = get_plain_format_arg();
+    let tempdir = setup_and_copy_files(&[]);
+    let output = run_tectonic_with_stdin(
+        tempdir.path(),
+        &[&fmt_arg, "-", "--message-format=json"],
+        "no end to this file",
+    );
+    error_or_panic(&output);
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("stdout line is not JSON"))
+        .collect();
+
+    assert!(events.iter().all(|e| e["type"].is_string()));
+    assert!(events
+        .iter()
+        .any(|e| e["kind"] == "note" && e["segments"]["highlighted"] == "TeX"));
+    assert!(events.iter().any(|e| e["type"] == "diagnostic"
+        && e["kind"] == "error"
+        && e["message"].as_str().unwrap().starts_with("Emergency stop")));
+
+    let last = events.last().unwrap();
+    assert_eq!(last["type"], "message");
+    assert_eq!(last["kind"], "error");
+}
+
 #[test]
 fn no_color_option() {
     // No input files here, but output files are created.
@@ -576,6 +642,107 @@ fn relative_include() {
     check_file(&tempdir, "subdirectory/relative_include.pdf");
 }
 
+#[cfg(feature = "serialization")]
+#[test]
+fn report_option() {
+    let fmt_arg = get_plain_format_arg();
+    let tempdir = setup_and_copy_files(&["subdirectory/content/1.tex"]);
+
+    let output = run_tectonic(
+        tempdir.path(),
+        &[
+            &fmt_arg,
+            "subdirectory/content/1.tex",
+            "--report=report.json",
+        ],
+    );
+    success_or_panic(&output);
+    check_file(&tempdir, "report.json");
+
+    let mut report = String::new();
+    File::open(tempdir.path().join("report.json"))
+        .unwrap()
+        .read_to_string(&mut report)
+        .unwrap();
+
+    assert!(report.contains(r#""1.pdf""#));
+    assert!(report.contains(r#""kind": "tex""#));
+    assert!(report.contains(r#""kind": "xdvipdfmx""#));
+}
+
+/// Run a plain TeX document from standard input through the TeX engine in
+/// semantic pagination mode, as for HTML output, returning the result along
+/// with the number of pages in the SPX file and the log.
+fn run_semantic(doc: &str) -> (Output, u16, String) {
+    let fmt_arg = get_plain_format_arg();
+    let tempdir = setup_and_copy_files(&[]);
+    let output = run_tectonic_with_stdin(
+        tempdir.path(),
+        &[
+            &fmt_arg,
+            "-",
+            "--outfmt=html",
+            "--pass=tex",
+            "--keep-intermediates",
+            "--keep-logs",
+        ],
+        doc,
+    );
+    success_or_panic(&output);
+
+    // The total page count is in the postamble, which the trailer points to.
+    let spx = fs::read(tempdir.path().join("texput.spx")).expect("`texput.spx` not found");
+    let end = spx.iter().rposition(|b| *b != 223).unwrap();
+    let post = u32::from_be_bytes(spx[end - 4..end].try_into().unwrap()) as usize;
+    let n_pages = u16::from_be_bytes([spx[post + 27], spx[post + 28]]);
+
+    let log =
+        fs::read_to_string(tempdir.path().join("texput.log")).expect("`texput.log` not found");
+    (output, n_pages, log)
+}
+
+/// In semantic pagination mode, lines and pages only break where the document
+/// forces them to, as with `\break` and `\eject` (the plain TeX versions of
+/// LaTeX's `\\` and `\newpage`) or a `\penalty-10000` (like `\pagebreak`).
+#[test]
+fn semantic_pagination_forced_breaks() {
+    let para = "These words are many more than fit on one line of the narrow page. ".repeat(10);
+    let tall = r"\hbox{x}\vskip 20pt ".repeat(40);
+    let doc = format!(
+        "\\hsize=2in \\vsize=2in\n\
+         {para}\\break {para}\\par\\message{{[lines=\\the\\prevgraf]}}\n\
+         {para}\\par\\message{{[lines=\\the\\prevgraf]}}\n\
+         \\eject\n\
+         {tall}\n\
+         \\penalty-10000\n\
+         Last page.\\bye\n"
+    );
+
+    let (_output, n_pages, log) = run_semantic(&doc);
+    assert!(log.contains("[lines=2]"));
+    assert!(log.contains("[lines=1]"));
+    assert!(!log.contains("[lines=3]"));
+
+    // The 800pt second page is much taller than \vsize, but isn't broken.
+    assert_eq!(n_pages, 3);
+}
+
+/// A long document without any forced page breaks mustn't overflow TeX's
+/// dimensions; it gets broken before it's taller than \maxdimen, with a
+/// warning.
+#[test]
+fn semantic_pagination_long_page() {
+    // About 30000pt in all; \maxdimen is just under 16384pt.
+    let doc = format!("{}\\bye\n", r"\hbox{x}\vskip 100pt ".repeat(300));
+
+    let (output, n_pages, log) = run_semantic(&doc);
+    assert_eq!(n_pages, 2);
+    assert!(log.contains(r"page grew taller than \maxdimen without a forced break"));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("warnings were issued by the TeX engine"));
+}
+
 // Regression #36
 #[test]
 fn space_in_filename() {
@@ -660,6 +827,548 @@ fn bundle_overrides() {
     }
 }
 
+/// Test inspecting and cleaning up the bundle cache
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_cache() {
+    use tectonic::digest::{self, Digest, DigestData};
+
+    let (_tempdir, temppath) = setup_v2();
+    let cache_dir = temppath.join("cache");
+
+    let run = |args: &[&str]| {
+        let mut command = prep_tectonic(&temppath, args);
+        command.env("TECTONIC_CACHE_DIR", &cache_dir);
+        println!("running {command:?}");
+        command.output().expect("tectonic failed to start")
+    };
+
+    // Set up a cache holding data for two versions of a bundle, only the
+    // newer of which is current.
+
+    let current = "1".repeat(64);
+    let old = "2".repeat(64);
+    let bundles = cache_dir.join("bundles");
+    fs::create_dir_all(bundles.join("hashes")).unwrap();
+    fs::write(
+        bundles
+            .join("hashes")
+            .join("https___example.com_bundle.ttb"),
+        format!("{current}\n"),
+    )
+    .unwrap();
+
+    let content = b"hello\n";
+    let mut dc = digest::create();
+    dc.update(content);
+    let hash = DigestData::from(dc).to_string();
+
+    for d in [&current, &old] {
+        let data = bundles.join("data").join(d);
+        fs::create_dir_all(data.join("tex")).unwrap();
+        fs::write(data.join("tex").join("good.tex"), content).unwrap();
+        fs::write(data.join("tex").join("bad.tex"), b"corrupted\n").unwrap();
+        fs::write(
+            bundles.join("data").join(format!("{d}.index")),
+            format!(
+                "[DEFAULTSEARCH]\nMAIN\n[SEARCH:MAIN]\n//\n[FILELIST]\n\
+                 0 10 6 {hash} tex/good.tex\n10 10 6 {hash} tex/bad.tex\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    let output = run(&["-X", "bundle", "cache", "list"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("{current}       2 files")));
+    assert!(stdout.contains("https___example.com_bundle.ttb"));
+    assert!(stdout.contains("(unreferenced)"));
+
+    let output = run(&["-X", "bundle", "cache", "du"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("total"));
+
+    // Verification finds the corrupted file, and can remove it.
+
+    let output = run(&["-X", "bundle", "cache", "verify", &current]);
+    error_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("`tex/bad.tex` is corrupt"));
+
+    let output = run(&["-X", "bundle", "cache", "verify", "--fix", &current]);
+    success_or_panic(&output);
+    assert!(!bundles
+        .join("data")
+        .join(&current)
+        .join("tex/bad.tex")
+        .exists());
+    assert!(bundles
+        .join("data")
+        .join(&current)
+        .join("tex/good.tex")
+        .exists());
+
+    // Garbage collection removes only the unreferenced data.
+
+    let output = run(&["-X", "bundle", "cache", "gc", "--dry-run"]);
+    success_or_panic(&output);
+    assert!(bundles.join("data").join(&old).exists());
+
+    let output = run(&["-X", "bundle", "cache", "gc"]);
+    success_or_panic(&output);
+    assert!(!bundles.join("data").join(&old).exists());
+    assert!(!bundles.join("data").join(format!("{old}.index")).exists());
+    assert!(bundles.join("data").join(&current).exists());
+
+    // With an age limit, data that are current but unused go too. An absurdly
+    // long limit just keeps everything.
+
+    let forever = u64::MAX.to_string();
+    let output = run(&["-X", "bundle", "cache", "gc", "--older-than", &forever]);
+    success_or_panic(&output);
+    assert!(bundles.join("data").join(&current).exists());
+
+    let output = run(&["-X", "bundle", "cache", "gc", "--older-than", "0"]);
+    success_or_panic(&output);
+    assert!(!bundles.join("data").join(&current).exists());
+    assert!(!bundles
+        .join("hashes")
+        .join("https___example.com_bundle.ttb")
+        .exists());
+}
+
+/// Test converting bundles between the TTB, ZIP, and directory layouts
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_convert() {
+    let tempdir = setup_and_copy_files(&[]);
+    let temppath = tempdir.path().to_owned();
+    let ttb = make_tiny_bundle(&temppath);
+
+    // The packer can also write ZIP bundles.
+    let output = run_tectonic(
+        &temppath,
+        &[
+            "-X",
+            "bundle",
+            "create",
+            "--job",
+            "pack",
+            "--build-dir",
+            "bb",
+            "spec.toml",
+            "zip",
+        ],
+    );
+    success_or_panic(&output);
+    assert!(temppath.join("bb").join("tiny").join("tiny.zip").is_file());
+
+    let ttb = ttb.to_str().unwrap();
+
+    for (input, output) in [(ttb, "t.zip"), ("t.zip", "dir"), ("dir", "t.ttb")] {
+        let output = run_tectonic(&temppath, &["-X", "bundle", "convert", input, output]);
+        success_or_panic(&output);
+    }
+
+    for name in ["FILELIST", "SEARCH", "SHA256SUM", "src/tex/a.tex"] {
+        assert!(temppath.join("dir").join(name).is_file());
+    }
+
+    // The round trip preserves the bundle exactly.
+    assert_eq!(
+        fs::read(ttb).unwrap(),
+        fs::read(temppath.join("t.ttb")).unwrap()
+    );
+
+    let output = run_tectonic(&temppath, &["-X", "bundle", "diff", ttb, "t.zip"]);
+    success_or_panic(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    // Existing outputs aren't overwritten.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "convert", ttb, "t.zip"]);
+    error_or_panic(&output);
+    let output = run_tectonic(&temppath, &["-X", "bundle", "convert", ttb, "dir"]);
+    error_or_panic(&output);
+
+    // Only bundles with the metadata of `bundle create` can be converted.
+    fs::create_dir(temppath.join("plain")).unwrap();
+    fs::write(temppath.join("plain").join("a.tex"), "hello\n").unwrap();
+    let output = run_tectonic(&temppath, &["-X", "bundle", "convert", "plain", "p.zip"]);
+    error_or_panic(&output);
+}
+
+/// Test comparing the contents of two bundles
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_diff() {
+    let tempdir = setup_and_copy_files(&[]);
+    let temppath = tempdir.path().to_owned();
+
+    let old = temppath.join("old");
+    let new = temppath.join("new");
+    fs::create_dir(&old).unwrap();
+    fs::create_dir(&new).unwrap();
+    fs::write(old.join("same.tex"), "same\n").unwrap();
+    fs::write(new.join("same.tex"), "same\n").unwrap();
+    fs::write(old.join("gone.tex"), "gone\n").unwrap();
+    fs::write(new.join("added.tex"), "added\n").unwrap();
+    fs::write(old.join("style.sty"), "one\ntwo\n").unwrap();
+    fs::write(new.join("style.sty"), "one\nthree\n").unwrap();
+
+    let output = run_tectonic(&temppath, &["-X", "bundle", "diff", "old", "new"]);
+    success_or_panic(&output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "A  added.tex\nD  gone.tex\nM  style.sty\n"
+    );
+
+    let output = run_tectonic(&temppath, &["-X", "bundle", "diff", "--text", "old", "new"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("--- a/style.sty\n+++ b/style.sty\n"));
+    assert!(stdout.contains("\n one\n-two\n+three\n"));
+
+    let output = run_tectonic(&temppath, &["-X", "bundle", "diff", "--json", "old", "new"]);
+    success_or_panic(&output);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["added"], serde_json::json!(["added.tex"]));
+    assert_eq!(report["removed"], serde_json::json!(["gone.tex"]));
+    assert_eq!(report["changed"], serde_json::json!(["style.sty"]));
+    assert!(report.get("diffs").is_none());
+}
+
+/// Test prefetching bundle files for offline use
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_fetch() {
+    let (_tempdir, temppath) = setup_v2();
+
+    // A mode must be given.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "fetch"]);
+    error_or_panic(&output);
+
+    let output = run_tectonic(&temppath, &["-X", "bundle", "fetch", "--all", "--build"]);
+    error_or_panic(&output);
+
+    // The test bundle is local, so there's nothing to prefetch from it.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "fetch", "--all"]);
+    error_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("only network bundles can be prefetched"));
+
+    // Fetching for a build runs the build without writing any outputs.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "fetch", "--build"]);
+    success_or_panic(&output);
+    assert!(!temppath
+        .join("build")
+        .join("default")
+        .join("default.pdf")
+        .exists());
+
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    assert!(temppath
+        .join("build")
+        .join("default")
+        .join("default.pdf")
+        .exists());
+}
+
+/// Test layering local bundles on top of the document's main bundle
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_overlays() {
+    let (_tempdir, temppath) = setup_v2();
+
+    // `plain.tex` comes from the test bundle; `house.sty` and `ckx.map` come
+    // from the overlay, which shadows the test bundle's version of the latter.
+    let overlay = temppath.join("house");
+    fs::create_dir(&overlay).unwrap();
+    fs::write(overlay.join("house.sty"), "% house style\n").unwrap();
+    fs::write(overlay.join("ckx.map"), "% overridden\n").unwrap();
+
+    let toml_path = temppath.join("Tectonic.toml");
+    let toml = fs::read_to_string(&toml_path).unwrap();
+    fs::write(
+        &toml_path,
+        toml.replace("[doc]\n", "[doc]\nbundle_overlays = ['house/']\n"),
+    )
+    .unwrap();
+
+    // The overlay path is relative to the document, not the working directory.
+    let src = temppath.join("src");
+
+    let output = run_tectonic(&src, &["-X", "bundle", "cat", "house.sty"]);
+    success_or_panic(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "% house style\n");
+
+    let output = run_tectonic(&src, &["-X", "bundle", "cat", "ckx.map"]);
+    success_or_panic(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "% overridden\n");
+
+    let output = run_tectonic(&src, &["-X", "bundle", "cat", "plain.tex"]);
+    success_or_panic(&output);
+    assert!(!output.stdout.is_empty());
+
+    let output = run_tectonic(&src, &["-X", "bundle", "search", "house"]);
+    success_or_panic(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "house.sty\n");
+
+    // A directory with a `SEARCH` file, like an unpacked bundle, finds files
+    // in the subdirectories that it lists.
+    make_tiny_bundle(&temppath);
+    fs::write(
+        &toml_path,
+        toml.replace("[doc]\n", "[doc]\nbundle_overlays = ['bb/tiny/content']\n"),
+    )
+    .unwrap();
+
+    let output = run_tectonic(&src, &["-X", "bundle", "cat", "a.tex"]);
+    success_or_panic(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n");
+
+    let output = run_tectonic(&src, &["-X", "bundle", "search", "src/tex/"]);
+    success_or_panic(&output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "src/tex/a.tex\nsrc/tex/b.sty\n"
+    );
+}
+
+/// Test serving a TTB bundle over HTTP
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_serve() {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpStream;
+
+    let (_tempdir, temppath) = setup_v2();
+
+    // Create a small bundle to serve.
+    let ttb = make_tiny_bundle(&temppath);
+    let ttb_len = fs::metadata(&ttb).unwrap().len();
+
+    // Only TTB files can be served.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "serve", "spec.toml"]);
+    error_or_panic(&output);
+
+    let mut server = prep_tectonic(
+        &temppath,
+        &[
+            "-X",
+            "bundle",
+            "serve",
+            "--addr",
+            "127.0.0.1:0",
+            ttb.to_str().unwrap(),
+        ],
+    )
+    .stderr(Stdio::piped())
+    .spawn()
+    .expect("tectonic failed to start");
+
+    // Keep the server's stderr open until it's shut down.
+    let mut server_stderr = BufReader::new(server.stderr.take().unwrap()).lines();
+    let mut digest = None;
+    let url = server_stderr
+        .by_ref()
+        .map(|l| l.unwrap())
+        .find_map(|l| {
+            if let Some((_, d)) = l.split_once("(digest ") {
+                digest = Some(d.trim_end_matches(')').to_owned());
+            }
+
+            l.split_once("http://").map(|(_, u)| format!("http://{u}"))
+        })
+        .expect("the server didn't report its URL");
+    let digest = digest.expect("the server didn't report the bundle digest");
+    let host = url["http://".len()..].split('/').next().unwrap().to_owned();
+
+    let request = |path: &str, range: &str| {
+        let mut stream = TcpStream::connect(&host).unwrap();
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: {host}\r\n{range}Connection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    };
+
+    let partial = request("/tiny.ttb", "Range: bytes=0-13\r\n");
+    let past_end = request("/tiny.ttb", &format!("Range: bytes={ttb_len}-\r\n"));
+    let whole = request("/tiny.ttb", "");
+    let missing = request("/other.ttb", "");
+
+    // A real client can fetch files from the served bundle.
+    let cache_dir = temppath.join("cache");
+    let output = prep_tectonic(
+        &temppath,
+        &["-X", "bundle", "fetch", "--bundle", &url, "/src//"],
+    )
+    .env("TECTONIC_CACHE_DIR", &cache_dir)
+    .output()
+    .expect("tectonic failed to start");
+
+    // A cached bundle can only be served once all of its files are cached.
+    let incomplete = prep_tectonic(&temppath, &["-X", "bundle", "serve", "--cache", &digest])
+        .env("TECTONIC_CACHE_DIR", &cache_dir)
+        .output()
+        .expect("tectonic failed to start");
+
+    let fetch_all = prep_tectonic(
+        &temppath,
+        &["-X", "bundle", "fetch", "--bundle", &url, "--all"],
+    )
+    .env("TECTONIC_CACHE_DIR", &cache_dir)
+    .output()
+    .expect("tectonic failed to start");
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+    drop(server_stderr);
+
+    error_or_panic(&incomplete);
+    success_or_panic(&fetch_all);
+
+    // Now the cache can stand in for the bundle file.
+    let mut server = prep_tectonic(
+        &temppath,
+        &[
+            "-X",
+            "bundle",
+            "serve",
+            "--addr",
+            "127.0.0.1:0",
+            "--cache",
+            &digest,
+        ],
+    )
+    .env("TECTONIC_CACHE_DIR", &cache_dir)
+    .stderr(Stdio::piped())
+    .spawn()
+    .expect("tectonic failed to start");
+
+    let mut server_stderr = BufReader::new(server.stderr.take().unwrap()).lines();
+    let cache_url = server_stderr
+        .by_ref()
+        .map(|l| l.unwrap())
+        .find_map(|l| l.split_once("http://").map(|(_, u)| format!("http://{u}")))
+        .expect("the server didn't report its URL");
+
+    let cache_dir_2 = temppath.join("cache2");
+    let from_cache = prep_tectonic(
+        &temppath,
+        &["-X", "bundle", "fetch", "--bundle", &cache_url, "/src//"],
+    )
+    .env("TECTONIC_CACHE_DIR", &cache_dir_2)
+    .output()
+    .expect("tectonic failed to start");
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+    drop(server_stderr);
+
+    success_or_panic(&from_cache);
+    assert_eq!(
+        fs::read_to_string(
+            cache_dir_2
+                .join("bundles/data")
+                .join(&digest)
+                .join("src/tex/a.tex")
+        )
+        .unwrap(),
+        "hello\n"
+    );
+
+    assert!(partial.starts_with("HTTP/1.1 206 "));
+    assert!(partial.contains(&format!("Content-Range: bytes 0-13/{ttb_len}\r\n")));
+    assert!(partial.ends_with("\r\n\r\ntectonicbundle"));
+
+    assert!(past_end.starts_with("HTTP/1.1 416 "));
+    assert!(past_end.contains(&format!("Content-Range: bytes */{ttb_len}\r\n")));
+
+    assert!(whole.starts_with("HTTP/1.1 200 "));
+    assert!(whole.contains(&format!("Content-Length: {ttb_len}\r\n")));
+
+    assert!(missing.starts_with("HTTP/1.1 404 "));
+
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("2 bundle file(s) are cached"));
+}
+
+/// Test pinning a document to its bundle with `Tectonic.lock`
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_bundle_update() {
+    let (_tempdir, temppath) = setup_v2();
+    let lock_path = temppath.join("Tectonic.lock");
+
+    // Without a lockfile, nothing is checked.
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+
+    let output = run_tectonic(&temppath, &["-X", "bundle", "update"]);
+    success_or_panic(&output);
+    let lock = fs::read_to_string(&lock_path).unwrap();
+    let digest = "0".repeat(64);
+    assert!(lock.contains(&format!("digest = \"{digest}\"")));
+    assert!(!lock.contains("[files]"));
+
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+
+    // A changed bundle stops the build, unless that's explicitly allowed.
+    let other = "1".repeat(64);
+    fs::write(&lock_path, lock.replace(&digest, &other)).unwrap();
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    error_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("has changed since"));
+
+    let output = run_tectonic(&temppath, &["-X", "build", "--allow-lock-mismatch"]);
+    success_or_panic(&output);
+
+    // With file digests, an unrelated change to the bundle is only a warning.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "update", "--files"]);
+    success_or_panic(&output);
+    let lock = fs::read_to_string(&lock_path).unwrap();
+    assert!(lock.contains(&format!("digest = \"{digest}\"")));
+    assert!(lock.contains("[files]"));
+
+    // The files used to generate the format are recorded too, even though
+    // the format was already cached by the earlier builds.
+    assert!(lock.contains("\"tectonic-format-latex.tex\""));
+
+    fs::write(&lock_path, lock.replace(&digest, &other)).unwrap();
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("none of the"));
+
+    // ... but not if one of those files has changed.
+    let (head, files) = lock.split_once("[files]").unwrap();
+    let (before, after) = files.split_once(" = \"").unwrap();
+    let broken = format!(
+        "{}[files]{before} = \"{}{}",
+        head.replace(&digest, &other),
+        "f".repeat(64),
+        &after[64..]
+    );
+    fs::write(&lock_path, broken).unwrap();
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    error_or_panic(&output);
+
+    // Updating keeps recording file digests.
+    let output = run_tectonic(&temppath, &["-X", "bundle", "update"]);
+    success_or_panic(&output);
+    assert_eq!(fs::read_to_string(&lock_path).unwrap(), lock);
+}
+
 /// Test various web bundle overrides for the v2 CLI
 #[cfg(feature = "serialization")]
 #[test]
@@ -705,6 +1414,94 @@ fn v2_build_basic() {
     success_or_panic(&output);
 }
 
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_depfile() {
+    let (_tempdir, temppath) = setup_v2();
+    let output = run_tectonic(
+        &temppath,
+        &[
+            "-X",
+            "build",
+            "--depfile=deps.d",
+            "--dep-manifest=deps.json",
+        ],
+    );
+    success_or_panic(&output);
+
+    let depfile = std::fs::read_to_string(temppath.join("deps.d")).unwrap();
+    let (targets, deps) = depfile.split_once(':').unwrap();
+    assert!(targets.contains("default.pdf"));
+    assert!(deps.contains("_preamble.tex"));
+    assert!(deps.contains("index.tex"));
+
+    let manifest = std::fs::read_to_string(temppath.join("deps.json")).unwrap();
+    assert!(manifest.contains(r#""bundle_digest""#));
+    assert!(manifest.contains(r#""bundle_inputs""#));
+    assert!(manifest.contains("default.pdf"));
+}
+
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_incremental() {
+    let (_tempdir, temppath) = setup_v2();
+
+    // A document that keeps some state in its `.aux` file, so that a fresh
+    // build needs two TeX passes.
+    let write_index = |text: &str| {
+        let mut file = File::create(temppath.join("src").join("index.tex")).unwrap();
+        writeln!(
+            file,
+            "\\newread\\auxin
+            \\openin\\auxin=\\jobname.aux
+            \\ifeof\\auxin\\else\\read\\auxin to\\x\\closein\\auxin\\fi
+            \\newwrite\\auxout
+            \\immediate\\openout\\auxout=\\jobname.aux
+            \\immediate\\write\\auxout{{\\relax}}
+            \\immediate\\closeout\\auxout
+            {text}"
+        )
+        .unwrap();
+    };
+
+    write_index("Hello.");
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Rerunning"));
+
+    // Nothing has changed, so nothing should be done.
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Skipping"));
+    assert!(!stdout.contains("Running TeX"));
+
+    // After a change, the saved `.aux` file means that one pass is enough.
+    write_index("Goodbye.");
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Running TeX"));
+    assert!(!stdout.contains("Rerunning"));
+
+    // Deleting an output means that it needs to be recreated.
+    std::fs::remove_file(temppath.join("build").join("default").join("default.pdf")).unwrap();
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    assert!(temppath
+        .join("build")
+        .join("default")
+        .join("default.pdf")
+        .exists());
+
+    // The cache can be bypassed.
+    let output = run_tectonic(&temppath, &["-X", "build", "--no-build-cache"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Rerunning"));
+}
+
 #[test]
 #[cfg(all(feature = "serialization", not(windows)))] // `echo` may not be available
 fn v2_build_open() {
@@ -794,6 +1591,322 @@ fn v2_build_multiple_outputs() {
     success_or_panic(&output);
 }
 
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_parallel() {
+    let (_tempdir, temppath) = setup_v2();
+
+    {
+        let mut toml_path = temppath.clone();
+        toml_path.push("Tectonic.toml");
+        let mut file = OpenOptions::new().append(true).open(toml_path).unwrap();
+        writeln!(
+            file,
+            "
+            [[output]]
+            name = 'alt'
+            type = 'pdf'
+            tex_format = 'plain'
+            inputs = ['_preamble.tex', 'alt.tex', '_postamble.tex']
+            "
+        )
+        .unwrap();
+    }
+
+    let alt_path = temppath.join("src").join("alt.tex");
+    fs::write(&alt_path, "Hello, alt!\n").unwrap();
+
+    let output = run_tectonic(
+        &temppath,
+        &["-X", "build", "--jobs", "2", "--report", "report.json"],
+    );
+    success_or_panic(&output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("with up to 2 parallel jobs"));
+    assert!(temppath.join("build/default/default.pdf").is_file());
+    assert!(temppath.join("build/alt/alt.pdf").is_file());
+    assert!(temppath.join("report.default.json").is_file());
+    assert!(temppath.join("report.alt.json").is_file());
+
+    // Failures in a worker are relayed, labeled with the output name.
+
+    fs::write(&alt_path, "\\undefinedcontrolsequence\n").unwrap();
+    let output = run_tectonic(&temppath, &["-X", "build", "-j", "2"]);
+    error_or_panic(&output);
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[alt] Undefined control sequence"));
+    assert!(stderr.contains("1 of 2 outputs failed to build"));
+}
+
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_profile_options() {
+    let (_tempdir, temppath) = setup_v2();
+
+    {
+        let mut toml_path = temppath.clone();
+        toml_path.push("Tectonic.toml");
+        let mut file = OpenOptions::new().append(true).open(toml_path).unwrap();
+        writeln!(
+            file,
+            "keep_intermediates = true
+            reruns = 0
+            paper_size = 'a4'"
+        )
+        .unwrap();
+    }
+
+    // A document that writes an `.aux` file, which would usually trigger a
+    // rerun.
+    {
+        let mut file = File::create(temppath.join("src").join("index.tex")).unwrap();
+        writeln!(
+            file,
+            "\\newwrite\\auxout
+            \\immediate\\openout\\auxout=\\jobname.aux
+            \\immediate\\write\\auxout{{\\relax}}
+            \\immediate\\closeout\\auxout
+            Hello."
+        )
+        .unwrap();
+    }
+
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("Rerunning"));
+
+    let outdir = temppath.join("build").join("default");
+    assert!(outdir.join("default.aux").is_file());
+    assert!(outdir.join("default.pdf").is_file());
+}
+
+/// Test PDF/A and PDF/X output, selected by output profile options.
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_pdf_conformance() {
+    let (_tempdir, temppath) = setup_v2();
+    let toml_path = temppath.join("Tectonic.toml");
+    let base_toml = fs::read_to_string(&toml_path).unwrap();
+
+    // Just enough of an ICC profile header to be recognized: a monitor
+    // profile for an RGB colour space.
+    {
+        let mut header = vec![0u8; 128];
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(b"RGB ");
+        header[36..40].copy_from_slice(b"acsp");
+        fs::write(temppath.join("src").join("test.icc"), header).unwrap();
+    }
+
+    fs::write(
+        &toml_path,
+        format!("{base_toml}pdf_conformance = 'pdfa-2b'\noutput_intent_profile = 'test.icc'\n"),
+    )
+    .unwrap();
+
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+
+    // The XMP metadata stream isn't compressed.
+    let pdf = fs::read(temppath.join("build").join("default").join("default.pdf")).unwrap();
+    let pdf = String::from_utf8_lossy(&pdf);
+    assert!(pdf.contains("<pdfaid:part>2</pdfaid:part>"));
+    assert!(pdf.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+
+    // PDF/X needs a printer profile and a title, and this document has
+    // neither.
+    fs::write(
+        &toml_path,
+        format!("{base_toml}pdf_conformance = 'pdfx-4'\noutput_intent_profile = 'test.icc'\n"),
+    )
+    .unwrap();
+
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    error_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not conform to PDF/X-4"));
+    assert!(stderr.contains("PDF/X requires a document title"));
+    assert!(stderr.contains("requires an output (printer) ICC profile"));
+}
+
+/// Test PDF encryption, set up in the output profile and on the command line.
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_pdf_encryption() {
+    let (_tempdir, temppath) = setup_v2();
+    let toml_path = temppath.join("Tectonic.toml");
+    let base_toml = fs::read_to_string(&toml_path).unwrap();
+    let pdf_path = temppath.join("build").join("default").join("default.pdf");
+
+    // A password in the environment doesn't turn on encryption by itself ...
+    let mut command = prep_tectonic(&temppath, &["-X", "build"]);
+    command.env("TECTONIC_PDF_USER_PASSWORD", "user");
+    let output = command.output().expect("tectonic failed to start");
+    success_or_panic(&output);
+
+    let pdf = String::from_utf8_lossy(&fs::read(&pdf_path).unwrap()).into_owned();
+    assert!(!pdf.contains("/Encrypt"));
+
+    // ... but it's used once `--encrypt` does.
+    let mut command = prep_tectonic(&temppath, &["-X", "build", "--encrypt"]);
+    command.env("TECTONIC_PDF_USER_PASSWORD", "user");
+    let output = command.output().expect("tectonic failed to start");
+    success_or_panic(&output);
+
+    let pdf = String::from_utf8_lossy(&fs::read(&pdf_path).unwrap()).into_owned();
+    assert!(pdf.contains("/Encrypt"));
+
+    fs::write(
+        &toml_path,
+        format!(
+            "{base_toml}\n[output.encryption]\nowner_password = 'owner'\npermissions = ['print']\n"
+        ),
+    )
+    .unwrap();
+
+    let output = run_tectonic(&temppath, &["-X", "build", "--user-password", "user"]);
+    success_or_panic(&output);
+
+    // With 128-bit keys, the encryption dictionary uses the AESV2 filter.
+    let pdf = String::from_utf8_lossy(&fs::read(&pdf_path).unwrap()).into_owned();
+    assert!(pdf.contains("/Encrypt"));
+    assert!(pdf.contains("/AESV2"));
+
+    let output = run_tectonic(&temppath, &["-X", "build", "--encrypt-key-bits", "256"]);
+    success_or_panic(&output);
+
+    let pdf = String::from_utf8_lossy(&fs::read(&pdf_path).unwrap()).into_owned();
+    assert!(pdf.starts_with("%PDF-1.7"));
+    assert!(pdf.contains("/AESV3"));
+
+    let output = run_tectonic(&temppath, &["-X", "build", "--permissions", "print,staple"]);
+    error_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown PDF permission `staple`"));
+
+    let output = run_tectonic(&temppath, &["-X", "build", "--encrypt-key-bits", "100"]);
+    error_or_panic(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unsupported PDF encryption key length 100"));
+}
+
+/// Test SyncTeX lookups in a deterministic build, which only work if paths are
+/// recorded relative to the document.
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_synctex_relative_paths() {
+    let (_tempdir, temppath) = setup_v2();
+    let toml_path = temppath.join("Tectonic.toml");
+    let base_toml = fs::read_to_string(&toml_path).unwrap();
+
+    fs::write(
+        &toml_path,
+        format!(
+            "{base_toml}synctex = true\nsynctex_relative_paths = true\ndeterministic_mode = true\n"
+        ),
+    )
+    .unwrap();
+
+    let output = run_tectonic(&temppath, &["-X", "build"]);
+    success_or_panic(&output);
+
+    let output = run_tectonic(&temppath, &["-X", "synctex", "view", "index.tex:1"]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(stdout.starts_with("Page:1 "));
+
+    // Look up a point just inside the region that was found.
+    let field = |name: &str| -> f64 {
+        let text = stdout
+            .split(' ')
+            .find_map(|f| f.strip_prefix(name))
+            .unwrap();
+        text.trim().parse().unwrap()
+    };
+    let position = format!("1:{}:{}", field("x:") + 1.0, field("y:") + 1.0);
+
+    let output = run_tectonic(&temppath, &["-X", "synctex", "edit", &position]);
+    success_or_panic(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.trim().ends_with("index.tex:1"));
+
+    let output = run_tectonic(&temppath, &["-X", "synctex", "view", "missing.tex:1"]);
+    error_or_panic(&output);
+}
+
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_build_workspace() {
+    let (tempdir, first) = setup_v2();
+    let root = tempdir.path().to_owned();
+
+    // Turn the document into one member of a two-document workspace.
+
+    let second = root.join("other");
+    fs::create_dir_all(second.join("src")).unwrap();
+
+    for name in ["_preamble.tex", "index.tex", "_postamble.tex"] {
+        fs::copy(first.join("src").join(name), second.join("src").join(name)).unwrap();
+    }
+
+    let toml = fs::read_to_string(first.join("Tectonic.toml")).unwrap();
+    fs::write(
+        second.join("Tectonic.toml"),
+        toml.replace("name = \"doc\"", "name = \"other\""),
+    )
+    .unwrap();
+
+    fs::write(
+        root.join("Tectonic.toml"),
+        "[workspace]\nmembers = [\"doc\", \"other\"]\n",
+    )
+    .unwrap();
+
+    let first_pdf = first.join("build/default/default.pdf");
+    let second_pdf = second.join("build/default/default.pdf");
+    let clean = || {
+        let _ = fs::remove_dir_all(first.join("build"));
+        let _ = fs::remove_dir_all(second.join("build"));
+    };
+
+    // From the root, everything is built ...
+
+    let output = run_tectonic(&root, &["-X", "build"]);
+    success_or_panic(&output);
+    assert!(first_pdf.is_file());
+    assert!(second_pdf.is_file());
+
+    // ... unless a document is chosen.
+
+    clean();
+    let output = run_tectonic(&root, &["-X", "build", "--doc", "other"]);
+    success_or_panic(&output);
+    assert!(!first_pdf.exists());
+    assert!(second_pdf.is_file());
+
+    let output = run_tectonic(&root, &["-X", "build", "--doc", "nonexistent"]);
+    error_or_panic(&output);
+
+    // Inside a member, only that member is built.
+
+    clean();
+    let output = run_tectonic(&first.join("src"), &["-X", "build"]);
+    success_or_panic(&output);
+    assert!(first_pdf.is_file());
+    assert!(!second_pdf.exists());
+
+    // `dump` needs a single document.
+
+    let output = run_tectonic(&root, &["-X", "dump", "default.log"]);
+    error_or_panic(&output);
+
+    let output = run_tectonic(&root, &["-X", "dump", "--doc", "other", "default.log"]);
+    success_or_panic(&output);
+}
+
 #[test]
 #[cfg(feature = "serialization")]
 fn v2_dump_basic() {
@@ -863,6 +1976,177 @@ fn v2_dump_suffix() {
     assert!(saw_first && saw_second);
 }
 
+#[cfg(feature = "serialization")]
+#[test]
+fn v2_lsp() {
+    let (_tempdir, temppath) = setup_v2();
+    let src = temppath.join("src");
+
+    fs::write(
+        src.join("index.tex"),
+        r"\newwrite\w
+\immediate\openout\w=\jobname.aux
+\immediate\write\w{\string\newlabel{sec:intro}{{1}{1}}}
+\immediate\write\w{\string\citation{knuth}}
+\immediate\write\w{\string\bibdata{refs}}
+\immediate\write\w{\string\bibstyle{plain}}
+\immediate\closeout\w
+Hello \hbox to 1pt{wide wide wide}
+",
+    )
+    .unwrap();
+
+    fs::write(
+        src.join("refs.bib"),
+        "@book{knuth,\n  title = {The TeXbook},\n  year = 1984\n}\n\n@article{broken,\n  title = {x} year\n}\n",
+    )
+    .unwrap();
+
+    fs::copy(TEST_ROOT.join("bibtex/plain.bst"), src.join("plain.bst")).unwrap();
+
+    let index_uri = url::Url::from_file_path(src.join("index.tex"))
+        .unwrap()
+        .to_string();
+    let bib_uri = url::Url::from_file_path(src.join("refs.bib"))
+        .unwrap()
+        .to_string();
+
+    let requests = [
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        serde_json::json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        serde_json::json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": {"uri": index_uri, "text": "See \\ref{sec} and \\cite{kn"},
+        }}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/completion", "params": {
+            "textDocument": {"uri": index_uri}, "position": {"line": 0, "character": 12},
+        }}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "textDocument/completion", "params": {
+            "textDocument": {"uri": index_uri}, "position": {"line": 0, "character": 27},
+        }}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "shutdown"}),
+        serde_json::json!({"jsonrpc": "2.0", "method": "exit"}),
+    ];
+
+    let mut input = String::new();
+
+    for r in &requests {
+        let body = r.to_string();
+        input.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    }
+
+    let output = run_tectonic_with_stdin(&temppath, &["-X", "lsp"], &input);
+    success_or_panic(&output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let messages: Vec<serde_json::Value> = stdout
+        .split("Content-Length: ")
+        .skip(1)
+        .map(|m| serde_json::from_str(m.split_once("\r\n\r\n").unwrap().1).unwrap())
+        .collect();
+
+    let diagnostics_for = |uri: &str| {
+        messages
+            .iter()
+            .find(|m| m["method"] == "textDocument/publishDiagnostics" && m["params"]["uri"] == uri)
+            .unwrap_or_else(|| panic!("no diagnostics published for {uri}"))["params"]
+            ["diagnostics"]
+            .clone()
+    };
+
+    let tex_diags = diagnostics_for(&index_uri);
+    assert_eq!(tex_diags[0]["severity"], 2);
+    assert_eq!(tex_diags[0]["range"]["start"]["line"], 7);
+    assert!(tex_diags[0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Overfull \\hbox"));
+
+    let bib_diags = diagnostics_for(&bib_uri);
+    assert_eq!(bib_diags[0]["severity"], 1);
+    assert_eq!(bib_diags[0]["range"]["start"]["line"], 6);
+
+    let result_for = |id: u64| {
+        messages
+            .iter()
+            .find(|m| m["id"] == id)
+            .unwrap_or_else(|| panic!("no response to request {id}"))["result"]
+            .clone()
+    };
+
+    assert_eq!(result_for(2)[0]["label"], "sec:intro");
+    assert_eq!(result_for(2)[0]["detail"], "1");
+
+    let keys: Vec<_> = result_for(3)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(keys, ["knuth", "broken"]);
+}
+
+#[cfg(all(feature = "serialization", unix))]
+#[test]
+fn v2_serve() {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+
+    let fmt_arg = get_plain_format_arg();
+    let format = fmt_arg.strip_prefix("--format=").unwrap();
+    let tempdir = setup_and_copy_files(&[]);
+    let socket = tempdir.path().join("tectonic.sock");
+
+    let mut command = prep_tectonic(tempdir.path(), &["-X", "serve", socket.to_str().unwrap()]);
+    command.stdout(Stdio::null()).stderr(Stdio::null());
+    let mut child = command.spawn().expect("tectonic failed to start");
+
+    let start = Instant::now();
+    let stream = loop {
+        if let Ok(s) = UnixStream::connect(&socket) {
+            break s;
+        }
+
+        if start.elapsed() > Duration::from_secs(30) {
+            let _ = child.kill();
+            panic!("the compile server never started listening");
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+
+    let mut request = |req: serde_json::Value| {
+        writeln!(writer, "{req}").unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str::<serde_json::Value>(&line).unwrap()
+    };
+
+    // Run two jobs to check that the server survives from one to the next.
+    for name in ["first", "second"] {
+        let resp = request(serde_json::json!({
+            "id": name,
+            "input_buffer": "Hello\\bye",
+            "tex_input_name": format!("{name}.tex"),
+            "outdir": tempdir.path(),
+            "format": format,
+        }));
+
+        assert_eq!(resp["id"], name);
+        assert_eq!(resp["success"], true, "job failed: {resp}");
+        check_file(&tempdir, &format!("{name}.pdf"));
+    }
+
+    let resp = request(serde_json::json!({ "input_buffer": "\\bye" }));
+    assert_eq!(resp["success"], false);
+    assert!(resp["error"].as_str().unwrap().contains("outdir"));
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 /// Checks that shell completions are correctly generated
 #[cfg(feature = "serialization")]
 #[test]
@@ -996,6 +2280,55 @@ fn extra_search_paths() {
     error_or_panic(&output);
 }
 
+/// Test that include paths can be searched recursively or with a `SEARCH` file
+#[test]
+fn extra_search_paths_recursive() {
+    let fmt_arg = get_plain_format_arg();
+    let tempdir = setup_and_copy_files(&["subdirectory/content/1.tex"]);
+
+    // The file isn't at the top level of the search path ...
+    let output = run_tectonic_with_stdin(
+        tempdir.path(),
+        &[&fmt_arg, "-", "-Zsearch-path=subdirectory"],
+        "\\input 1.tex\n\\bye",
+    );
+    error_or_panic(&output);
+
+    // ... but it is in the tree.
+    let output = run_tectonic_with_stdin(
+        tempdir.path(),
+        &[&fmt_arg, "-", "-Zsearch-path=subdirectory//"],
+        "\\input 1.tex\n\\bye",
+    );
+    success_or_panic(&output);
+
+    let output = run_tectonic_with_stdin(
+        tempdir.path(),
+        &[
+            &fmt_arg,
+            "-",
+            "--hide=subdirectory/content/1.tex",
+            "-Zsearch-path=subdirectory//",
+        ],
+        "\\input 1.tex\n\\bye",
+    );
+    error_or_panic(&output);
+
+    // A `SEARCH` file gives the directories to look in.
+    fs::write(
+        tempdir.path().join("subdirectory").join("SEARCH"),
+        "/content//\n",
+    )
+    .unwrap();
+
+    let output = run_tectonic_with_stdin(
+        tempdir.path(),
+        &[&fmt_arg, "-", "-Zsearch-path=subdirectory"],
+        "\\input 1.tex\n\\bye",
+    );
+    success_or_panic(&output);
+}
+
 /// Ensures that watch command succeeds, and when a file is changed while running it rebuilds
 /// periodically
 #[cfg(all(feature = "serialization", not(target_arch = "mips")))]